@@ -0,0 +1,353 @@
+mod output;
+mod remote;
+mod serve;
+
+use std::{
+    io::{Read, Write},
+    net::IpAddr,
+    path::{Path, PathBuf},
+};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use jiff::Timestamp;
+use reqwest::StatusCode;
+use thiserror::Error;
+
+use crate::{
+    conf::{Conf, ConfError, Content, Tls},
+    event_store::{EmailQuery, EventQuery, EventStoreError, SnapshotError},
+    fixtures::FixtureError,
+    mailbox::{self, Maildir},
+};
+
+#[derive(Parser, Debug)]
+#[command(name = "app", version, about = "Local stand-in for the AWS SES v2 API")]
+pub struct Cli {
+    /// Config file [default: ses-local.toml or .yaml in the working directory, if present]
+    #[arg(long, global = true, env = "SES_LOCAL_CONFIG")]
+    pub config: Option<PathBuf>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Start the server (the default when no command is given)
+    Serve(ServeArgs),
+    /// Stream newly captured emails, or events, to the terminal
+    Tail(TailArgs),
+    /// List captured emails
+    List(ListArgs),
+    /// Show a captured email
+    Show(ShowArgs),
+    /// Write every stored event to a JSON archive, or the emails to an mbox file or Maildir
+    Export(ExportArgs),
+    /// Load events from an archive written by `export`
+    Import(ImportArgs),
+    /// Inspect the configuration
+    #[command(subcommand)]
+    Config(ConfigCommand),
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Validate the configuration and print the effective values as TOML
+    Check(ServeArgs),
+}
+
+#[derive(Args, Debug, Default)]
+pub struct ServeArgs {
+    /// Port to listen on, overrides server.port
+    #[arg(long, short)]
+    pub port: Option<u16>,
+    /// Port to serve email bodies from, on a separate origin to the UI, overrides
+    /// server.content.port
+    #[arg(long)]
+    pub content_port: Option<u16>,
+    /// Address to bind to, overrides server.bind
+    #[arg(long)]
+    pub bind: Option<IpAddr>,
+    /// PEM certificate chain to serve HTTPS with, overrides server.tls.cert
+    #[arg(long, requires = "tls_key")]
+    pub tls_cert: Option<PathBuf>,
+    /// PEM private key for --tls-cert, overrides server.tls.key
+    #[arg(long, requires = "tls_cert")]
+    pub tls_key: Option<PathBuf>,
+    /// Directory to load events from at startup and save them to on exit, overrides storage.path
+    #[arg(long)]
+    pub data_dir: Option<PathBuf>,
+    /// Directory of templates and emails to seed at startup, overrides fixtures.path
+    #[arg(long)]
+    pub fixtures: Option<PathBuf>,
+    /// Directory of compiled UI assets, overrides server.assets.path
+    #[arg(long)]
+    pub assets_path: Option<PathBuf>,
+    /// Snapshot (from POST /admin/snapshot) to start from, replacing any data dir contents
+    #[arg(long)]
+    pub restore: Option<PathBuf>,
+    /// Maildir to also write each newly captured email to, overrides sink.maildir
+    #[arg(long)]
+    pub maildir_sink: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct RemoteArgs {
+    /// Base URL of the running SES.local
+    #[arg(long, env = "SES_LOCAL_URL", default_value = "http://localhost:8080")]
+    pub url: String,
+}
+
+#[derive(Args, Debug)]
+pub struct EmailFilter {
+    /// Only emails sent to this address (To, Cc or Bcc)
+    #[arg(long)]
+    pub to: Option<String>,
+    /// Only emails whose subject contains this text
+    #[arg(long)]
+    pub subject: Option<String>,
+    /// Only emails captured at or after this time (RFC 3339)
+    #[arg(long)]
+    pub since: Option<Timestamp>,
+}
+
+#[derive(Args, Debug)]
+pub struct TailArgs {
+    #[command(flatten)]
+    pub remote: RemoteArgs,
+    #[command(flatten)]
+    pub filter: EmailFilter,
+    /// Stream every event instead of just emails
+    #[arg(long, conflicts_with_all = ["to", "subject"])]
+    pub events: bool,
+    /// With --events, only events of this type (e.g. SendEmail)
+    #[arg(long = "type", requires = "events")]
+    pub event_type: Option<String>,
+    /// Print one JSON document per line
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ListArgs {
+    #[command(flatten)]
+    pub remote: RemoteArgs,
+    #[command(flatten)]
+    pub filter: EmailFilter,
+    /// Maximum number of emails to list
+    #[arg(long, short = 'n')]
+    pub limit: Option<usize>,
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ShowArgs {
+    #[command(flatten)]
+    pub remote: RemoteArgs,
+    pub message_id: String,
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ExportArgs {
+    #[command(flatten)]
+    pub remote: RemoteArgs,
+    #[arg(long, value_enum, default_value_t = ExportFormat::Json)]
+    pub format: ExportFormat,
+    /// File (or with --format maildir, directory) to write to, stdout if omitted
+    #[arg(long, short, required_if_eq("format", "maildir"))]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    /// Every event, re-importable with `import`
+    Json,
+    /// The emails, oldest first, in one mboxrd file
+    Mbox,
+    /// The emails, one file each, in a Maildir
+    Maildir,
+}
+
+#[derive(Args, Debug)]
+pub struct ImportArgs {
+    #[command(flatten)]
+    pub remote: RemoteArgs,
+    /// Archive to read, stdin if omitted
+    pub input: Option<PathBuf>,
+}
+
+#[derive(Error, Debug)]
+pub enum CliError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("request failed: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("SES.local responded {status}: {body}")]
+    Status { status: StatusCode, body: String },
+    #[error("no email with message id {0}")]
+    NotFound(String),
+    #[error(transparent)]
+    Conf(#[from] ConfError),
+    #[error(transparent)]
+    Fixture(#[from] FixtureError),
+    #[error(transparent)]
+    Snapshot(#[from] SnapshotError),
+    #[error(transparent)]
+    Store(#[from] EventStoreError),
+}
+
+impl ServeArgs {
+    /// Loads the configuration with these flags applied on top.
+    fn conf(&self, file: Option<&Path>) -> Result<Conf, ConfError> {
+        let mut conf = Conf::load(file)?;
+        if let Some(port) = self.port {
+            conf.server.port = port;
+        }
+        if let Some(port) = self.content_port {
+            let url = conf.server.content.take().and_then(|content| content.url);
+            conf.server.content = Some(Content { port, url });
+        }
+        if let Some(bind) = self.bind {
+            conf.server.bind = bind;
+        }
+        if let (Some(cert), Some(key)) = (&self.tls_cert, &self.tls_key) {
+            conf.server.tls = Some(Tls {
+                cert: cert.clone(),
+                key: key.clone(),
+            });
+        }
+        if let Some(path) = &self.assets_path {
+            conf.server.assets.path = path.clone();
+        }
+        if let Some(path) = &self.data_dir {
+            conf.storage.path = Some(path.clone());
+        }
+        if let Some(path) = &self.fixtures {
+            conf.fixtures.path = Some(path.clone());
+        }
+        if let Some(path) = &self.maildir_sink {
+            conf.sink.maildir = Some(path.clone());
+        }
+        conf.validate()?;
+        Ok(conf)
+    }
+}
+
+impl EmailFilter {
+    fn query(&self, limit: Option<usize>) -> EmailQuery {
+        EmailQuery {
+            to: self.to.clone(),
+            subject: self.subject.clone(),
+            since: self.since,
+            limit,
+            ..Default::default()
+        }
+    }
+}
+
+pub async fn run(cli: Cli) -> Result<(), CliError> {
+    match cli.command {
+        None => serve::run(ServeArgs::default().conf(cli.config.as_deref())?, None).await,
+        Some(Command::Serve(args)) => {
+            serve::run(args.conf(cli.config.as_deref())?, args.restore).await
+        }
+        Some(Command::Config(ConfigCommand::Check(args))) => {
+            let conf = args.conf(cli.config.as_deref())?;
+            print!("{}", toml::to_string(&conf).map_err(std::io::Error::other)?);
+            Ok(())
+        }
+        Some(Command::Tail(args)) => {
+            let remote = remote::Remote::new(&args.remote.url);
+            if args.events {
+                let query = EventQuery {
+                    event_type: args.event_type,
+                    since: args.filter.since,
+                    ..Default::default()
+                };
+                remote.tail_events(&query, args.json).await
+            } else {
+                remote
+                    .tail_emails(&args.filter.query(None), args.json)
+                    .await
+            }
+        }
+        Some(Command::List(args)) => {
+            let remote = remote::Remote::new(&args.remote.url);
+            let emails = remote.list(&args.filter.query(args.limit)).await?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&emails)?);
+            } else {
+                print!("{}", output::table(&emails));
+            }
+            Ok(())
+        }
+        Some(Command::Show(args)) => {
+            let remote = remote::Remote::new(&args.remote.url);
+            let email = remote
+                .show(&args.message_id)
+                .await?
+                .ok_or(CliError::NotFound(args.message_id))?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&email)?);
+            } else {
+                print!("{}", output::details(&email));
+            }
+            Ok(())
+        }
+        Some(Command::Export(args)) => {
+            let remote = remote::Remote::new(&args.remote.url);
+            let mut events = remote.export().await?;
+            let archive = match args.format {
+                ExportFormat::Json => {
+                    let mut json = serde_json::to_vec_pretty(&events)?;
+                    json.push(b'\n');
+                    json
+                }
+                ExportFormat::Mbox => {
+                    events.reverse();
+                    let mut mbox = vec![];
+                    mailbox::write_mbox(&mut mbox, &events)?;
+                    mbox
+                }
+                ExportFormat::Maildir => {
+                    let maildir = Maildir::create(args.output.unwrap_or_default())?;
+                    let mut delivered = 0;
+                    for event in &events {
+                        if maildir.deliver(event)?.is_some() {
+                            delivered += 1;
+                        }
+                    }
+                    eprintln!(
+                        "exported {} emails to {}",
+                        delivered,
+                        maildir.path().display()
+                    );
+                    return Ok(());
+                }
+            };
+            match args.output {
+                Some(path) => std::fs::write(path, archive)?,
+                None => std::io::stdout().write_all(&archive)?,
+            }
+            Ok(())
+        }
+        Some(Command::Import(args)) => {
+            let remote = remote::Remote::new(&args.remote.url);
+            let archive = match args.input {
+                Some(path) => std::fs::read(path)?,
+                None => {
+                    let mut archive = vec![];
+                    std::io::stdin().read_to_end(&mut archive)?;
+                    archive
+                }
+            };
+            let imported = remote.import(serde_json::from_slice(&archive)?).await?;
+            eprintln!("imported {} events", imported);
+            Ok(())
+        }
+    }
+}
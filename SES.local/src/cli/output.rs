@@ -0,0 +1,150 @@
+use std::fmt::Write;
+
+use crate::event_store::{send_email::SendEmail, Event};
+
+const HEADERS: [&str; 4] = ["MESSAGE ID", "FROM", "TO", "SUBJECT"];
+
+fn columns(email: &SendEmail) -> [String; 4] {
+    [
+        email.response.message_id.clone().unwrap_or_default(),
+        email.request.get_from().unwrap_or_default().to_string(),
+        email
+            .request
+            .get_to()
+            .and_then(|d| d.to_addresses.as_ref())
+            .map(|to| to.join(", "))
+            .unwrap_or_default(),
+        email
+            .request
+            .get_rendered_subject()
+            .unwrap_or_default()
+            .to_string(),
+    ]
+}
+
+/// A single tab separated line, as printed by `tail`.
+pub fn row(email: &SendEmail) -> String {
+    columns(email).join("\t")
+}
+
+pub fn event_row(event: &Event) -> String {
+    [
+        event.timestamp.as_str(),
+        &event.get_name(),
+        event.get_message_id().unwrap_or_default(),
+    ]
+    .join("\t")
+}
+
+/// Emails as a table with columns padded to their widest value.
+pub fn table(emails: &[SendEmail]) -> String {
+    let rows = emails.iter().map(columns).collect::<Vec<_>>();
+    let mut widths = HEADERS.map(str::len);
+    for row in &rows {
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+    let mut table = String::new();
+    let header = HEADERS.map(String::from);
+    for row in std::iter::once(&header).chain(&rows) {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(value, width)| format!("{:width$}", value, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+        _ = writeln!(table, "{}", line.trim_end());
+    }
+    table
+}
+
+/// Headers followed by the text body, or the HTML body when there is no text part.
+pub fn details(email: &SendEmail) -> String {
+    let mut details = String::new();
+    let request = &email.request;
+    let destination = request.get_to();
+    let addresses = |list: Option<&Vec<String>>| list.map(|a| a.join(", "));
+    let headers = [
+        ("Message-ID", email.response.message_id.clone()),
+        ("From", request.get_from().map(String::from)),
+        (
+            "To",
+            addresses(destination.and_then(|d| d.to_addresses.as_ref())),
+        ),
+        (
+            "Cc",
+            addresses(destination.and_then(|d| d.cc_addresses.as_ref())),
+        ),
+        (
+            "Bcc",
+            addresses(destination.and_then(|d| d.bcc_addresses.as_ref())),
+        ),
+        ("Subject", request.get_rendered_subject().map(String::from)),
+    ];
+    for (name, value) in headers {
+        if let Some(value) = value {
+            _ = writeln!(details, "{}: {}", name, value);
+        }
+    }
+    if let Some(body) = request.get_text().or_else(|| request.get_html()) {
+        _ = writeln!(details, "\n{}", body.trim_end());
+    }
+    details
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ses_serde::operations::send_email::SendEmailInput;
+
+    fn create_email(to: &str, subject: &str) -> SendEmail {
+        let input: SendEmailInput = serde_json::from_value(serde_json::json!({
+            "FromEmailAddress": "sender@example.com",
+            "Destination": { "ToAddresses": [to] },
+            "Content": {
+                "Simple": {
+                    "Subject": { "Data": subject },
+                    "Body": { "Text": { "Data": "Hello\n" } }
+                }
+            }
+        }))
+        .unwrap();
+        SendEmail::new(input)
+    }
+
+    #[test]
+    fn table_pads_columns() {
+        let mut a = create_email("a@example.com", "Welcome");
+        a.response.message_id = Some(String::from("1"));
+        let mut b = create_email("someone@example.com", "Reset");
+        b.response.message_id = Some(String::from("2"));
+
+        assert_eq!(
+            table(&[a, b]),
+            "\
+MESSAGE ID  FROM                TO                   SUBJECT
+1           sender@example.com  a@example.com        Welcome
+2           sender@example.com  someone@example.com  Reset
+"
+        );
+    }
+
+    #[test]
+    fn details_headers_and_body() {
+        let mut email = create_email("a@example.com", "Welcome");
+        email.response.message_id = Some(String::from("1"));
+
+        assert_eq!(
+            details(&email),
+            "\
+Message-ID: 1
+From: sender@example.com
+To: a@example.com
+Subject: Welcome
+
+Hello
+"
+        );
+    }
+}
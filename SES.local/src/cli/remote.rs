@@ -0,0 +1,194 @@
+use eventsource_stream::Eventsource;
+use futures::StreamExt;
+use reqwest::{header::ACCEPT, RequestBuilder, Response, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::{output, CliError};
+use crate::event_store::{send_email::SendEmail, EmailQuery, Event, EventQuery, Page};
+
+/// The local JSON API of a running SES.local, as used by the CLI.
+pub struct Remote {
+    http: reqwest::Client,
+    url: String,
+}
+
+#[derive(Deserialize)]
+struct Imported {
+    imported: usize,
+}
+
+impl Remote {
+    pub fn new(url: &str) -> Self {
+        Remote {
+            http: reqwest::Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub async fn list(&self, query: &EmailQuery) -> Result<Vec<SendEmail>, CliError> {
+        let mut query = query.clone();
+        let mut emails = vec![];
+        loop {
+            let page: Page<SendEmail> = json(self.get("/emails").query(&query)).await?;
+            emails.extend(page.items);
+            match page.next_cursor {
+                Some(cursor) if query.limit.is_none_or(|limit| emails.len() < limit) => {
+                    query.cursor = Some(cursor)
+                }
+                _ => return Ok(emails),
+            }
+        }
+    }
+
+    pub async fn show(&self, message_id: &str) -> Result<Option<SendEmail>, CliError> {
+        let response = self.get(&format!("/emails/{}", message_id)).send().await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            _ => Ok(Some(check(response).await?.json().await?)),
+        }
+    }
+
+    pub async fn export(&self) -> Result<Vec<Event>, CliError> {
+        let mut query = EventQuery::default();
+        let mut events = vec![];
+        loop {
+            let page: Page<Event> = json(self.get("/events").query(&query)).await?;
+            events.extend(page.items);
+            match page.next_cursor {
+                Some(cursor) => query.cursor = Some(cursor),
+                None => return Ok(events),
+            }
+        }
+    }
+
+    pub async fn import(&self, events: Vec<Event>) -> Result<usize, CliError> {
+        let request = self.http.post(self.url("/events")).json(&events);
+        let Imported { imported } = json(request).await?;
+        Ok(imported)
+    }
+
+    pub async fn tail_emails(&self, query: &EmailQuery, as_json: bool) -> Result<(), CliError> {
+        self.tail("/emails/stream", "email", query, |data| {
+            let email: SendEmail = serde_json::from_str(data)?;
+            Ok(match as_json {
+                true => serde_json::to_string(&email)?,
+                false => output::row(&email),
+            })
+        })
+        .await
+    }
+
+    pub async fn tail_events(&self, query: &EventQuery, as_json: bool) -> Result<(), CliError> {
+        self.tail("/events/stream", "event", query, |data| {
+            let event: Event = serde_json::from_str(data)?;
+            Ok(match as_json {
+                true => serde_json::to_string(&event)?,
+                false => output::event_row(&event),
+            })
+        })
+        .await
+    }
+
+    // Prints the stream's `name` events, leaving out updates, deletes and clears.
+    async fn tail(
+        &self,
+        path: &str,
+        name: &str,
+        query: &impl Serialize,
+        format: impl Fn(&str) -> Result<String, CliError>,
+    ) -> Result<(), CliError> {
+        let response = check(
+            self.http
+                .get(self.url(path))
+                .query(query)
+                .header(ACCEPT, "text/event-stream")
+                .send()
+                .await?,
+        )
+        .await?;
+        let mut stream = response.bytes_stream().eventsource();
+        while let Some(event) = stream.next().await {
+            match event {
+                Ok(event) if event.event == name => println!("{}", format(&event.data)?),
+                Ok(_) => {}
+                Err(err) => return Err(std::io::Error::other(err.to_string()).into()),
+            }
+        }
+        Ok(())
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.url, path)
+    }
+
+    fn get(&self, path: &str) -> RequestBuilder {
+        self.http
+            .get(self.url(path))
+            .header(ACCEPT, "application/json")
+    }
+}
+
+async fn json<T: DeserializeOwned>(request: RequestBuilder) -> Result<T, CliError> {
+    Ok(check(request.send().await?).await?.json().await?)
+}
+
+async fn check(response: Response) -> Result<Response, CliError> {
+    let status = response.status();
+    if status.is_success() {
+        Ok(response)
+    } else {
+        Err(CliError::Status {
+            status,
+            body: response.text().await.unwrap_or_default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        event_store::{send_email::SendEmail, EventContent},
+        Server,
+    };
+    use ses_serde::operations::send_email::SendEmailInput;
+
+    fn create_event(to: &str) -> Event {
+        let input: SendEmailInput = serde_json::from_value(serde_json::json!({
+            "Destination": { "ToAddresses": [to] },
+            "Content": { "Simple": { "Subject": { "Data": "Hi" } } }
+        }))
+        .unwrap();
+        Event::new(EventContent::SendEmail(SendEmail::new(input)))
+    }
+
+    #[tokio::test]
+    async fn export_import() {
+        let source = Server::builder().start().await.unwrap();
+        let target = Server::builder().start().await.unwrap();
+        {
+            let mut esw = source.event_store().write().await;
+            for to in ["a@example.com", "b@example.com", "c@example.com"] {
+                _ = esw.push(create_event(to)).await;
+            }
+        }
+
+        let events = Remote::new(&source.endpoint_url()).export().await.unwrap();
+        assert_eq!(events.len(), 3);
+
+        let remote = Remote::new(&target.endpoint_url());
+        assert_eq!(remote.import(events.clone()).await.unwrap(), 3);
+        assert_eq!(remote.import(events).await.unwrap(), 0);
+
+        let query = EmailQuery {
+            limit: Some(2),
+            ..Default::default()
+        };
+        let emails = remote.list(&query).await.unwrap();
+        assert_eq!(emails.len(), 2);
+        assert_eq!(
+            emails[0].request.get_to().unwrap().to_addresses,
+            Some(vec![String::from("c@example.com")])
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use tokio::sync::RwLock;
+
+use super::CliError;
+use crate::{
+    conf::Conf,
+    event_store::{EventStore, Snapshot},
+    fixtures,
+    mailbox::Maildir,
+    AppEventStore, Server,
+};
+
+const EVENTS_FILE: &str = "events.json";
+
+pub async fn run(conf: Conf, restore: Option<PathBuf>) -> Result<(), CliError> {
+    let data_dir = conf.storage.path;
+    let event_store = Arc::new(RwLock::new(
+        EventStore::new().with_retention(conf.retention),
+    ));
+    match (&restore, &data_dir) {
+        (Some(snapshot), _) => load(&event_store, snapshot).await?,
+        (None, Some(data_dir)) => load_data_dir(&event_store, data_dir).await?,
+        _ => {}
+    }
+    event_store
+        .write()
+        .await
+        .add_templates(conf.templates.into_iter().map(|(n, t)| (n, t.into())));
+    if let Some(fixtures) = &conf.fixtures.path {
+        seed(&event_store, fixtures).await?;
+    }
+    if let Some(path) = &conf.sink.maildir {
+        Maildir::create(path)?.sink(&event_store).await;
+        tracing::info!("writing captured emails to {}", path.display());
+    }
+    let mut builder = Server::builder()
+        .ip(conf.server.bind)
+        .port(conf.server.port)
+        .assets_path(conf.server.assets.path)
+        .identities(conf.identities)
+        .rules(conf.rules)
+        .event_store(event_store);
+    if let Some(tls) = conf.server.tls {
+        builder = builder.tls(tls.cert, tls.key);
+    }
+    if let Some(content) = conf.server.content {
+        builder = builder.content_port(content.port);
+        if let Some(url) = content.url {
+            builder = builder.content_url(url);
+        }
+    }
+    let server = builder.start().await?;
+    tracing::info!("SES.local available at {}", server.endpoint_url());
+    if let Some(addr) = server.content_addr() {
+        tracing::info!("serving email content from port {}", addr.port());
+    }
+
+    shutdown_signal().await?;
+    // Stop taking requests first, so nothing acknowledged after the save is lost.
+    let event_store = server.event_store().clone();
+    server.shutdown().await;
+    if let Some(data_dir) = &data_dir {
+        if let Err(err) = save(&event_store, data_dir).await {
+            tracing::error!("saving to {} failed: {}", data_dir.display(), err);
+        }
+    }
+    Ok(())
+}
+
+// Ctrl-C in a terminal, or SIGTERM from `docker stop` and process managers.
+async fn shutdown_signal() -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut terminate = signal(SignalKind::terminate())?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result,
+            _ = terminate.recv() => Ok(()),
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await
+}
+
+// Data dirs saved before snapshots existed hold a bare array of events, which
+// `Snapshot::from_slice` upgrades.
+async fn load(event_store: &AppEventStore, path: &Path) -> Result<(), CliError> {
+    let snapshot = Snapshot::from_slice(&std::fs::read(path)?)?;
+    let restored = event_store.write().await.restore(snapshot).await?;
+    tracing::info!("restored {} events from {}", restored, path.display());
+    Ok(())
+}
+
+// Retention stays as configured, as for any restore, so edits to it take effect on the next
+// start.
+async fn load_data_dir(event_store: &AppEventStore, data_dir: &Path) -> Result<(), CliError> {
+    let path = data_dir.join(EVENTS_FILE);
+    if !path.exists() {
+        return Ok(());
+    }
+    let snapshot = Snapshot::from_slice(&std::fs::read(&path)?)?;
+    let restored = event_store.write().await.restore(snapshot).await?;
+    tracing::info!("restored {} events from {}", restored, path.display());
+    Ok(())
+}
+
+// Fixtures keep their ids, so those already restored from the data dir aren't added twice.
+async fn seed(event_store: &AppEventStore, dir: &Path) -> Result<(), CliError> {
+    let fixtures = fixtures::load(dir)?;
+    let mut esw = event_store.write().await;
+    let templates = fixtures.templates.len();
+    esw.add_templates(fixtures.templates);
+    let emails = fixtures
+        .emails
+        .into_iter()
+        .map(|event| esw.with_template(event))
+        .collect();
+    let seeded = esw.import(emails).await?;
+    tracing::info!(
+        "seeded {} templates and {} emails from {}",
+        templates,
+        seeded,
+        dir.display()
+    );
+    Ok(())
+}
+
+async fn save(event_store: &AppEventStore, data_dir: &Path) -> Result<(), CliError> {
+    std::fs::create_dir_all(data_dir)?;
+    let path = data_dir.join(EVENTS_FILE);
+    let esr = event_store.read().await;
+    std::fs::write(&path, serde_json::to_vec(&esr.snapshot())?)?;
+    tracing::info!("saved {} events to {}", esr.get_all().len(), path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_store::{Event, Retention};
+
+    #[tokio::test]
+    async fn data_dir_keeps_configured_retention() {
+        let data_dir = std::env::temp_dir().join(format!("ses-local-serve-{}", std::process::id()));
+        let saved = Retention {
+            max: Some(1),
+            age: None,
+        };
+        let configured = Retention {
+            max: Some(5),
+            age: None,
+        };
+        let mut es = EventStore::new().with_retention(saved);
+        es.push(Event::empty()).await.unwrap();
+        std::fs::create_dir_all(&data_dir).unwrap();
+        std::fs::write(
+            data_dir.join(EVENTS_FILE),
+            serde_json::to_vec(&es.snapshot()).unwrap(),
+        )
+        .unwrap();
+
+        let event_store = Arc::new(RwLock::new(
+            EventStore::new().with_retention(configured.clone()),
+        ));
+        load_data_dir(&event_store, &data_dir).await.unwrap();
+        let snapshot = event_store.read().await.snapshot();
+        assert_eq!(snapshot.retention, configured);
+        assert_eq!(snapshot.events, es.snapshot().events);
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+}
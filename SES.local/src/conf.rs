@@ -1,44 +1,437 @@
-use std::sync::OnceLock;
+use std::{
+    collections::{BTreeMap, HashMap},
+    net::{IpAddr, Ipv4Addr},
+    path::{Path, PathBuf},
+};
 
-use config::Config;
-use serde::Deserialize;
+use axum::http::Uri;
+use config::{Config, Environment, File};
+use serde::{Deserialize, Serialize};
+use ses_serde::types::EmailTemplateContent;
+use thiserror::Error;
 
-#[derive(Debug, Deserialize)]
-#[allow(unused)]
+use crate::event_store::Retention;
+
+const ENV_PREFIX: &str = "APP";
+// Looked up in the working directory, with any extension the config crate understands.
+const DEFAULT_FILE: &str = "ses-local";
+const DEFAULT_PORT: u16 = 8080;
+
+/// Effective configuration: defaults, overlaid by the config file, then `APP_*` environment
+/// variables (e.g. `APP_SERVER_PORT`), then command-line flags. Templates, identities and rules
+/// are only read from the file.
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
 pub struct Conf {
     pub server: Server,
+    #[serde(default)]
+    pub storage: Storage,
+    #[serde(default)]
+    pub retention: Retention,
+    #[serde(default)]
+    pub fixtures: Fixtures,
+    #[serde(default)]
+    pub sink: Sink,
+    /// Email templates by name, as `CreateEmailTemplate` would store them. Fixtures with the
+    /// same name replace them.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub templates: BTreeMap<String, Template>,
+    /// Verified senders: addresses, or domains covering every address at them. When set,
+    /// `SendEmail` from anyone else is rejected as SES does for unverified identities.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub identities: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<Rule>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Template {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+impl From<Template> for EmailTemplateContent {
+    fn from(template: Template) -> Self {
+        EmailTemplateContent {
+            subject: template.subject,
+            text: template.text,
+            html: template.html,
+        }
+    }
 }
 
-#[derive(Debug, Deserialize)]
-#[allow(unused)]
+/// What `SendEmail` does for a recipient, applied by the first rule that matches any of them.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    /// An address, or a domain such as `example.org` covering every address at it.
+    pub recipient: String,
+    pub action: Action,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    /// Fail the call with `MessageRejected`, to exercise error handling.
+    Reject,
+    /// Accept the call but don't capture the email.
+    Drop,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
 pub struct Server {
+    pub bind: IpAddr,
     pub port: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<Content>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<Tls>,
     pub assets: Assets,
 }
 
-#[derive(Debug, Deserialize)]
-#[allow(unused)]
+/// Email bodies served from their own port, isolating them from the UI's origin.
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Content {
+    pub port: u16,
+    /// Public URL `port` is reached at, such as `https://mail.example.test` behind a proxy.
+    /// Defaults to the UI's hostname on `port`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+/// Serves HTTPS instead of HTTP, on the content port too.
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Tls {
+    /// PEM file with the certificate chain, leaf first.
+    pub cert: PathBuf,
+    /// PEM file with the certificate's private key.
+    pub key: PathBuf,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
 pub struct Assets {
-    pub path: String,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Storage {
+    /// Directory events are loaded from at startup and saved to on exit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Fixtures {
+    /// Directory of templates and emails to seed the event store with at startup.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Sink {
+    /// Maildir every newly captured email is also written to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maildir: Option<PathBuf>,
+}
+
+#[derive(Error, Debug)]
+pub enum ConfError {
+    #[error("config file {0} does not exist")]
+    Missing(PathBuf),
+    #[error("invalid configuration: {0}")]
+    Load(#[from] config::ConfigError),
+    #[error("invalid configuration:{}", .0.iter().map(|e| format!("\n  - {}", e)).collect::<String>())]
+    Invalid(Vec<String>),
 }
 
 impl Conf {
-    pub fn get<'a>() -> &'a Self {
-        static CONFIG: OnceLock<Conf> = OnceLock::new();
-        CONFIG.get_or_init(|| {
-            // parse and create Config
-            Config::builder()
-                .add_source(
-                    config::Environment::with_prefix("APP")
-                        .try_parsing(true)
-                        .separator("_"),
-                )
-                .set_default("server.assets.path", "assets")
-                .unwrap()
-                .build()
-                .unwrap()
-                .try_deserialize()
-                .unwrap()
-        })
+    /// Loads `file` if given, otherwise `ses-local.{toml,yaml,...}` if there is one.
+    pub fn load(file: Option<&Path>) -> Result<Self, ConfError> {
+        Self::load_from(file, std::env::vars())
+    }
+
+    fn load_from(
+        file: Option<&Path>,
+        env: impl Iterator<Item = (String, String)>,
+    ) -> Result<Self, ConfError> {
+        let file = match file {
+            Some(path) if !path.exists() => return Err(ConfError::Missing(path.to_path_buf())),
+            Some(path) => File::from(path),
+            None => File::with_name(DEFAULT_FILE).required(false),
+        };
+        let conf: Conf = Config::builder()
+            .set_default("server.bind", Ipv4Addr::UNSPECIFIED.to_string())?
+            .set_default("server.port", DEFAULT_PORT)?
+            .set_default("server.assets.path", "assets")?
+            .add_source(file)
+            .add_source(
+                Environment::with_prefix(ENV_PREFIX)
+                    .try_parsing(true)
+                    .separator("_")
+                    .source(Some(section_vars(env))),
+            )
+            .build()?
+            .try_deserialize()?;
+        conf.validate()?;
+        Ok(conf)
+    }
+
+    pub fn validate(&self) -> Result<(), ConfError> {
+        let mut errors = vec![];
+        for (name, template) in &self.templates {
+            if template == &Template::default() {
+                errors.push(format!("templates.{} needs a subject, html or text", name));
+            }
+        }
+        for identity in &self.identities {
+            if identity.is_empty() || identity.contains(char::is_whitespace) {
+                errors.push(format!(
+                    "identities entry {:?} must be an address or domain",
+                    identity
+                ));
+            }
+        }
+        for rule in &self.rules {
+            if rule.recipient.is_empty() || rule.recipient.contains(char::is_whitespace) {
+                errors.push(format!(
+                    "rules recipient {:?} must be an address or domain",
+                    rule.recipient
+                ));
+            }
+        }
+        if self.retention.max == Some(0) {
+            errors.push(String::from("retention.max must be at least 1"));
+        }
+        if self.retention.age.is_some_and(|age| !age.is_positive()) {
+            errors.push(String::from("retention.age must be positive"));
+        }
+        if let Some(url) = self.server.content.as_ref().and_then(|c| c.url.as_ref()) {
+            let origin = url.parse::<Uri>().ok().filter(|uri| {
+                matches!(uri.scheme_str(), Some("http" | "https")) && uri.host().is_some()
+            });
+            if origin.is_none() {
+                errors.push(format!(
+                    "server.content.url {} must be an http or https URL",
+                    url
+                ));
+            }
+        }
+        if let Some(tls) = &self.server.tls {
+            for (key, path) in [("cert", &tls.cert), ("key", &tls.key)] {
+                if !path.is_file() {
+                    errors.push(format!(
+                        "server.tls.{} {} is not a file",
+                        key,
+                        path.display()
+                    ));
+                }
+            }
+        }
+        if let Some(path) = &self.storage.path {
+            if path.exists() && !path.is_dir() {
+                errors.push(format!(
+                    "storage.path {} exists but is not a directory",
+                    path.display()
+                ));
+            }
+        }
+        if let Some(path) = &self.sink.maildir {
+            if path.exists() && !path.is_dir() {
+                errors.push(format!(
+                    "sink.maildir {} exists but is not a directory",
+                    path.display()
+                ));
+            }
+        }
+        if let Some(path) = &self.fixtures.path {
+            if !path.is_dir() {
+                errors.push(format!(
+                    "fixtures.path {} is not a directory",
+                    path.display()
+                ));
+            }
+        }
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(ConfError::Invalid(errors)),
+        }
+    }
+}
+
+// Only `APP_<SECTION>_*` variables are config, so unrelated ones such as `APP_ENV` don't trip
+// the unknown field check while a misspelt `APP_SERVER_PROT` still does.
+fn section_vars(env: impl Iterator<Item = (String, String)>) -> HashMap<String, String> {
+    let sections = ["SERVER", "STORAGE", "RETENTION", "FIXTURES", "SINK"]
+        .map(|s| format!("{}_{}_", ENV_PREFIX, s));
+    env.filter(|(key, _)| {
+        sections
+            .iter()
+            .any(|section| key.to_uppercase().starts_with(section))
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use jiff::SignedDuration;
+
+    use super::*;
+
+    fn env(vars: &[(&str, &str)]) -> impl Iterator<Item = (String, String)> {
+        vars.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    fn write(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{}-{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn defaults() {
+        let conf = Conf::load_from(None, env(&[("APP_ENV", "dev")])).unwrap();
+        assert_eq!(conf.server.bind, IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        assert_eq!(conf.server.port, DEFAULT_PORT);
+        assert_eq!(conf.server.assets.path, PathBuf::from("assets"));
+        assert_eq!(conf.retention, Retention::default());
+    }
+
+    #[test]
+    fn file_then_env() {
+        let path = write(
+            "ses-local.toml",
+            r#"
+            [server]
+            port = 9000
+            bind = "127.0.0.1"
+
+            [retention]
+            max = 100
+            age = "168h"
+            "#,
+        );
+        let conf = Conf::load_from(
+            Some(&path),
+            env(&[
+                ("APP_SERVER_PORT", "9001"),
+                ("APP_SERVER_CONTENT_PORT", "9002"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(conf.server.port, 9001);
+        assert_eq!(
+            conf.server.content,
+            Some(Content {
+                port: 9002,
+                url: None
+            })
+        );
+        assert_eq!(conf.server.bind, IpAddr::V4(Ipv4Addr::LOCALHOST));
+        assert_eq!(conf.retention.max, Some(100));
+        assert_eq!(conf.retention.age, Some(SignedDuration::from_hours(7 * 24)));
+    }
+
+    #[test]
+    fn yaml() {
+        let path = write("ses-local.yaml", "storage:\n  path: /tmp/ses-local\n");
+        let conf = Conf::load_from(Some(&path), env(&[])).unwrap();
+        assert_eq!(conf.storage.path, Some(PathBuf::from("/tmp/ses-local")));
+    }
+
+    #[test]
+    fn sending() {
+        let path = write(
+            "sending.yaml",
+            "templates:\n  welcome:\n    subject: Welcome {{name}}\n\
+            identities: [example.com, ops@example.org]\n\
+            rules:\n  - recipient: bounce@example.com\n    action: reject\n",
+        );
+        let conf = Conf::load_from(Some(&path), env(&[])).unwrap();
+        assert_eq!(
+            conf.templates["welcome"],
+            Template {
+                subject: Some(String::from("Welcome {{name}}")),
+                ..Default::default()
+            }
+        );
+        assert_eq!(conf.identities, ["example.com", "ops@example.org"]);
+        assert_eq!(
+            conf.rules,
+            [Rule {
+                recipient: String::from("bounce@example.com"),
+                action: Action::Reject,
+            }]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let path = write("typo.toml", "[server]\nprot = 9000\n");
+        let err = Conf::load_from(Some(&path), env(&[])).unwrap_err();
+        assert!(err.to_string().contains("unknown field `prot`"), "{}", err);
+
+        let err = Conf::load_from(None, env(&[("APP_SERVER_PORT", "high")])).unwrap_err();
+        assert!(err.to_string().contains("server.port"), "{}", err);
+
+        let path = write("invalid.toml", "[retention]\nmax = 0\nage = \"-1h\"\n");
+        let err = Conf::load_from(Some(&path), env(&[])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid configuration:\n  - retention.max must be at least 1\n  - retention.age must be positive"
+        );
+
+        let err = Conf::load_from(
+            None,
+            env(&[
+                ("APP_SERVER_CONTENT_PORT", "9002"),
+                ("APP_SERVER_CONTENT_URL", "mail.example.test"),
+            ]),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid configuration:\n  - server.content.url mail.example.test must be an http or https URL"
+        );
+
+        let err = Conf::load_from(
+            None,
+            env(&[
+                ("APP_SERVER_TLS_CERT", "missing.pem"),
+                ("APP_SERVER_TLS_KEY", "missing.key"),
+            ]),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid configuration:\n  - server.tls.cert missing.pem is not a file\n  - server.tls.key missing.key is not a file"
+        );
+
+        let path = write(
+            "sending.toml",
+            "[templates.empty]\n\n[[rules]]\nrecipient = \"\"\naction = \"reject\"\n",
+        );
+        let err = Conf::load_from(Some(&path), env(&[])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid configuration:\n  - templates.empty needs a subject, html or text\n  - rules recipient \"\" must be an address or domain"
+        );
+
+        let err = Conf::load_from(Some(Path::new("missing.toml")), env(&[])).unwrap_err();
+        assert_eq!(err.to_string(), "config file missing.toml does not exist");
     }
 }
@@ -0,0 +1,272 @@
+use std::sync::LazyLock;
+
+use mail_parser::{MessagePart, MimeHeaders};
+use percent_encoding::percent_decode_str;
+use regex::{Captures, Regex};
+use serde::Serialize;
+use ses_serde::types::{
+    Attachment, AttachmentContentDisposition, AttachmentContentTransferEncoding,
+};
+
+use super::{EmailRequest, RawEmail};
+
+const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+
+// A `cid:` URL as an attribute value or CSS `url()`, keeping what precedes it.
+static CID: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)(["'(]\s*)cid:([^"'\s)>]+)"#).unwrap());
+
+/// An attachment of a captured email, from the request's `Attachments` or a raw message's MIME
+/// parts, with its contents decoded.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct EmailAttachment {
+    pub file_name: String,
+    pub content_type: String,
+    pub inline: bool,
+    pub content_id: Option<String>,
+    /// As given in the request or the part's Content-Transfer-Encoding header.
+    pub transfer_encoding: Option<String>,
+    pub size: usize,
+    #[serde(skip)]
+    pub contents: Vec<u8>,
+}
+
+impl EmailAttachment {
+    /// Types browsers can show without the risk of running anything from the attachment.
+    pub fn is_previewable(&self) -> bool {
+        self.is_image() || self.is_text() || self.content_type == "application/pdf"
+    }
+
+    pub fn is_image(&self) -> bool {
+        matches!(
+            self.content_type.as_str(),
+            "image/png" | "image/jpeg" | "image/gif" | "image/webp" | "image/bmp"
+        )
+    }
+
+    pub fn is_text(&self) -> bool {
+        matches!(self.content_type.as_str(), "text/plain" | "text/csv")
+    }
+}
+
+/// Attachments of `request`, in the order they appear in it.
+pub fn list(request: &EmailRequest) -> Vec<EmailAttachment> {
+    match request {
+        EmailRequest::Raw(email) => RawEmail::parse(email)
+            .map(|message| {
+                message
+                    .attachments()
+                    .enumerate()
+                    .map(|(n, part)| from_part(n, part))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        _ => request.get_attachments().iter().map(from_ses).collect(),
+    }
+}
+
+/// `html` with each `cid:` reference to one of `attachments` replaced by `url` of its index.
+/// References no attachment matches are left as they are.
+pub fn resolve_cids(
+    html: &str,
+    attachments: &[EmailAttachment],
+    url: impl Fn(usize) -> String,
+) -> String {
+    CID.replace_all(html, |caps: &Captures| {
+        match find_cid(attachments, &caps[2]) {
+            Some(n) => format!("{}{}", &caps[1], url(n)),
+            None => caps[0].to_string(),
+        }
+    })
+    .into_owned()
+}
+
+/// Content ids `html` refers to that none of `attachments` has, each once.
+pub fn unresolved_cids(html: &str, attachments: &[EmailAttachment]) -> Vec<String> {
+    let mut unresolved: Vec<String> = vec![];
+    for caps in CID.captures_iter(html) {
+        let cid = &caps[2];
+        if find_cid(attachments, cid).is_none() && !unresolved.iter().any(|u| u == cid) {
+            unresolved.push(cid.to_string());
+        }
+    }
+    unresolved
+}
+
+fn find_cid(attachments: &[EmailAttachment], cid: &str) -> Option<usize> {
+    let cid = percent_decode_str(cid).decode_utf8_lossy();
+    attachments
+        .iter()
+        .position(|a| a.content_id.as_deref() == Some(cid.as_ref()))
+}
+
+// SES takes `RawContent` already decoded; the transfer encoding only picks how it's sent.
+fn from_ses(attachment: &Attachment) -> EmailAttachment {
+    EmailAttachment {
+        file_name: attachment.file_name.clone(),
+        content_type: attachment
+            .content_type
+            .clone()
+            .unwrap_or_else(|| String::from(DEFAULT_CONTENT_TYPE)),
+        inline: attachment.content_disposition == Some(AttachmentContentDisposition::Inline),
+        content_id: attachment.content_id.clone(),
+        transfer_encoding: attachment
+            .content_transfer_encoding
+            .as_ref()
+            .map(|encoding| {
+                String::from(match encoding {
+                    AttachmentContentTransferEncoding::Base64 => "base64",
+                    AttachmentContentTransferEncoding::QuotedPrintable => "quoted-printable",
+                    AttachmentContentTransferEncoding::SevenBit => "7bit",
+                })
+            }),
+        size: attachment.raw_content.inner.len(),
+        contents: attachment.raw_content.inner.clone(),
+    }
+}
+
+// mail-parser decodes the part according to its Content-Transfer-Encoding.
+fn from_part(n: usize, part: &MessagePart) -> EmailAttachment {
+    let content_type = part
+        .content_type()
+        .map(|ct| match ct.subtype() {
+            Some(subtype) => format!("{}/{}", ct.ctype(), subtype),
+            None => ct.ctype().to_string(),
+        })
+        .unwrap_or_else(|| String::from(DEFAULT_CONTENT_TYPE))
+        .to_lowercase();
+    EmailAttachment {
+        file_name: part
+            .attachment_name()
+            .map(String::from)
+            .unwrap_or_else(|| format!("attachment-{}", n + 1)),
+        content_type,
+        inline: part.content_disposition().is_some_and(|cd| cd.is_inline()),
+        content_id: part
+            .content_id()
+            .map(|cid| cid.trim_matches(['<', '>']).to_string()),
+        transfer_encoding: part.content_transfer_encoding().map(str::to_lowercase),
+        size: part.contents().len(),
+        contents: part.contents().to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ses_serde::operations::send_email::SendEmailInput;
+
+    use super::*;
+    use crate::event_store::send_email::eml;
+
+    #[test]
+    fn ses_attachments() {
+        let input: SendEmailInput = serde_json::from_value(serde_json::json!({
+            "Content": {
+                "Simple": {
+                    "Subject": { "Data": "Invoice" },
+                    "Attachments": [{
+                        "RawContent": "JVBERg==",
+                        "FileName": "invoice.pdf",
+                        "ContentType": "application/pdf",
+                        "ContentDisposition": "ATTACHMENT",
+                        "ContentTransferEncoding": "BASE64"
+                    }, {
+                        "RawContent": "iVBORw==",
+                        "FileName": "logo.png",
+                        "ContentType": "image/png",
+                        "ContentDisposition": "INLINE",
+                        "ContentId": "logo"
+                    }]
+                }
+            }
+        }))
+        .unwrap();
+        let attachments = list(&EmailRequest::new(input));
+        assert_eq!(
+            attachments,
+            [
+                EmailAttachment {
+                    file_name: String::from("invoice.pdf"),
+                    content_type: String::from("application/pdf"),
+                    inline: false,
+                    content_id: None,
+                    transfer_encoding: Some(String::from("base64")),
+                    size: 4,
+                    contents: b"%PDF".to_vec(),
+                },
+                EmailAttachment {
+                    file_name: String::from("logo.png"),
+                    content_type: String::from("image/png"),
+                    inline: true,
+                    content_id: Some(String::from("logo")),
+                    transfer_encoding: None,
+                    size: 4,
+                    contents: b"\x89PNG".to_vec(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn raw_attachments_decoded() {
+        let raw = "From: s@example.com\r\n\
+            To: a@example.com\r\n\
+            Content-Type: multipart/mixed; boundary=b\r\n\
+            \r\n\
+            --b\r\n\
+            Content-Type: text/plain\r\n\
+            \r\n\
+            Body\r\n\
+            --b\r\n\
+            Content-Type: text/plain\r\n\
+            Content-Disposition: attachment; filename=notes.txt\r\n\
+            Content-Transfer-Encoding: quoted-printable\r\n\
+            \r\n\
+            caf=C3=A9\r\n\
+            --b\r\n\
+            Content-Type: image/png\r\n\
+            Content-Disposition: inline\r\n\
+            Content-ID: <logo>\r\n\
+            Content-Transfer-Encoding: base64\r\n\
+            \r\n\
+            iVBORw==\r\n\
+            --b--\r\n";
+        let request = EmailRequest::new(eml::parse(raw.as_bytes()).unwrap());
+        let attachments = list(&request);
+        assert_eq!(attachments.len(), 2);
+        assert_eq!(attachments[0].file_name, "notes.txt");
+        assert_eq!(attachments[0].contents, "café".as_bytes());
+        assert_eq!(
+            attachments[0].transfer_encoding.as_deref(),
+            Some("quoted-printable")
+        );
+        assert_eq!(attachments[1].file_name, "attachment-2");
+        assert_eq!(attachments[1].content_type, "image/png");
+        assert!(attachments[1].inline);
+        assert_eq!(attachments[1].content_id.as_deref(), Some("logo"));
+        assert_eq!(attachments[1].contents, b"\x89PNG");
+    }
+
+    #[test]
+    fn cids() {
+        let attachments = [EmailAttachment {
+            file_name: String::from("logo.png"),
+            content_type: String::from("image/png"),
+            inline: true,
+            content_id: Some(String::from("logo@example.com")),
+            transfer_encoding: None,
+            size: 0,
+            contents: vec![],
+        }];
+        let html = r#"<img src="cid:logo@example.com"><img src='CID:logo%40example.com'>
+            <div style="background: url(cid:missing)">cid:logo@example.com</div>
+            <img src="cid:missing">"#;
+        assert_eq!(
+            resolve_cids(html, &attachments, |n| format!("/a/{}", n)),
+            r#"<img src="/a/0"><img src='/a/0'>
+            <div style="background: url(cid:missing)">cid:logo@example.com</div>
+            <img src="cid:missing">"#
+        );
+        assert_eq!(unresolved_cids(html, &attachments), ["missing"]);
+    }
+}
@@ -1,6 +0,0 @@
-use super::EmailWrapper;
-
-#[derive(Debug)]
-pub struct RawEmail {}
-
-impl EmailWrapper for RawEmail {}
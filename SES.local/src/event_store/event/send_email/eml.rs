@@ -0,0 +1,293 @@
+use aws_smithy_serde::types::Blob;
+use jiff::Timestamp;
+use mail_builder::{
+    headers::{address::Address as HeaderAddress, raw::Raw},
+    MessageBuilder,
+};
+use mail_parser::{Address, MessageParser};
+use ses_serde::{
+    operations::send_email::SendEmailInput,
+    types::{AttachmentContentDisposition, Destination, EmailContent, RawMessage},
+};
+
+use super::{address, threading::MESSAGE_ID_DOMAIN, EmailRequest, SendEmail};
+
+const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// A raw `SendEmailInput` for an RFC 5322 message, with the sender and recipients taken from
+/// its headers as SES would for `SendEmail` with raw content. `None` if it doesn't parse.
+pub fn parse(eml: &[u8]) -> Option<SendEmailInput> {
+    let message = MessageParser::default().parse_headers(eml)?;
+    let addresses = |address: Option<&Address>| {
+        address.map(|a| {
+            a.iter()
+                .filter_map(|addr| addr.address())
+                .map(String::from)
+                .collect::<Vec<String>>()
+        })
+    };
+    Some(SendEmailInput {
+        from_email_address: message
+            .from()
+            .and_then(|from| from.first())
+            .and_then(|from| from.address())
+            .map(String::from),
+        from_email_address_identity_arn: None,
+        destination: Some(Destination {
+            to_addresses: addresses(message.to()),
+            cc_addresses: addresses(message.cc()),
+            bcc_addresses: addresses(message.bcc()),
+        }),
+        reply_to_addresses: None,
+        feedback_forwarding_email_address: None,
+        feedback_forwarding_email_address_identity_arn: None,
+        content: Some(EmailContent {
+            simple: None,
+            template: None,
+            raw: Some(RawMessage {
+                data: Blob {
+                    inner: eml.to_vec(),
+                },
+            }),
+        }),
+        email_tags: None,
+        configuration_set_name: None,
+        endpoint_id: None,
+        list_management_options: None,
+    })
+}
+
+/// An uploaded RFC 5322 message, stored as a raw email. `None` if it doesn't parse.
+pub fn import(eml: &[u8]) -> Option<SendEmail> {
+    Some(SendEmail {
+        imported: true,
+        ..SendEmail::new(parse(eml)?)
+    })
+}
+
+/// The RFC 5322 message SES would have delivered for `email`, sent at `date`.
+///
+/// Raw emails are returned as sent. Simple and template emails are assembled with a
+/// multipart/alternative body, their attachments and the Message-ID and Date headers SES adds.
+pub fn build(email: &SendEmail, date: Timestamp) -> Vec<u8> {
+    let request = &email.request;
+    if let EmailRequest::Raw(input) = request {
+        if let Some(raw) = input.content.as_ref().and_then(|c| c.raw.as_ref()) {
+            return raw.data.inner.clone();
+        }
+    }
+    let mut message = MessageBuilder::new().date(date.as_second());
+    if let Some(id) = &email.response.message_id {
+        message = message.message_id(format!("{}@{}", id, MESSAGE_ID_DOMAIN));
+    }
+    if let Some(from) = request.get_from() {
+        message = message.from(mailbox(from));
+    }
+    if let Some(to) = request.get_to() {
+        if let Some(addresses) = &to.to_addresses {
+            message = message.to(mailboxes(addresses));
+        }
+        if let Some(addresses) = &to.cc_addresses {
+            message = message.cc(mailboxes(addresses));
+        }
+    }
+    if let Some(reply_to) = request.get_reply_to() {
+        message = message.reply_to(mailboxes(reply_to));
+    }
+    if let Some(subject) = request.get_rendered_subject() {
+        message = message.subject(subject);
+    }
+    for header in request.get_headers() {
+        message = message.header(header.name.as_str(), Raw::new(header.value.as_str()));
+    }
+    if let Some(text) = request.get_text() {
+        message = message.text_body(text);
+    }
+    if let Some(html) = request.get_html() {
+        message = message.html_body(html);
+    }
+    for attachment in request.get_attachments() {
+        let content_type = attachment
+            .content_type
+            .as_deref()
+            .unwrap_or(DEFAULT_CONTENT_TYPE);
+        let contents = attachment.raw_content.inner.clone();
+        message = match (&attachment.content_disposition, &attachment.content_id) {
+            (Some(AttachmentContentDisposition::Inline), Some(cid)) => {
+                message.inline(content_type, cid.as_str(), contents)
+            }
+            _ => message.attachment(content_type, attachment.file_name.as_str(), contents),
+        };
+    }
+    message
+        .write_to_vec()
+        .expect("writing to a Vec doesn't fail")
+}
+
+/// An SES mailbox such as `Shop <shop@example.com>` as a header address, so the display name
+/// isn't written as part of the address.
+fn mailbox(mailbox: &str) -> HeaderAddress<'_> {
+    let mailbox = address::parse(mailbox);
+    HeaderAddress::new_address(mailbox.name, mailbox.address)
+}
+
+fn mailboxes(mailboxes: &[String]) -> HeaderAddress<'_> {
+    HeaderAddress::new_list(mailboxes.iter().map(|m| mailbox(m)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use mail_parser::MimeHeaders;
+    use ses_serde::types::{Attachment, Body, Content, Message, MessageHeader};
+
+    use super::*;
+
+    #[test]
+    fn addresses_from_headers() {
+        let eml = b"From: Sender <sender@example.com>\r\n\
+            To: a@example.com, B <b@example.com>\r\n\
+            Cc: c@example.com\r\n\
+            Subject: Hi\r\n\
+            \r\n\
+            Hello\r\n";
+        let input = parse(eml).unwrap();
+        assert_eq!(
+            input.from_email_address.as_deref(),
+            Some("sender@example.com")
+        );
+        let destination = input.destination.unwrap();
+        assert_eq!(
+            destination.to_addresses,
+            Some(vec![
+                String::from("a@example.com"),
+                String::from("b@example.com")
+            ])
+        );
+        assert_eq!(
+            destination.cc_addresses,
+            Some(vec![String::from("c@example.com")])
+        );
+        assert_eq!(destination.bcc_addresses, None);
+        assert_eq!(input.content.unwrap().raw.unwrap().data.inner, eml);
+    }
+
+    #[test]
+    fn builds_simple_emails() {
+        let content = |data: &str| Content {
+            data: data.to_string(),
+            charset: None,
+        };
+        let mut input = parse(b"From: s@example.com\r\nTo: a@example.com\r\n\r\n").unwrap();
+        input.destination.as_mut().unwrap().cc_addresses =
+            Some(vec![String::from("c@example.com")]);
+        input.content = Some(EmailContent {
+            simple: Some(Message {
+                subject: Some(content("Hi")),
+                body: Some(Body {
+                    text: Some(content("Hello")),
+                    html: Some(content("<p>Hello</p>")),
+                }),
+                headers: Some(vec![MessageHeader {
+                    name: String::from("X-Campaign"),
+                    value: String::from("spring"),
+                }]),
+                attachments: Some(vec![Attachment {
+                    raw_content: Blob {
+                        inner: b"%PDF".to_vec(),
+                    },
+                    content_disposition: None,
+                    file_name: String::from("invoice.pdf"),
+                    content_description: None,
+                    content_id: None,
+                    content_transfer_encoding: None,
+                    content_type: Some(String::from("application/pdf")),
+                }]),
+            }),
+            template: None,
+            raw: None,
+        });
+        let email = SendEmail::new(input);
+        let date: Timestamp = "2024-01-02T03:04:05Z".parse().unwrap();
+        let eml = build(&email, date);
+
+        let message = MessageParser::default().parse(&eml).unwrap();
+        assert_eq!(
+            message.message_id(),
+            Some(
+                format!(
+                    "{}@email.amazonses.com",
+                    email.response.message_id.as_ref().unwrap()
+                )
+                .as_str()
+            )
+        );
+        assert_eq!(message.date().unwrap().to_timestamp(), date.as_second());
+        assert_eq!(message.subject(), Some("Hi"));
+        assert_eq!(
+            message.cc().unwrap().first().unwrap().address(),
+            Some("c@example.com")
+        );
+        assert_eq!(
+            message.header_raw("X-Campaign").map(str::trim),
+            Some("spring")
+        );
+        assert_eq!(message.body_text(0).as_deref(), Some("Hello"));
+        assert_eq!(message.body_html(0).as_deref(), Some("<p>Hello</p>"));
+        let attachment = message.attachment(0).unwrap();
+        assert_eq!(attachment.attachment_name(), Some("invoice.pdf"));
+        assert_eq!(attachment.contents(), b"%PDF");
+    }
+
+    #[test]
+    fn display_names_round_trip() {
+        let mut input = parse(b"From: s@example.com\r\n\r\n").unwrap();
+        input.from_email_address = Some(String::from("Shop <shop@example.com>"));
+        input.destination = Some(Destination {
+            to_addresses: Some(vec![String::from(r#""Doe, Jane" <jane@example.com>"#)]),
+            cc_addresses: None,
+            bcc_addresses: None,
+        });
+        input.content = Some(EmailContent {
+            simple: Some(Message {
+                subject: None,
+                body: Some(Body {
+                    text: Some(Content {
+                        data: String::from("Hello"),
+                        charset: None,
+                    }),
+                    html: None,
+                }),
+                headers: None,
+                attachments: None,
+            }),
+            template: None,
+            raw: None,
+        });
+        let eml = build(&SendEmail::new(input), Timestamp::now());
+
+        let message = MessageParser::default().parse(&eml).unwrap();
+        let from = message.from().unwrap().first().unwrap();
+        assert_eq!(from.name(), Some("Shop"));
+        assert_eq!(from.address(), Some("shop@example.com"));
+        let to = message.to().unwrap().first().unwrap();
+        assert_eq!(to.name(), Some("Doe, Jane"));
+        assert_eq!(to.address(), Some("jane@example.com"));
+
+        let reparsed = parse(&eml).unwrap();
+        assert_eq!(
+            reparsed.from_email_address.as_deref(),
+            Some("shop@example.com")
+        );
+        assert_eq!(
+            reparsed.destination.unwrap().to_addresses,
+            Some(vec![String::from("jane@example.com")])
+        );
+    }
+
+    #[test]
+    fn raw_emails_verbatim() {
+        let eml = b"From: s@example.com\r\nTo: a@example.com\r\nSubject: Raw\r\n\r\nAs is\r\n";
+        let email = SendEmail::new(parse(eml).unwrap());
+        assert_eq!(build(&email, Timestamp::now()), eml);
+    }
+}
@@ -0,0 +1,200 @@
+use std::sync::LazyLock;
+
+use percent_encoding::percent_decode_str;
+use regex::Regex;
+use scraper::{Html, Selector};
+use url::Url;
+
+use super::EmailRequest;
+
+pub use ses_local_serde::send_email::extract::{Link, Match, Part};
+
+static TEXT_URL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"https?://[^\s<>"'()\[\]]+"#).unwrap());
+static ANCHOR: LazyLock<Selector> = LazyLock::new(|| Selector::parse("a[href]").unwrap());
+
+// Query parameters commonly used by click trackers to carry the destination.
+const REDIRECT_PARAMS: [&str; 8] = [
+    "url",
+    "u",
+    "redirect",
+    "redirect_url",
+    "target",
+    "dest",
+    "destination",
+    "link",
+];
+const MAX_REDIRECTS: usize = 5;
+const TRAILING_PUNCTUATION: [char; 6] = ['.', ',', ';', ':', '!', '?'];
+
+pub fn links(request: &EmailRequest) -> Vec<Link> {
+    let mut links = vec![];
+    if let Some(html) = request.get_html() {
+        let document = Html::parse_document(&html);
+        links.extend(
+            document
+                .select(&ANCHOR)
+                .enumerate()
+                .filter_map(|(position, a)| {
+                    let href = a.value().attr("href")?.trim().to_string();
+                    let text = a.text().collect::<String>().trim().to_string();
+                    Some(Link {
+                        resolved: resolve(&href),
+                        text: (!text.is_empty()).then_some(text),
+                        href,
+                        part: Part::Html,
+                        position,
+                    })
+                }),
+        );
+    }
+    if let Some(text) = request.get_text() {
+        links.extend(TEXT_URL.find_iter(&text).enumerate().map(|(position, m)| {
+            // Punctuation ending the sentence around a URL isn't part of it.
+            let href = m.as_str().trim_end_matches(TRAILING_PUNCTUATION);
+            Link {
+                href: href.to_string(),
+                resolved: resolve(href),
+                text: None,
+                part: Part::Text,
+                position,
+            }
+        }));
+    }
+    links
+}
+
+pub fn matches(request: &EmailRequest, regex: &Regex) -> Vec<Match> {
+    let parts = [
+        (Part::Text, request.get_text()),
+        (Part::Html, request.get_html()),
+    ];
+    parts
+        .into_iter()
+        .filter_map(|(part, content)| Some((part, content?)))
+        .flat_map(|(part, content)| {
+            regex
+                .captures_iter(&content)
+                .map(|caps| Match {
+                    part,
+                    offset: caps.get(0).map(|m| m.start()).unwrap_or_default(),
+                    groups: caps
+                        .iter()
+                        .map(|g| g.map(|g| g.as_str().to_string()))
+                        .collect(),
+                    named: regex
+                        .capture_names()
+                        .flatten()
+                        .filter_map(|name| {
+                            Some((name.to_string(), caps.name(name)?.as_str().to_string()))
+                        })
+                        .collect(),
+                })
+                .collect::<Vec<Match>>()
+        })
+        .collect()
+}
+
+fn resolve(href: &str) -> String {
+    let mut resolved = href.to_string();
+    for _ in 0..MAX_REDIRECTS {
+        match unwrap_redirect(&resolved) {
+            Some(target) => resolved = target,
+            None => break,
+        }
+    }
+    resolved
+}
+
+fn unwrap_redirect(href: &str) -> Option<String> {
+    let url = Url::parse(href).ok()?;
+    // SES click tracking: https://<id>.r.<region>.awstrack.me/L0/<encoded target>/...
+    if url.host_str()?.ends_with(".awstrack.me") {
+        let mut segments = url.path_segments()?;
+        if segments.next()? == "L0" {
+            let target = percent_decode_str(segments.next()?).decode_utf8().ok()?;
+            return is_web_url(&target).then(|| target.into_owned());
+        }
+    }
+    url.query_pairs()
+        .find(|(key, value)| REDIRECT_PARAMS.contains(&key.as_ref()) && is_web_url(value))
+        .map(|(_, value)| value.into_owned())
+}
+
+fn is_web_url(value: &str) -> bool {
+    Url::parse(value).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unwraps_tracking_redirects() {
+        assert_eq!(
+            resolve("https://click.example.com/track?id=1&url=https%3A%2F%2Fapp.example.com%2Freset%3Ftoken%3Dabc"),
+            "https://app.example.com/reset?token=abc"
+        );
+        assert_eq!(
+            resolve("https://abc.r.us-east-1.awstrack.me/L0/https:%2F%2Fapp.example.com%2Fverify/1/0100/xyz="),
+            "https://app.example.com/verify"
+        );
+        assert_eq!(
+            resolve("https://app.example.com/?u=not-a-url"),
+            "https://app.example.com/?u=not-a-url"
+        );
+    }
+
+    #[test]
+    fn links_and_matches() {
+        let re = EmailRequest::new(
+            serde_json::from_value(serde_json::json!({
+                "Content": { "Simple": {
+                    "Subject": { "Data": "Email Subject!" },
+                    "Body": {
+                        "Html": { "Data": "<p>Code: 123456</p><a href=\"https://t.example.com/c?url=https%3A%2F%2Fexample.com%2Fverify\"> Verify </a>" },
+                        "Text": { "Data": "Code: 123456 https://example.com/verify. Help: https://example.com/help?" }
+                    }
+                } }
+            }))
+            .unwrap(),
+        );
+        assert_eq!(
+            links(&re),
+            vec![
+                Link {
+                    href: "https://t.example.com/c?url=https%3A%2F%2Fexample.com%2Fverify"
+                        .to_string(),
+                    resolved: "https://example.com/verify".to_string(),
+                    text: Some("Verify".to_string()),
+                    part: Part::Html,
+                    position: 0,
+                },
+                Link {
+                    href: "https://example.com/verify".to_string(),
+                    resolved: "https://example.com/verify".to_string(),
+                    text: None,
+                    part: Part::Text,
+                    position: 0,
+                },
+                Link {
+                    href: "https://example.com/help".to_string(),
+                    resolved: "https://example.com/help".to_string(),
+                    text: None,
+                    part: Part::Text,
+                    position: 1,
+                },
+            ]
+        );
+
+        let matches = matches(&re, &Regex::new(r"Code: (?<code>\d{6})").unwrap());
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].part, Part::Text);
+        assert_eq!(matches[0].offset, 0);
+        assert_eq!(
+            matches[0].groups,
+            vec![Some("Code: 123456".to_string()), Some("123456".to_string())]
+        );
+        assert_eq!(matches[1].named["code"], "123456");
+    }
+}
@@ -0,0 +1,136 @@
+use ses_serde::types::MessageHeader;
+
+use super::{EmailRequest, RawEmail};
+
+/// The email's headers: a raw message's own, in order, or for simple and template emails the
+/// ones SES would write from the request. Bcc is included so it can be checked.
+pub fn list(request: &EmailRequest) -> Vec<MessageHeader> {
+    if let EmailRequest::Raw(email) = request {
+        return RawEmail::parse(email)
+            .map(|message| {
+                message
+                    .headers_raw()
+                    .map(|(name, value)| header(name, unfold(value)))
+                    .collect()
+            })
+            .unwrap_or_default();
+    }
+    let input = request.get_input();
+    let mut headers = vec![];
+    if let Some(from) = request.get_from() {
+        headers.push(header("From", from.to_string()));
+    }
+    if let Some(to) = request.get_to() {
+        for (name, addresses) in [
+            ("To", &to.to_addresses),
+            ("Cc", &to.cc_addresses),
+            ("Bcc", &to.bcc_addresses),
+        ] {
+            if let Some(addresses) = addresses.as_ref().filter(|a| !a.is_empty()) {
+                headers.push(header(name, addresses.join(", ")));
+            }
+        }
+    }
+    if let Some(reply_to) = request.get_reply_to().filter(|r| !r.is_empty()) {
+        headers.push(header("Reply-To", reply_to.join(", ")));
+    }
+    if let Some(subject) = request.get_rendered_subject() {
+        headers.push(header("Subject", subject.into_owned()));
+    }
+    headers.extend(request.get_headers().iter().cloned());
+    if let Some(configuration_set) = &input.configuration_set_name {
+        headers.push(header("X-SES-CONFIGURATION-SET", configuration_set.clone()));
+    }
+    if let Some(tags) = input.email_tags.as_ref().filter(|t| !t.is_empty()) {
+        let tags = tags
+            .iter()
+            .map(|tag| format!("{}={}", tag.name, tag.value))
+            .collect::<Vec<_>>();
+        headers.push(header("X-SES-MESSAGE-TAGS", tags.join(", ")));
+    }
+    if let Some(list) = &input.list_management_options {
+        let value = match &list.topic_name {
+            Some(topic) => format!("{}; topic={}", list.contact_list_name, topic),
+            None => list.contact_list_name.clone(),
+        };
+        headers.push(header("X-SES-LIST-MANAGEMENT-OPTIONS", value));
+    }
+    headers
+}
+
+fn header(name: &str, value: String) -> MessageHeader {
+    MessageHeader {
+        name: name.to_string(),
+        value,
+    }
+}
+
+// Folded header values continue on lines starting with whitespace.
+fn unfold(value: &str) -> String {
+    value
+        .split('\n')
+        .map(|line| line.trim_end_matches('\r').trim())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use ses_serde::operations::send_email::SendEmailInput;
+
+    use super::*;
+    use crate::event_store::send_email::eml;
+
+    fn names_and_values(headers: Vec<MessageHeader>) -> Vec<(String, String)> {
+        headers.into_iter().map(|h| (h.name, h.value)).collect()
+    }
+
+    #[test]
+    fn from_request() {
+        let input: SendEmailInput = serde_json::from_value(serde_json::json!({
+            "FromEmailAddress": "s@example.com",
+            "Destination": {
+                "ToAddresses": ["a@example.com", "b@example.com"],
+                "BccAddresses": ["c@example.com"]
+            },
+            "ReplyToAddresses": ["r@example.com"],
+            "Content": {
+                "Simple": {
+                    "Subject": { "Data": "Hi" },
+                    "Headers": [{ "Name": "X-Campaign", "Value": "spring" }]
+                }
+            },
+            "EmailTags": [{ "Name": "flow", "Value": "welcome" }],
+            "ConfigurationSetName": "default",
+            "ListManagementOptions": { "ContactListName": "news", "TopicName": "weekly" }
+        }))
+        .unwrap();
+        assert_eq!(
+            names_and_values(list(&EmailRequest::new(input))),
+            [
+                ("From", "s@example.com"),
+                ("To", "a@example.com, b@example.com"),
+                ("Bcc", "c@example.com"),
+                ("Reply-To", "r@example.com"),
+                ("Subject", "Hi"),
+                ("X-Campaign", "spring"),
+                ("X-SES-CONFIGURATION-SET", "default"),
+                ("X-SES-MESSAGE-TAGS", "flow=welcome"),
+                ("X-SES-LIST-MANAGEMENT-OPTIONS", "news; topic=weekly"),
+            ]
+            .map(|(n, v)| (n.to_string(), v.to_string()))
+        );
+    }
+
+    #[test]
+    fn from_raw_message() {
+        let raw = b"From: s@example.com\r\nSubject: A long\r\n  subject\r\n\r\nHi\r\n";
+        let request = EmailRequest::new(eml::parse(raw).unwrap());
+        assert_eq!(
+            names_and_values(list(&request)),
+            [("From", "s@example.com"), ("Subject", "A long subject")]
+                .map(|(n, v)| (n.to_string(), v.to_string()))
+        );
+    }
+}
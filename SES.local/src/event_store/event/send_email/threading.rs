@@ -0,0 +1,151 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Serialize;
+
+use super::{headers, EmailTag, SendEmail};
+
+// SES qualifies the message ids it hands out with this domain in the Message-ID header.
+pub const MESSAGE_ID_DOMAIN: &str = "email.amazonses.com";
+
+static MSG_ID: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^<>\s]+>").unwrap());
+
+/// The headers that place an email in a conversation, with ids in their `<...>` form.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct ThreadHeaders {
+    /// The Message-ID recipients see: the one SES assigned, or an imported message's own.
+    pub message_id: Option<String>,
+    /// A raw message's own Message-ID, which SES replaces with the one it assigns.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replaced_message_id: Option<String>,
+    pub in_reply_to: Vec<String>,
+    pub references: Vec<String>,
+}
+
+impl ThreadHeaders {
+    /// Every id other emails may refer to this one by.
+    pub fn ids(&self) -> impl Iterator<Item = &String> {
+        self.message_id.iter().chain(&self.replaced_message_id)
+    }
+
+    /// The ids this email refers to, the one it replies to first.
+    pub fn referenced(&self) -> impl Iterator<Item = &String> {
+        self.in_reply_to.iter().chain(self.references.iter().rev())
+    }
+}
+
+/// The Message-ID header SES writes for a message id it handed out.
+pub fn ses_message_id(message_id: &str) -> String {
+    format!("<{}@{}>", message_id, MESSAGE_ID_DOMAIN)
+}
+
+/// The threading headers of `email`, from a raw message's MIME headers or the custom headers
+/// of a simple or template email.
+pub fn headers(email: &SendEmail) -> ThreadHeaders {
+    let mut threading = ThreadHeaders::default();
+    let mut own = None;
+    for header in headers::list(&email.request) {
+        let ids = || parse_ids(&header.value);
+        match header.name.to_ascii_lowercase().as_str() {
+            "message-id" => own = ids().into_iter().next(),
+            "in-reply-to" => threading.in_reply_to.extend(ids()),
+            "references" => threading.references.extend(ids()),
+            _ => {}
+        }
+    }
+    let assigned = email.response.message_id.as_deref().map(ses_message_id);
+    match email.imported {
+        true => threading.message_id = own.or(assigned),
+        false => {
+            threading.message_id = assigned;
+            if matches!(email.request.get_tag(), EmailTag::Raw) {
+                threading.replaced_message_id = own;
+            }
+        }
+    }
+    threading
+}
+
+// Ids are `<...>`; bare ones, which some senders write, are bracketed as they would have been.
+fn parse_ids(value: &str) -> Vec<String> {
+    let ids: Vec<String> = MSG_ID
+        .find_iter(value)
+        .map(|id| id.as_str().to_string())
+        .collect();
+    match ids.is_empty() {
+        true => value
+            .split([' ', ',', '\t'])
+            .filter(|id| !id.is_empty())
+            .map(|id| format!("<{}>", id))
+            .collect(),
+        false => ids,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_store::send_email::eml;
+
+    #[test]
+    fn from_custom_headers() {
+        let email = SendEmail::new(
+            serde_json::from_value(serde_json::json!({
+                "Content": {
+                    "Simple": {
+                        "Subject": { "Data": "Re: Ticket" },
+                        "Headers": [
+                            { "Name": "In-Reply-To", "Value": "<a@example.com>" },
+                            { "Name": "references", "Value": "<root@example.com>\r\n <a@example.com>" }
+                        ]
+                    }
+                }
+            }))
+            .unwrap(),
+        );
+        assert_eq!(
+            headers(&email),
+            ThreadHeaders {
+                message_id: Some(format!(
+                    "<{}@email.amazonses.com>",
+                    email.response.message_id.as_ref().unwrap()
+                )),
+                replaced_message_id: None,
+                in_reply_to: vec![String::from("<a@example.com>")],
+                references: vec![
+                    String::from("<root@example.com>"),
+                    String::from("<a@example.com>")
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn from_raw_message() {
+        let raw = b"Message-ID: <own@example.com>\r\n\
+            In-Reply-To: bare@example.com\r\n\
+            References: <root@example.com> <bare@example.com>\r\n\
+            \r\n\
+            Hi\r\n";
+        let sent = SendEmail::new(eml::parse(raw).unwrap());
+        let threading = headers(&sent);
+        assert_eq!(
+            threading.message_id,
+            sent.response.message_id.as_deref().map(ses_message_id)
+        );
+        assert_eq!(
+            threading.replaced_message_id.as_deref(),
+            Some("<own@example.com>")
+        );
+        assert_eq!(threading.in_reply_to, ["<bare@example.com>"]);
+        assert_eq!(
+            threading.references,
+            ["<root@example.com>", "<bare@example.com>"]
+        );
+
+        let imported = eml::import(raw).unwrap();
+        let threading = headers(&imported);
+        assert_eq!(threading.message_id.as_deref(), Some("<own@example.com>"));
+        assert_eq!(threading.replaced_message_id, None);
+    }
+}
@@ -1,9 +1,25 @@
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 
-use super::{send_email::SendEmail, Event, EventContent};
+use super::{
+    inbox::{self, InboxEmail, Mailbox, ReadState},
+    send_email::{
+        threading::{self, ThreadHeaders},
+        EmailRequest, SendEmail,
+    },
+    threads::{self, Thread},
+    EmailQuery, Event, EventContent, EventQuery, Page, QueryError, Retention, Snapshot, Stats,
+};
 use futures::Stream;
+use jiff::Timestamp;
+use ses_serde::types::EmailTemplateContent;
 use thiserror::Error;
-use tokio::{sync::broadcast, task::JoinHandle};
+use tokio::{
+    sync::broadcast::{self, error::RecvError},
+    task::JoinHandle,
+};
+
+// Room for bursts such as clearing a filter's matches, before slow subscribers start lagging.
+const CHANGES_CAPACITY: usize = 256;
 
 #[derive(Error, Debug)]
 pub enum EventStoreError {
@@ -11,36 +27,193 @@ pub enum EventStoreError {
     Failed,
 }
 
+/// What happened to the store, as broadcast to subscribers of [`EventStore::get_changes`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    Added(Event),
+    /// An event replaced by a newer version with the same id, or an email marked read or
+    /// unread in an inbox.
+    Updated(Event),
+    /// An event removed by a delete or by retention, as it was before.
+    Deleted(Event),
+    Cleared,
+    /// Many events stored or removed at once, by an import or a restore. Subscribers reload
+    /// what they show instead of getting a change for each event.
+    Reloaded,
+}
+
 #[derive(Clone)]
 pub struct EventStore {
     events: VecDeque<Event>,
-    stream: broadcast::Sender<Event>,
+    stream: broadcast::Sender<Change>,
+    retention: Retention,
+    // Which emails each inbox has read.
+    read: ReadState,
+    // The threading headers of each stored email by event id, parsed once as it's stored.
+    threading: HashMap<String, ThreadHeaders>,
+    // Templates seeded from fixtures, by name, for emails sent with a `TemplateName`.
+    templates: HashMap<String, EmailTemplateContent>,
+}
+
+impl Default for EventStore {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl EventStore {
     pub fn new() -> Self {
-        let (stream, mut rx) = broadcast::channel(2);
+        let (stream, mut rx) = broadcast::channel(CHANGES_CAPACITY);
         let events = VecDeque::new();
         let mut events_clone = events.clone();
         tokio::spawn(async move {
-            while let Ok(event) = rx.recv().await {
-                events_clone.push_front(event);
+            while let Ok(change) = rx.recv().await {
+                if let Change::Added(event) = change {
+                    events_clone.push_front(event);
+                }
             }
         });
-        EventStore { events, stream }
+        EventStore {
+            events,
+            stream,
+            retention: Retention::default(),
+            read: ReadState::default(),
+            threading: HashMap::new(),
+            templates: HashMap::new(),
+        }
+    }
+
+    pub fn with_retention(mut self, retention: Retention) -> Self {
+        self.retention = retention;
+        self
     }
 
     pub async fn push(&mut self, event: Event) -> Result<Event, EventStoreError> {
+        let event = self.with_template(event);
         self.events.push_front(event.clone());
+        self.cache_threading(&event);
+        let expired = self.apply_retention();
         let saved = self.wait_for_event_id(&event.id);
-        match self.stream.send(event.clone()) {
+        let sent = self.stream.send(Change::Added(event.clone()));
+        for event in expired {
+            self.notify(Change::Deleted(event));
+        }
+        match sent {
             Ok(_) => saved.await.or(Err(EventStoreError::Failed)),
             _ => Err(EventStoreError::Failed),
         }
     }
 
+    /// Stores email templates by name, replacing any with the same names.
+    pub fn add_templates(
+        &mut self,
+        templates: impl IntoIterator<Item = (String, EmailTemplateContent)>,
+    ) {
+        self.templates.extend(templates);
+    }
+
+    /// `event` with a stored template's content filled in, if it's an email sent by the name
+    /// or ARN of that template rather than with the content itself.
+    pub fn with_template(&self, mut event: Event) -> Event {
+        let Some(EventContent::SendEmail(SendEmail {
+            request: EmailRequest::Template(input),
+            ..
+        })) = &mut event.content
+        else {
+            return event;
+        };
+        let Some(template) = input.content.as_mut().and_then(|c| c.template.as_mut()) else {
+            return event;
+        };
+        if template.template_content.is_none() {
+            let arn_name = template
+                .template_arn
+                .as_deref()
+                .and_then(|arn| arn.rsplit_once(":template/"))
+                .map(|(_, name)| name);
+            let name = template.template_name.as_deref().or(arn_name);
+            template.template_content = name.and_then(|name| self.templates.get(name)).cloned();
+        }
+        event
+    }
+
+    /// Replaces the stored event with `event`'s id, returning whether there was one.
+    pub fn update(&mut self, event: Event) -> bool {
+        match self.events.iter_mut().find(|e| e.id == event.id) {
+            Some(stored) => {
+                *stored = event.clone();
+                self.cache_threading(&event);
+                self.notify(Change::Updated(event));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stores previously exported events among those already stored by timestamp, skipping
+    /// ids that are already stored. Events with the same timestamp are assumed to be newest
+    /// first, as stored. Subscribers get a single [`Change::Reloaded`] for the whole batch.
+    pub async fn import(&mut self, events: Vec<Event>) -> Result<usize, EventStoreError> {
+        let imported = self.merge(events);
+        if imported > 0 {
+            self.apply_retention();
+            self.notify(Change::Reloaded);
+        }
+        Ok(imported)
+    }
+
+    // Merges `events` into the newest-first list in one pass, returning how many were new.
+    fn merge(&mut self, mut events: Vec<Event>) -> usize {
+        let timestamp = |ev: &Event| ev.timestamp.parse::<Timestamp>().ok();
+        let mut ids = self
+            .events
+            .iter()
+            .map(|ev| ev.id.clone())
+            .collect::<HashSet<_>>();
+        events.retain(|ev| ids.insert(ev.id.clone()));
+        // Stable, so events with the same timestamp keep their order.
+        events.sort_by_key(|ev| std::cmp::Reverse(timestamp(ev)));
+        let imported = events.len();
+        let mut stored = std::mem::take(&mut self.events).into_iter().peekable();
+        let mut merged = VecDeque::with_capacity(stored.len() + imported);
+        for event in events {
+            let at = timestamp(&event);
+            while let Some(newer) = stored.next_if(|stored| timestamp(stored) > at) {
+                merged.push_back(newer);
+            }
+            self.cache_threading(&event);
+            merged.push_back(event);
+        }
+        merged.extend(stored);
+        self.events = merged;
+        imported
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            templates: self.templates.clone(),
+            read: self.read.clone(),
+            ..Snapshot::new(
+                self.events.iter().cloned().collect(),
+                self.retention.clone(),
+            )
+        }
+    }
+
+    /// Replaces the stored events, templates and read state with those in `snapshot`, returning
+    /// how many events were restored. Retention stays as configured, applied to the restored
+    /// events; the snapshot's is only a record of the server it came from.
+    pub async fn restore(&mut self, snapshot: Snapshot) -> Result<usize, EventStoreError> {
+        self.reset();
+        self.templates = snapshot.templates;
+        let restored = self.merge(snapshot.events) - self.apply_retention().len();
+        self.read = snapshot.read;
+        self.notify(Change::Reloaded);
+        Ok(restored)
+    }
+
     pub fn get_all(&self) -> Vec<&Event> {
-        (&self.events).into_iter().collect::<Vec<&Event>>()
+        self.events.iter().collect::<Vec<&Event>>()
     }
 
     pub fn get_by_event_id(&self, id: &str) -> Option<&Event> {
@@ -52,31 +225,121 @@ impl EventStore {
         let mut rx = self.stream.subscribe();
         tokio::spawn(async move {
             let mut em: Option<Event> = None;
-            while let Ok(event) = rx.recv().await {
-                if event.id == id {
-                    em = Some(event.clone());
-                    break;
+            while let Ok(change) = rx.recv().await {
+                match change {
+                    Change::Added(event) if event.id == id => {
+                        em = Some(event);
+                        break;
+                    }
+                    _ => {}
                 }
             }
             em.unwrap()
         })
     }
 
+    // Returns the events retention dropped.
+    fn apply_retention(&mut self) -> Vec<Event> {
+        let now = Timestamp::now();
+        let mut index = 0;
+        let mut expired = vec![];
+        self.events.retain(|ev| {
+            index += 1;
+            let keeps = self.retention.keeps(index - 1, ev, now);
+            if !keeps {
+                expired.push(ev.clone());
+            }
+            keeps
+        });
+        for event in &expired {
+            self.forget(event);
+        }
+        expired
+    }
+
+    // Drops what's kept about `event` besides the event itself, once it's removed.
+    fn forget(&mut self, event: &Event) {
+        self.threading.remove(&event.id);
+        if let (Some(EventContent::SendEmail(_)), Some(message_id)) =
+            (&event.content, event.get_message_id())
+        {
+            self.read.forget(message_id);
+        }
+    }
+
+    fn cache_threading(&mut self, event: &Event) {
+        if let Some(EventContent::SendEmail(email)) = &event.content {
+            self.threading
+                .insert(event.id.clone(), threading::headers(email));
+        }
+    }
+
+    // Stored emails with their threading headers, newest first.
+    fn emails_with_threading(&self) -> Vec<(&SendEmail, &ThreadHeaders)> {
+        self.events
+            .iter()
+            .filter_map(|ev| match &ev.content {
+                Some(EventContent::SendEmail(email)) => Some((email, self.threading.get(&ev.id)?)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // Nobody listening isn't an error outside of `push`, which waits for its own broadcast.
+    fn notify(&self, change: Change) {
+        _ = self.stream.send(change);
+    }
+
     pub fn clear(&mut self) {
+        self.reset();
+        self.notify(Change::Cleared);
+    }
+
+    fn reset(&mut self) {
         self.events.clear();
+        self.read.clear();
+        self.threading.clear();
     }
 
     pub fn delete_event(&mut self, id: &str) {
         if let Some(index) = self.events.iter().position(|e| *e.id == *id) {
-            self.events.remove(index);
+            if let Some(event) = self.events.remove(index) {
+                self.forget(&event);
+                self.notify(Change::Deleted(event));
+            }
         }
     }
 
+    /// Events as they're added, as [`EventStore::get_changes`] without the other changes.
     pub fn get_stream(&self) -> impl Stream<Item = Event> + use<> {
-        let mut rx = self.stream.subscribe();
+        let changes = self.get_changes();
         async_stream::stream! {
-            while let Ok(event) = rx.recv().await {
-                yield event;
+            for await change in changes {
+                if let Change::Added(event) = change {
+                    yield event;
+                }
+            }
+        }
+    }
+
+    /// The channel [`EventStore::get_changes`] reads, for subscribers that need to know when
+    /// they fell behind.
+    pub fn subscribe(&self) -> broadcast::Receiver<Change> {
+        self.stream.subscribe()
+    }
+
+    /// Every change from now on. Changes missed by falling too far behind are skipped.
+    pub fn get_changes(&self) -> impl Stream<Item = Change> + use<> {
+        let mut rx = self.subscribe();
+        async_stream::stream! {
+            loop {
+                match rx.recv().await {
+                    Ok(change) => yield change,
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!("change subscriber lagged, skipping {} changes", missed);
+                    }
+                    Err(RecvError::Closed) => break,
+                }
             }
         }
     }
@@ -103,11 +366,227 @@ impl EventStore {
             _ => None,
         })
     }
+
+    /// The event an email was captured in, for when its timestamp is needed too.
+    pub fn get_email_event_by_message_id(&self, message_id: &str) -> Option<&Event> {
+        self.get_all()
+            .into_iter()
+            .find(|ev| ev.get_message_id() == Some(message_id))
+    }
+
+    /// Every event about the email with `message_id`, oldest first.
+    pub fn get_events_by_message_id(&self, message_id: &str) -> Vec<&Event> {
+        self.events
+            .iter()
+            .rev()
+            .filter(|ev| ev.get_message_id() == Some(message_id))
+            .collect()
+    }
+
+    pub fn query_events(&self, query: &EventQuery) -> Result<Page<&Event>, QueryError> {
+        Page::new(
+            self.events
+                .iter()
+                .filter(|ev| query.matches(ev))
+                .map(|ev| (ev, ev)),
+            query.cursor.as_deref(),
+            query.limit,
+        )
+    }
+
+    pub fn query_emails(&self, query: &EmailQuery) -> Result<Page<&SendEmail>, QueryError> {
+        Page::new(
+            self.events.iter().filter_map(|ev| match &ev.content {
+                Some(EventContent::SendEmail(se)) if query.matches(ev, se) => Some((ev, se)),
+                _ => None,
+            }),
+            query.cursor.as_deref(),
+            query.limit,
+        )
+    }
+
+    pub fn get_near_misses(&self, query: &EmailQuery) -> Vec<&SendEmail> {
+        self.events
+            .iter()
+            .filter_map(|ev| match &ev.content {
+                Some(EventContent::SendEmail(se)) if query.nearly_matches(ev, se) => Some(se),
+                _ => None,
+            })
+            .take(query.limit.unwrap_or(10))
+            .collect::<Vec<&SendEmail>>()
+    }
+
+    /// The threads with an email matching `query`, most recently active first. Threads are
+    /// returned whole, paging is ignored.
+    pub fn threads(&self, query: &EmailQuery) -> Vec<Thread<'_>> {
+        // Without a cursor, there's none to be invalid.
+        let matching = self
+            .query_emails(&EmailQuery {
+                limit: None,
+                cursor: None,
+                ..query.clone()
+            })
+            .map(|page| page.items)
+            .unwrap_or_default();
+        let matching: HashSet<&str> = matching
+            .iter()
+            .filter_map(|email| email.response.message_id.as_deref())
+            .collect();
+        threads::group(self.emails_with_threading())
+            .into_iter()
+            .filter(|thread| {
+                thread.messages.iter().any(|m| {
+                    m.email
+                        .response
+                        .message_id
+                        .as_deref()
+                        .is_some_and(|id| matching.contains(id))
+                })
+            })
+            .collect()
+    }
+
+    /// The thread of the email with `message_id`.
+    pub fn thread(&self, message_id: &str) -> Option<Thread<'_>> {
+        threads::find(self.emails_with_threading(), message_id)
+    }
+
+    /// An overview of everything stored, with send rates up to `now`.
+    pub fn stats(&self, now: Timestamp) -> Stats {
+        Stats::new(&self.events, now)
+    }
+
+    /// Every address emails were sent to, in order, with its total and unread counts.
+    pub fn mailboxes(&self) -> Vec<Mailbox> {
+        let mut mailboxes: BTreeMap<String, Mailbox> = BTreeMap::new();
+        for email in self.get_all_emails() {
+            let message_id = email.response.message_id.as_deref().unwrap_or_default();
+            for address in inbox::recipients(email) {
+                let read = self.read.is_read(&address, message_id);
+                let mailbox = mailboxes.entry(address.clone()).or_insert(Mailbox {
+                    address,
+                    total: 0,
+                    unread: 0,
+                });
+                mailbox.total += 1;
+                mailbox.unread += usize::from(!read);
+            }
+        }
+        mailboxes.into_values().collect()
+    }
+
+    /// The emails matching `query` that went to `address` in To, CC or BCC, with whether
+    /// they've been read there.
+    pub fn inbox(
+        &self,
+        address: &str,
+        query: &EmailQuery,
+    ) -> Result<Page<InboxEmail<'_>>, QueryError> {
+        let page = self.query_emails(&EmailQuery {
+            to: Some(address.to_string()),
+            ..query.clone()
+        })?;
+        Ok(Page {
+            items: page
+                .items
+                .into_iter()
+                .map(|email| InboxEmail {
+                    read: self.read.is_read(
+                        address,
+                        email.response.message_id.as_deref().unwrap_or_default(),
+                    ),
+                    email,
+                })
+                .collect(),
+            next_cursor: page.next_cursor,
+            total: page.total,
+        })
+    }
+
+    /// `email` as it appears in `address`'s inbox, or `None` if it wasn't sent there.
+    pub fn inbox_email<'a>(&self, address: &str, email: &'a SendEmail) -> Option<InboxEmail<'a>> {
+        let message_id = email.response.message_id.as_deref().unwrap_or_default();
+        inbox::recipients(email)
+            .contains(&address.to_lowercase())
+            .then(|| InboxEmail {
+                read: self.read.is_read(address, message_id),
+                email,
+            })
+    }
+
+    /// Marks an email read or unread in `address`'s inbox, returning whether it's there.
+    /// Subscribers get a [`Change::Updated`] for the email's event.
+    pub fn set_read(&mut self, address: &str, message_id: &str, read: bool) -> bool {
+        let event = self.events.iter().find(|ev| match &ev.content {
+            Some(EventContent::SendEmail(email)) => {
+                email.response.message_id.as_deref() == Some(message_id)
+                    && inbox::recipients(email).contains(&address.to_lowercase())
+            }
+            _ => false,
+        });
+        let Some(event) = event.cloned() else {
+            return false;
+        };
+        self.read.set(address, message_id, read);
+        self.notify(Change::Updated(event));
+        true
+    }
+
+    /// Deletes the emails matching `query` along with every other event about them, returning
+    /// how many emails were deleted. An email without a message id has no other events, so
+    /// only its own is deleted.
+    pub fn delete_emails(&mut self, query: &EmailQuery) -> usize {
+        let mut message_ids = vec![];
+        let mut event_ids = vec![];
+        for ev in &self.events {
+            if let Some(EventContent::SendEmail(se)) = &ev.content {
+                if query.matches(ev, se) {
+                    match ev.get_message_id() {
+                        Some(id) => message_ids.push(id.to_string()),
+                        None => event_ids.push(ev.id.clone()),
+                    }
+                }
+            }
+        }
+        self.delete_events_where(|ev| {
+            event_ids.contains(&ev.id)
+                || ev
+                    .get_message_id()
+                    .is_some_and(|id| message_ids.iter().any(|m| m == id))
+        });
+        message_ids.len() + event_ids.len()
+    }
+
+    /// Deletes an email and every other event about it, returning whether it was found.
+    pub fn delete_email(&mut self, message_id: &str) -> bool {
+        if self.get_email_by_message_id(message_id).is_none() {
+            return false;
+        }
+        self.delete_events_where(|ev| ev.get_message_id() == Some(message_id));
+        true
+    }
+
+    fn delete_events_where(&mut self, deleted: impl Fn(&Event) -> bool) {
+        let mut removed = vec![];
+        self.events.retain(|ev| {
+            if deleted(ev) {
+                removed.push(ev.clone());
+                false
+            } else {
+                true
+            }
+        });
+        for event in removed {
+            self.forget(&event);
+            self.notify(Change::Deleted(event));
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use futures::StreamExt;
+    use jiff::SignedDuration;
 
     use super::*;
 
@@ -120,6 +599,132 @@ mod tests {
         assert_eq!(es.events, vec![event]);
     }
 
+    #[tokio::test]
+    async fn push_stored_template() {
+        let mut es = EventStore::new();
+        es.add_templates([(
+            String::from("Welcome"),
+            EmailTemplateContent {
+                subject: Some(String::from("Welcome {{name}}")),
+                text: None,
+                html: None,
+            },
+        )]);
+        let send = |template: &str| {
+            let input = serde_json::from_str(&format!(
+                r#"{{"Content": {{"Template": {{{}, "TemplateData": "{{\"name\": \"Ann\"}}"}}}}}}"#,
+                template
+            ))
+            .unwrap();
+            Event::new(EventContent::SendEmail(SendEmail::new(input)))
+        };
+        let subject = |event: Event| match event.content {
+            Some(EventContent::SendEmail(email)) => email
+                .request
+                .get_rendered_subject()
+                .map(|subject| subject.to_string()),
+            _ => None,
+        };
+
+        let by_name = es.push(send(r#""TemplateName": "Welcome""#)).await;
+        assert_eq!(subject(by_name.unwrap()).as_deref(), Some("Welcome Ann"));
+        let arn = r#""TemplateArn": "arn:aws:ses:us-east-1:123456789012:template/Welcome""#;
+        let by_arn = es.push(send(arn)).await;
+        assert_eq!(subject(by_arn.unwrap()).as_deref(), Some("Welcome Ann"));
+        let unknown = es.push(send(r#""TemplateName": "Other""#)).await;
+        assert_eq!(subject(unknown.unwrap()), None);
+
+        let query = |query: &str| serde_urlencoded::from_str::<EmailQuery>(query).unwrap();
+        let rendered = query("subject=welcome%20ann");
+        assert_eq!(es.query_emails(&rendered).unwrap().total, 2);
+        assert_eq!(es.query_emails(&query("q=ann")).unwrap().total, 2);
+        let near = query("to=b@example.com&subject=welcome%20ann");
+        assert_eq!(es.get_near_misses(&near).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn import() {
+        let mut es = EventStore::new();
+        let at = |timestamp: &str| Event {
+            timestamp: String::from(timestamp),
+            ..Event::empty()
+        };
+        let existing = es.push(at("2024-06-01T00:00:00Z")).await.unwrap();
+        let newer = at("2024-09-01T00:00:00Z");
+        let middle = at("2024-03-01T00:00:00Z");
+        let older = at("2024-01-01T00:00:00Z");
+        let mut changes = es.stream.subscribe();
+        let imported = es
+            .import(vec![
+                older.clone(),
+                newer.clone(),
+                existing.clone(),
+                middle.clone(),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(imported, 3);
+        assert_eq!(es.events, vec![newer, existing, middle, older]);
+        assert_eq!(changes.try_recv(), Ok(Change::Reloaded));
+        assert!(changes.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn retention() {
+        let mut es = EventStore::new().with_retention(Retention {
+            max: Some(2),
+            age: Some(SignedDuration::from_hours(1)),
+        });
+        let old = Event {
+            timestamp: (Timestamp::now() - SignedDuration::from_hours(2)).to_string(),
+            ..Event::empty()
+        };
+        es.push(old.clone()).await.unwrap();
+        assert!(es.events.is_empty());
+
+        let events = [Event::empty(), Event::empty(), Event::empty()];
+        for event in &events {
+            es.push(event.clone()).await.unwrap();
+        }
+        assert_eq!(es.events, vec![events[2].clone(), events[1].clone()]);
+    }
+
+    #[tokio::test]
+    async fn snapshot_restore() {
+        let mut es = EventStore::new();
+        for _ in 0..3 {
+            es.push(Event::empty()).await.unwrap();
+        }
+        let template = EmailTemplateContent {
+            subject: Some(String::from("Hi")),
+            text: None,
+            html: None,
+        };
+        es.add_templates([(String::from("Welcome"), template.clone())]);
+        es.read.set("ann@example.com", "1", true);
+        let snapshot = es.snapshot();
+
+        let mut restored = EventStore::new();
+        restored.push(Event::empty()).await.unwrap();
+        assert_eq!(restored.restore(snapshot.clone()).await.unwrap(), 3);
+        assert_eq!(restored.events, es.events);
+        assert_eq!(restored.templates["Welcome"], template);
+        assert!(restored.read.is_read("ann@example.com", "1"));
+
+        // The configured retention stays, and applies to what's restored.
+        let retention = Retention {
+            max: Some(2),
+            age: None,
+        };
+        let mut limited = EventStore::new().with_retention(retention.clone());
+        assert_eq!(limited.restore(snapshot).await.unwrap(), 2);
+        assert_eq!(limited.retention, retention);
+        assert_eq!(
+            limited.events,
+            es.events.range(..2).cloned().collect::<Vec<_>>()
+        );
+    }
+
     #[tokio::test]
     async fn get_all() {
         let mut es = EventStore::new();
@@ -171,6 +776,142 @@ mod tests {
         assert_eq!(es.events.into_iter().collect::<Vec<Event>>(), vec![ev1]);
     }
 
+    #[tokio::test]
+    async fn delete_email() {
+        let mut es = EventStore::new();
+        let email = Event::new(EventContent::SendEmail(SendEmail::new(
+            serde_json::from_value(serde_json::json!({})).unwrap(),
+        )));
+        let other = Event::empty();
+        _ = es.push(email.clone()).await.unwrap();
+        _ = es.push(other.clone()).await.unwrap();
+        let message_id = email.get_message_id().unwrap();
+        assert!(es.delete_email(message_id));
+        assert!(!es.delete_email(message_id));
+        assert_eq!(es.get_all(), vec![&other]);
+    }
+
+    #[tokio::test]
+    async fn delete_emails_without_message_ids() {
+        let mut es = EventStore::new();
+        let mut email = SendEmail::new(serde_json::from_value(serde_json::json!({})).unwrap());
+        email.response.message_id = None;
+        let other = Event::empty();
+        _ = es.push(Event::new(EventContent::SendEmail(email))).await;
+        _ = es.push(other.clone()).await;
+        assert_eq!(es.delete_emails(&EmailQuery::default()), 1);
+        assert_eq!(es.get_all(), vec![&other]);
+        assert_eq!(es.delete_emails(&EmailQuery::default()), 0);
+    }
+
+    #[tokio::test]
+    async fn thread_follows_deletes() {
+        let mut es = EventStore::new();
+        let root = SendEmail::new(serde_json::from_value(serde_json::json!({})).unwrap());
+        let root_id = root.response.message_id.clone().unwrap();
+        let reply = SendEmail::new(
+            serde_json::from_value(serde_json::json!({
+                "Content": { "Simple": { "Headers": [
+                    { "Name": "In-Reply-To", "Value": threading::ses_message_id(&root_id) }
+                ] } }
+            }))
+            .unwrap(),
+        );
+        let reply_id = reply.response.message_id.clone().unwrap();
+        for email in [root, reply] {
+            _ = es.push(Event::new(EventContent::SendEmail(email))).await;
+        }
+        assert_eq!(es.thread(&root_id).unwrap().messages.len(), 2);
+        assert_eq!(es.threads(&EmailQuery::default()).len(), 1);
+
+        assert!(es.delete_email(&reply_id));
+        assert_eq!(es.thread(&root_id).unwrap().messages.len(), 1);
+        assert!(es.thread(&reply_id).is_none());
+        assert_eq!(es.threading.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn get_events_by_message_id() {
+        let mut es = EventStore::new();
+        let email = SendEmail::new(serde_json::from_value(serde_json::json!({})).unwrap());
+        let first = Event::new(EventContent::SendEmail(email.clone()));
+        let second = Event::new(EventContent::SendEmail(email.clone()));
+        for event in [&first, &Event::empty(), &second] {
+            _ = es.push(event.clone()).await.unwrap();
+        }
+        assert_eq!(
+            es.get_events_by_message_id(email.response.message_id.as_ref().unwrap()),
+            vec![&first, &second]
+        );
+    }
+
+    #[tokio::test]
+    async fn inbox() {
+        let mut es = EventStore::new();
+        for to in [
+            ["a@example.com", "b@example.com"],
+            ["A@example.com", "c@example.com"],
+        ] {
+            let email = SendEmail::new(
+                serde_json::from_value(serde_json::json!({
+                    "Destination": { "ToAddresses": [to[0]], "CcAddresses": [to[1]] }
+                }))
+                .unwrap(),
+            );
+            _ = es
+                .push(Event::new(EventContent::SendEmail(email)))
+                .await
+                .unwrap();
+        }
+        let newest = es.get_all()[0].get_message_id().unwrap().to_string();
+        let mut changes = es.stream.subscribe();
+        assert!(es.set_read("a@EXAMPLE.com", &newest, true));
+        assert!(!es.set_read("b@example.com", &newest, true));
+        assert_eq!(
+            changes.try_recv(),
+            Ok(Change::Updated(es.get_all()[0].clone()))
+        );
+        assert!(changes.try_recv().is_err());
+
+        let counts = es
+            .mailboxes()
+            .into_iter()
+            .map(|m| (m.address, m.total, m.unread))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            counts,
+            [
+                (String::from("a@example.com"), 2, 1),
+                (String::from("b@example.com"), 1, 1),
+                (String::from("c@example.com"), 1, 1),
+            ]
+        );
+        let inbox = es.inbox("a@example.com", &EmailQuery::default()).unwrap();
+        assert_eq!(
+            inbox.items.iter().map(|e| e.read).collect::<Vec<_>>(),
+            [true, false]
+        );
+        assert!(
+            !es.inbox("c@example.com", &EmailQuery::default())
+                .unwrap()
+                .items[0]
+                .read
+        );
+        let email = es.get_email_by_message_id(&newest).unwrap().clone();
+        assert!(es.inbox_email("c@example.com", &email).is_some());
+        assert!(es.inbox_email("b@example.com", &email).is_none());
+
+        // Read state goes with the email, rather than carrying over to one stored again.
+        assert!(es.delete_email(&newest));
+        _ = es.push(Event::new(EventContent::SendEmail(email))).await;
+        assert!(
+            !es.inbox("a@example.com", &EmailQuery::default())
+                .unwrap()
+                .items[0]
+                .read
+        );
+    }
+
     #[tokio::test]
     async fn get_stream() {
         let mut es = EventStore::new();
@@ -182,4 +923,101 @@ mod tests {
         let c = stream.take(2).collect::<Vec<Event>>().await;
         assert_eq!(c, vec![ev1, ev2])
     }
+
+    #[tokio::test]
+    async fn get_changes() {
+        let mut es = EventStore::new().with_retention(Retention {
+            max: Some(2),
+            age: None,
+        });
+        let changes = es.get_changes();
+        let events = [Event::empty(), Event::empty(), Event::empty()];
+        for event in &events {
+            _ = es.push(event.clone()).await.unwrap();
+        }
+        let updated = Event {
+            timestamp: String::from("2024-01-01T00:00:00Z"),
+            ..events[1].clone()
+        };
+        assert!(es.update(updated.clone()));
+        assert!(!es.update(events[0].clone()));
+        es.delete_event(&events[2].id);
+        es.clear();
+        let c = changes.take(7).collect::<Vec<Change>>().await;
+        assert_eq!(
+            c,
+            vec![
+                Change::Added(events[0].clone()),
+                Change::Added(events[1].clone()),
+                Change::Added(events[2].clone()),
+                Change::Deleted(events[0].clone()),
+                Change::Updated(updated),
+                Change::Deleted(events[2].clone()),
+                Change::Cleared,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn query_events_paged() {
+        let mut es = EventStore::new();
+        let ev1 = Event::empty();
+        let ev2 = Event::empty();
+        let ev3 = Event::empty();
+        _ = es.push(ev1.clone()).await.unwrap();
+        _ = es.push(ev2.clone()).await.unwrap();
+        _ = es.push(ev3.clone()).await.unwrap();
+        let mut query = EventQuery {
+            limit: Some(2),
+            ..Default::default()
+        };
+        let page = es.query_events(&query).unwrap();
+        assert_eq!(page.items, vec![&ev3, &ev2]);
+        assert_eq!(
+            page.next_cursor,
+            Some(format!("{}_{}", ev2.timestamp, ev2.id))
+        );
+        assert_eq!(page.total, 3);
+        query.cursor = page.next_cursor;
+        let page = es.query_events(&query).unwrap();
+        assert_eq!(page.items, vec![&ev1]);
+        assert_eq!(page.next_cursor, None);
+        assert_eq!(page.total, 3);
+
+        query.cursor = Some(String::from("deleted"));
+        assert_eq!(es.query_events(&query), Err(QueryError::InvalidCursor));
+    }
+
+    #[tokio::test]
+    async fn query_events_resume_after_deleted_cursor() {
+        let mut es = EventStore::new();
+        let ev1 = Event::empty();
+        let ev2 = Event::empty();
+        let ev3 = Event::empty();
+        _ = es.push(ev1.clone()).await.unwrap();
+        _ = es.push(ev2.clone()).await.unwrap();
+        _ = es.push(ev3.clone()).await.unwrap();
+        let mut query = EventQuery {
+            limit: Some(2),
+            ..Default::default()
+        };
+        query.cursor = es.query_events(&query).unwrap().next_cursor;
+
+        es.delete_event(&ev2.id);
+        let page = es.query_events(&query).unwrap();
+        assert_eq!(page.items, vec![&ev1]);
+        assert_eq!(page.next_cursor, None);
+        assert_eq!(page.total, 2);
+    }
+
+    #[tokio::test]
+    async fn query_events_by_type() {
+        let mut es = EventStore::new();
+        _ = es.push(Event::empty()).await.unwrap();
+        let query = EventQuery {
+            event_type: Some("SendEmail".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(es.query_events(&query).unwrap().items, Vec::<&Event>::new());
+    }
 }
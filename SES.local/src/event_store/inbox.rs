@@ -0,0 +1,130 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use super::send_email::{address, SendEmail};
+
+/// A recipient address and how many of the captured emails went to it.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Mailbox {
+    pub address: String,
+    pub total: usize,
+    pub unread: usize,
+}
+
+/// An email as it appears in one recipient's inbox.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct InboxEmail<'a> {
+    pub read: bool,
+    #[serde(flatten)]
+    pub email: &'a SendEmail,
+}
+
+/// The message ids each recipient has read, by lowercased address.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct ReadState(HashMap<String, HashSet<String>>);
+
+impl ReadState {
+    pub fn is_read(&self, address: &str, message_id: &str) -> bool {
+        self.0
+            .get(&address.to_lowercase())
+            .is_some_and(|read| read.contains(message_id))
+    }
+
+    pub fn set(&mut self, address: &str, message_id: &str, read: bool) {
+        let address = address.to_lowercase();
+        match read {
+            true => {
+                self.0
+                    .entry(address)
+                    .or_default()
+                    .insert(message_id.to_string());
+            }
+            false => {
+                if let Some(read) = self.0.get_mut(&address) {
+                    read.remove(message_id);
+                }
+            }
+        }
+    }
+
+    /// Drops `message_id` from every inbox, once the email is gone.
+    pub fn forget(&mut self, message_id: &str) {
+        self.0.retain(|_, read| {
+            read.remove(message_id);
+            !read.is_empty()
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+/// The lowercased addresses in an email's To, CC and BCC, each once, without display names.
+pub fn recipients(email: &SendEmail) -> Vec<String> {
+    let mut recipients: Vec<String> = vec![];
+    let Some(destination) = email.request.get_to() else {
+        return recipients;
+    };
+    let addresses = [
+        &destination.to_addresses,
+        &destination.cc_addresses,
+        &destination.bcc_addresses,
+    ]
+    .into_iter()
+    .flatten()
+    .flatten();
+    for mailbox in addresses {
+        let address = address::parse(mailbox).address.to_lowercase();
+        if !recipients.contains(&address) {
+            recipients.push(address);
+        }
+    }
+    recipients
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_state_ignores_case() {
+        let mut read = ReadState::default();
+        read.set("Ann@Example.com", "1", true);
+        assert!(read.is_read("ann@example.com", "1"));
+        assert!(!read.is_read("bob@example.com", "1"));
+        read.set("ANN@example.com", "1", false);
+        assert!(!read.is_read("ann@example.com", "1"));
+    }
+
+    #[test]
+    fn read_state_forgets() {
+        let mut read = ReadState::default();
+        read.set("ann@example.com", "1", true);
+        read.set("ann@example.com", "2", true);
+        read.set("bob@example.com", "1", true);
+        read.forget("1");
+        assert!(!read.is_read("ann@example.com", "1"));
+        assert!(read.is_read("ann@example.com", "2"));
+        assert_eq!(read.0.len(), 1);
+    }
+
+    #[test]
+    fn recipients_deduplicated() {
+        let email = SendEmail::new(
+            serde_json::from_value(serde_json::json!({
+                "Destination": {
+                    "ToAddresses": ["Ann <Ann@example.com>", "b@example.com"],
+                    "CcAddresses": ["ann@example.com"],
+                    "BccAddresses": ["c@example.com"]
+                }
+            }))
+            .unwrap(),
+        );
+        assert_eq!(
+            recipients(&email),
+            ["ann@example.com", "b@example.com", "c@example.com"]
+        );
+    }
+}
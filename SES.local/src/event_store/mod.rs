@@ -1,4 +1,16 @@
 mod event;
+#[allow(clippy::module_inception)]
 mod event_store;
+mod inbox;
+mod retention;
+mod snapshot;
+mod stats;
+mod threads;
 pub use event::{send_email, Event, EventContent};
-pub use event_store::EventStore;
+pub use event_store::{Change, EventStore, EventStoreError};
+pub use inbox::{InboxEmail, Mailbox, ReadState};
+pub use retention::Retention;
+pub use ses_local_serde::{EmailQuery, EventQuery, Page, QueryError};
+pub use snapshot::{Snapshot, SnapshotError, SNAPSHOT_VERSION};
+pub use stats::{Breakdown, Bucket, Count, Stats};
+pub use threads::{Thread, ThreadMessage};
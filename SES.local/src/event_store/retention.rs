@@ -0,0 +1,27 @@
+use jiff::{SignedDuration, Timestamp};
+use serde::{Deserialize, Serialize};
+
+use super::Event;
+
+/// Limits on how many events, or how old, the store keeps. Unset means unlimited.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Retention {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub age: Option<SignedDuration>,
+}
+
+impl Retention {
+    /// Whether `event`, at `index` in the newest-first list, should be kept.
+    pub fn keeps(&self, index: usize, event: &Event, now: Timestamp) -> bool {
+        if self.max.is_some_and(|max| index >= max) {
+            return false;
+        }
+        match (self.age, event.timestamp.parse::<Timestamp>()) {
+            (Some(age), Ok(timestamp)) => now.duration_since(timestamp) <= age,
+            _ => true,
+        }
+    }
+}
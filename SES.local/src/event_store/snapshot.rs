@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use ses_serde::types::EmailTemplateContent;
+use thiserror::Error;
+
+use super::{Event, ReadState, Retention};
+
+/// Bumped whenever the snapshot layout, or the `Event` schema inside it, changes in a way old
+/// snapshots don't deserialize into. Add a step to [`migrate`] alongside.
+pub const SNAPSHOT_VERSION: u64 = 2;
+
+/// Everything needed to reproduce the server's state elsewhere.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub version: u64,
+    pub created_at: Timestamp,
+    pub retention: Retention,
+    /// Newest first, as stored.
+    pub events: Vec<Event>,
+    /// Email templates by name, as seeded from fixtures.
+    pub templates: HashMap<String, EmailTemplateContent>,
+    /// Which emails each inbox has read.
+    pub read: ReadState,
+}
+
+#[derive(Error, Debug)]
+pub enum SnapshotError {
+    #[error("snapshot version {0} is newer than this server supports ({SNAPSHOT_VERSION})")]
+    UnsupportedVersion(u64),
+    #[error("snapshot is missing its version")]
+    MissingVersion,
+    #[error("invalid snapshot: {0}")]
+    Invalid(#[from] serde_json::Error),
+}
+
+impl Snapshot {
+    pub fn new(events: Vec<Event>, retention: Retention) -> Self {
+        Snapshot {
+            version: SNAPSHOT_VERSION,
+            created_at: Timestamp::now(),
+            retention,
+            events,
+            templates: HashMap::new(),
+            read: ReadState::default(),
+        }
+    }
+
+    /// Reads a snapshot of any version this server knows, upgrading it to the current one.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let value = migrate(serde_json::from_slice(bytes)?)?;
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+fn migrate(mut value: Value) -> Result<Value, SnapshotError> {
+    loop {
+        let version = match &value {
+            // Before snapshots existed `export` and the data dir held a bare array of events.
+            Value::Array(_) => 0,
+            _ => value["version"]
+                .as_u64()
+                .ok_or(SnapshotError::MissingVersion)?,
+        };
+        value = match version {
+            0 => serde_json::json!({
+                "version": 1,
+                "created_at": Timestamp::now(),
+                "retention": Retention::default(),
+                "events": value,
+            }),
+            // Templates and read state weren't kept before version 2.
+            1 => {
+                value["version"] = 2.into();
+                value["templates"] = serde_json::json!({});
+                value["read"] = serde_json::json!({});
+                value
+            }
+            SNAPSHOT_VERSION => return Ok(value),
+            v => return Err(SnapshotError::UnsupportedVersion(v)),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let snapshot = Snapshot::new(
+            vec![Event::empty(), Event::empty()],
+            Retention {
+                max: Some(10),
+                age: None,
+            },
+        );
+        let bytes = serde_json::to_vec(&snapshot).unwrap();
+        assert_eq!(Snapshot::from_slice(&bytes).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn migrates_event_arrays() {
+        let events = vec![Event::empty()];
+        let bytes = serde_json::to_vec(&events).unwrap();
+        let snapshot = Snapshot::from_slice(&bytes).unwrap();
+        assert_eq!(snapshot.version, SNAPSHOT_VERSION);
+        assert_eq!(snapshot.events, events);
+        assert_eq!(snapshot.retention, Retention::default());
+    }
+
+    #[test]
+    fn migrates_version_1() {
+        let bytes = br#"{"version": 1, "created_at": "2024-06-01T00:00:00Z", "retention": {}, "events": []}"#;
+        let snapshot = Snapshot::from_slice(bytes).unwrap();
+        assert_eq!(snapshot.version, SNAPSHOT_VERSION);
+        assert!(snapshot.templates.is_empty());
+        assert_eq!(snapshot.read, ReadState::default());
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        let err = Snapshot::from_slice(br#"{"version": 99, "events": []}"#).unwrap_err();
+        assert!(matches!(err, SnapshotError::UnsupportedVersion(99)));
+        let err = Snapshot::from_slice(br#"{"events": []}"#).unwrap_err();
+        assert!(matches!(err, SnapshotError::MissingVersion));
+    }
+}
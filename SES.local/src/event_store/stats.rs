@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+
+use jiff::{SignedDuration, Timestamp};
+use serde::Serialize;
+
+use super::{
+    inbox,
+    send_email::{address, SendEmail},
+    Event, EventContent,
+};
+
+// How far back the send rate charts go, in buckets.
+const MINUTES: usize = 60;
+const HOURS: usize = 24;
+// Breakdowns beyond this many keys are cut, with the rest counted as other.
+const TOP: usize = 10;
+
+/// An overview of what the store captured.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Stats {
+    pub generated_at: Timestamp,
+    pub events: usize,
+    pub emails: usize,
+    /// Sends in each of the last 60 minutes, oldest first.
+    pub per_minute: Vec<Bucket>,
+    /// Sends in each of the last 24 hours, oldest first.
+    pub per_hour: Vec<Bucket>,
+    pub by_sender: Breakdown,
+    pub by_recipient_domain: Breakdown,
+    pub by_configuration_set: Breakdown,
+    pub by_tag: Breakdown,
+    pub by_type: Breakdown,
+    pub top_subjects: Breakdown,
+    /// Always `None`: only sends are captured, so bounces and complaints can't be counted.
+    pub bounces: Option<usize>,
+    pub complaints: Option<usize>,
+    pub bounce_rate: Option<f64>,
+    pub complaint_rate: Option<f64>,
+}
+
+/// The sends in the minute or hour starting at `start`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Bucket {
+    pub start: Timestamp,
+    pub count: usize,
+}
+
+/// The most frequent keys with their counts, most frequent first.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct Breakdown {
+    pub top: Vec<Count>,
+    /// Emails counted under keys that didn't make the top.
+    pub other: usize,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Count {
+    pub key: String,
+    pub count: usize,
+}
+
+impl Stats {
+    /// Stats for `events`, with the send rates counted back from `now`.
+    pub fn new<'a>(events: impl IntoIterator<Item = &'a Event>, now: Timestamp) -> Self {
+        let mut stats = Tally::default();
+        let mut per_minute = vec![0; MINUTES];
+        let mut per_hour = vec![0; HOURS];
+        let (minute, hour) = (floor(now, 60), floor(now, 3600));
+        let mut event_count = 0;
+        for event in events {
+            event_count += 1;
+            let Some(EventContent::SendEmail(email)) = &event.content else {
+                continue;
+            };
+            if let Ok(timestamp) = event.timestamp.parse::<Timestamp>() {
+                count_back(&mut per_minute, minute, timestamp, 60);
+                count_back(&mut per_hour, hour, timestamp, 3600);
+            }
+            stats.add(email);
+        }
+        Stats {
+            generated_at: now,
+            events: event_count,
+            emails: stats.emails,
+            per_minute: buckets(per_minute, minute, 60),
+            per_hour: buckets(per_hour, hour, 3600),
+            by_sender: breakdown(stats.senders),
+            by_recipient_domain: breakdown(stats.domains),
+            by_configuration_set: breakdown(stats.configuration_sets),
+            by_tag: breakdown(stats.tags),
+            by_type: breakdown(stats.types),
+            top_subjects: breakdown(stats.subjects),
+            bounces: None,
+            complaints: None,
+            bounce_rate: None,
+            complaint_rate: None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Tally {
+    emails: usize,
+    senders: HashMap<String, usize>,
+    domains: HashMap<String, usize>,
+    configuration_sets: HashMap<String, usize>,
+    tags: HashMap<String, usize>,
+    types: HashMap<String, usize>,
+    subjects: HashMap<String, usize>,
+}
+
+impl Tally {
+    fn add(&mut self, email: &SendEmail) {
+        self.emails += 1;
+        let request = &email.request;
+        let input = request.get_input();
+        if let Some(from) = request.get_from() {
+            increment(
+                &mut self.senders,
+                address::parse(from).address.to_lowercase(),
+            );
+        }
+        let mut domains: Vec<String> = vec![];
+        for recipient in inbox::recipients(email) {
+            if let Some((_, domain)) = recipient.rsplit_once('@') {
+                if !domains.iter().any(|d| d == domain) {
+                    domains.push(domain.to_string());
+                }
+            }
+        }
+        for domain in domains {
+            increment(&mut self.domains, domain);
+        }
+        if let Some(configuration_set) = &input.configuration_set_name {
+            increment(&mut self.configuration_sets, configuration_set.clone());
+        }
+        for tag in input.email_tags.iter().flatten() {
+            increment(&mut self.tags, format!("{}={}", tag.name, tag.value));
+        }
+        increment(&mut self.types, request.get_tag().to_string());
+        if let Some(subject) = request.get_rendered_subject() {
+            increment(&mut self.subjects, subject.into_owned());
+        }
+    }
+}
+
+fn increment(counts: &mut HashMap<String, usize>, key: String) {
+    *counts.entry(key).or_default() += 1;
+}
+
+fn breakdown(counts: HashMap<String, usize>) -> Breakdown {
+    let mut counts: Vec<Count> = counts
+        .into_iter()
+        .map(|(key, count)| Count { key, count })
+        .collect();
+    // Ties go alphabetically so the order doesn't change between requests.
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)));
+    let other = counts.iter().skip(TOP).map(|c| c.count).sum();
+    counts.truncate(TOP);
+    Breakdown { top: counts, other }
+}
+
+fn floor(timestamp: Timestamp, seconds: i64) -> i64 {
+    timestamp.as_second().div_euclid(seconds)
+}
+
+// Counts `timestamp` in the bucket it falls in, the last bucket being the one `current` is in.
+fn count_back(counts: &mut [usize], current: i64, timestamp: Timestamp, seconds: i64) {
+    let ago = current - floor(timestamp, seconds);
+    if let Ok(ago) = usize::try_from(ago) {
+        if ago < counts.len() {
+            let n = counts.len() - 1 - ago;
+            counts[n] += 1;
+        }
+    }
+}
+
+fn buckets(counts: Vec<usize>, current: i64, seconds: i64) -> Vec<Bucket> {
+    let len = counts.len() as i64;
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(n, count)| Bucket {
+            start: Timestamp::UNIX_EPOCH
+                + SignedDuration::from_secs((current - len + 1 + n as i64) * seconds),
+            count,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(timestamp: &str, email: serde_json::Value) -> Event {
+        Event {
+            timestamp: timestamp.to_string(),
+            ..Event::new(EventContent::SendEmail(SendEmail::new(
+                serde_json::from_value(email).unwrap(),
+            )))
+        }
+    }
+
+    #[test]
+    fn aggregates() {
+        let now: Timestamp = "2024-01-01T12:30:30Z".parse().unwrap();
+        let events = [
+            event(
+                "2024-01-01T12:30:10Z",
+                serde_json::json!({
+                    "FromEmailAddress": "Shop <Shop@example.com>",
+                    "Destination": {
+                        "ToAddresses": ["a@one.test", "b@one.test"],
+                        "CcAddresses": ["c@two.test"]
+                    },
+                    "Content": { "Simple": { "Subject": { "Data": "Receipt" } } },
+                    "ConfigurationSetName": "default",
+                    "EmailTags": [{ "Name": "flow", "Value": "receipt" }]
+                }),
+            ),
+            event(
+                "2024-01-01T12:29:59Z",
+                serde_json::json!({
+                    "FromEmailAddress": "shop@example.com",
+                    "Destination": { "ToAddresses": ["d@one.test"] },
+                    "Content": { "Simple": { "Subject": { "Data": "Receipt" } } }
+                }),
+            ),
+            event(
+                "2024-01-01T10:00:00Z",
+                serde_json::json!({
+                    "FromEmailAddress": "news@example.com",
+                    "Content": { "Simple": { "Subject": { "Data": "News" } } }
+                }),
+            ),
+            Event::empty(),
+        ];
+        let stats = Stats::new(&events, now);
+        fn counts(breakdown: &Breakdown) -> Vec<(&str, usize)> {
+            breakdown
+                .top
+                .iter()
+                .map(|c| (c.key.as_str(), c.count))
+                .collect()
+        }
+
+        assert_eq!((stats.events, stats.emails), (4, 3));
+        assert_eq!(
+            counts(&stats.by_sender),
+            [("shop@example.com", 2), ("news@example.com", 1)]
+        );
+        assert_eq!(
+            counts(&stats.by_recipient_domain),
+            [("one.test", 2), ("two.test", 1)]
+        );
+        assert_eq!(counts(&stats.by_configuration_set), [("default", 1)]);
+        assert_eq!(counts(&stats.by_tag), [("flow=receipt", 1)]);
+        assert_eq!(counts(&stats.by_type), [("Simple", 3)]);
+        assert_eq!(counts(&stats.top_subjects), [("Receipt", 2), ("News", 1)]);
+
+        assert_eq!(stats.per_minute.len(), 60);
+        let minute = stats.per_minute.last().unwrap();
+        assert_eq!(minute.start, "2024-01-01T12:30:00Z".parse().unwrap());
+        assert_eq!(minute.count, 1);
+        assert_eq!(stats.per_minute[58].count, 1);
+        assert_eq!(stats.per_hour.len(), 24);
+        assert_eq!(stats.per_hour[23].count, 2);
+        assert_eq!(stats.per_hour[21].count, 1);
+        assert_eq!(
+            stats.per_hour[21].start,
+            "2024-01-01T10:00:00Z".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn breakdown_cuts_to_top() {
+        let counts = (0..TOP + 2)
+            .map(|n| (format!("key-{:02}", n), n + 1))
+            .collect();
+        let breakdown = breakdown(counts);
+        assert_eq!(breakdown.top.len(), TOP);
+        assert_eq!(breakdown.top[0].key, format!("key-{:02}", TOP + 1));
+        assert_eq!(breakdown.other, 1 + 2);
+    }
+}
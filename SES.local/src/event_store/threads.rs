@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use super::send_email::{threading::ThreadHeaders, SendEmail};
+
+/// Emails linked by their Message-ID, In-Reply-To and References headers, oldest first.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Thread<'a> {
+    /// The message id of the thread's first email.
+    pub id: String,
+    pub subject: Option<String>,
+    pub messages: Vec<ThreadMessage<'a>>,
+}
+
+/// An email in a thread, with where it sits in the conversation.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ThreadMessage<'a> {
+    pub threading: ThreadHeaders,
+    /// The message id of the captured email this one replies to.
+    pub parent: Option<String>,
+    pub depth: usize,
+    /// Referenced ids that match no captured email.
+    pub missing: Vec<String>,
+    #[serde(flatten)]
+    pub email: &'a SendEmail,
+}
+
+impl Thread<'_> {
+    pub fn contains(&self, message_id: &str) -> bool {
+        self.messages
+            .iter()
+            .any(|m| m.email.response.message_id.as_deref() == Some(message_id))
+    }
+}
+
+/// `emails`, newest first with their threading headers, grouped into threads with the most
+/// recently active first.
+///
+/// Emails that refer to the same id share a thread even when the email with that id wasn't
+/// captured, so replies to one message sent elsewhere still end up together.
+pub fn group<'a>(emails: Vec<(&'a SendEmail, &'a ThreadHeaders)>) -> Vec<Thread<'a>> {
+    let grouping = Grouping::new(&emails);
+    grouping
+        .groups
+        .iter()
+        .map(|members| grouping.thread(members))
+        .collect()
+}
+
+/// The thread among `emails`, as for [`group`], of the email with `message_id`.
+pub fn find<'a>(
+    emails: Vec<(&'a SendEmail, &'a ThreadHeaders)>,
+    message_id: &str,
+) -> Option<Thread<'a>> {
+    let n = emails
+        .iter()
+        .position(|(email, _)| email.response.message_id.as_deref() == Some(message_id))?;
+    let grouping = Grouping::new(&emails);
+    grouping
+        .groups
+        .iter()
+        .find(|members| members.contains(&n))
+        .map(|members| grouping.thread(members))
+}
+
+struct Grouping<'e, 'a> {
+    emails: &'e [(&'a SendEmail, &'a ThreadHeaders)],
+    owners: HashMap<&'a str, usize>,
+    /// Indexes into `emails`, newest first, for each thread.
+    groups: Vec<Vec<usize>>,
+}
+
+impl<'e, 'a> Grouping<'e, 'a> {
+    fn new(emails: &'e [(&'a SendEmail, &'a ThreadHeaders)]) -> Self {
+        let mut sets = DisjointSet::new(emails.len());
+        let mut seen: HashMap<&str, usize> = HashMap::new();
+        for (n, (_, threading)) in emails.iter().enumerate() {
+            for id in threading.ids().chain(threading.referenced()) {
+                match seen.get(id.as_str()) {
+                    Some(&other) => sets.union(n, other),
+                    None => {
+                        seen.insert(id, n);
+                    }
+                }
+            }
+        }
+        let owners = emails
+            .iter()
+            .enumerate()
+            .flat_map(|(n, (_, threading))| threading.ids().map(move |id| (id.as_str(), n)))
+            .collect();
+
+        let mut groups: Vec<Vec<usize>> = vec![];
+        let mut group_of: HashMap<usize, usize> = HashMap::new();
+        for n in 0..emails.len() {
+            let root = sets.find(n);
+            let group = *group_of.entry(root).or_insert_with(|| {
+                groups.push(vec![]);
+                groups.len() - 1
+            });
+            groups[group].push(n);
+        }
+        Grouping {
+            emails,
+            owners,
+            groups,
+        }
+    }
+
+    fn thread(&self, members: &[usize]) -> Thread<'a> {
+        let (emails, owners) = (self.emails, &self.owners);
+        let members: Vec<usize> = members.iter().rev().copied().collect();
+        let parents: HashMap<usize, usize> = members
+            .iter()
+            .filter_map(|&n| {
+                emails[n]
+                    .1
+                    .referenced()
+                    .filter_map(|id| owners.get(id.as_str()))
+                    .find(|&&parent| parent != n)
+                    .map(|&parent| (n, parent))
+            })
+            .collect();
+        let messages = members
+            .iter()
+            .map(|&n| ThreadMessage {
+                threading: emails[n].1.clone(),
+                parent: parents
+                    .get(&n)
+                    .and_then(|&p| emails[p].0.response.message_id.clone()),
+                depth: depth(&parents, n),
+                missing: missing(emails[n].1, owners),
+                email: emails[n].0,
+            })
+            .collect::<Vec<_>>();
+        let first = emails[members[0]].0;
+        Thread {
+            id: first.response.message_id.clone().unwrap_or_default(),
+            subject: first.request.get_rendered_subject().map(|s| s.into_owned()),
+            messages,
+        }
+    }
+}
+
+fn missing(threading: &ThreadHeaders, owners: &HashMap<&str, usize>) -> Vec<String> {
+    let mut missing: Vec<String> = vec![];
+    for id in threading.referenced() {
+        if !owners.contains_key(id.as_str()) && !missing.contains(id) {
+            missing.push(id.clone());
+        }
+    }
+    missing
+}
+
+// Replies that refer to each other in a loop stop once every email has been counted.
+fn depth(parents: &HashMap<usize, usize>, mut n: usize) -> usize {
+    let mut depth = 0;
+    while let Some(&parent) = parents.get(&n) {
+        if depth == parents.len() {
+            break;
+        }
+        depth += 1;
+        n = parent;
+    }
+    depth
+}
+
+struct DisjointSet(Vec<usize>);
+
+impl DisjointSet {
+    fn new(size: usize) -> Self {
+        DisjointSet((0..size).collect())
+    }
+
+    fn find(&mut self, mut n: usize) -> usize {
+        while self.0[n] != n {
+            self.0[n] = self.0[self.0[n]];
+            n = self.0[n];
+        }
+        n
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.0[a] = b;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_store::send_email::threading;
+
+    fn email(subject: &str, headers: &[(&str, &str)]) -> SendEmail {
+        let headers: Vec<_> = headers
+            .iter()
+            .map(|(name, value)| serde_json::json!({ "Name": name, "Value": value }))
+            .collect();
+        SendEmail::new(
+            serde_json::from_value(serde_json::json!({
+                "Content": {
+                    "Simple": { "Subject": { "Data": subject }, "Headers": headers }
+                }
+            }))
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn groups_replies() {
+        let root = email("Ticket", &[]);
+        let root_id = threading::ses_message_id(root.response.message_id.as_ref().unwrap());
+        let reply = email("Re: Ticket", &[("In-Reply-To", &root_id)]);
+        let reply_id = threading::ses_message_id(reply.response.message_id.as_ref().unwrap());
+        let references = format!("{} {}", root_id, reply_id);
+        let follow_up = email(
+            "Re: Ticket",
+            &[("In-Reply-To", &reply_id), ("References", &references)],
+        );
+        let other = email("Other", &[]);
+        // Two replies to a message that wasn't captured.
+        let orphan_a = email("Re: Lost", &[("In-Reply-To", "<lost@example.com>")]);
+        let orphan_b = email("Re: Lost", &[("References", "<lost@example.com>")]);
+
+        let emails = [&orphan_b, &orphan_a, &other, &follow_up, &reply, &root];
+        let headers: Vec<ThreadHeaders> = emails.iter().map(|e| threading::headers(e)).collect();
+        let threads = group(emails.into_iter().zip(&headers).collect());
+        assert_eq!(threads.len(), 3);
+        assert_eq!(threads[0].id, orphan_a.response.message_id.clone().unwrap());
+        assert_eq!(threads[0].subject.as_deref(), Some("Re: Lost"));
+        assert_eq!(threads[0].messages.len(), 2);
+        assert_eq!(threads[0].messages[0].missing, ["<lost@example.com>"]);
+        assert_eq!(threads[0].messages[1].parent, None);
+        assert_eq!(threads[1].messages.len(), 1);
+
+        let ticket = &threads[2];
+        assert_eq!(ticket.id, root.response.message_id.clone().unwrap());
+        assert_eq!(ticket.subject.as_deref(), Some("Ticket"));
+        assert_eq!(
+            ticket
+                .messages
+                .iter()
+                .map(|m| (m.email, m.parent.clone(), m.depth))
+                .collect::<Vec<_>>(),
+            [
+                (&root, None, 0),
+                (&reply, root.response.message_id.clone(), 1),
+                (&follow_up, reply.response.message_id.clone(), 2),
+            ]
+        );
+        assert!(ticket.messages.iter().all(|m| m.missing.is_empty()));
+        assert!(ticket.contains(follow_up.response.message_id.as_ref().unwrap()));
+
+        let reply_id = reply.response.message_id.as_ref().unwrap();
+        let found = find(emails.into_iter().zip(&headers).collect(), reply_id);
+        assert_eq!(found.as_ref(), Some(ticket));
+        assert_eq!(
+            find(emails.into_iter().zip(&headers).collect(), "unknown"),
+            None
+        );
+    }
+}
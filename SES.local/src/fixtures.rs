@@ -0,0 +1,249 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use ses_serde::{operations::send_email::SendEmailInput, types::EmailTemplateContent};
+use thiserror::Error;
+
+use crate::event_store::{
+    send_email::{eml, SendEmail},
+    Event, EventContent,
+};
+
+// Subdirectories of the fixtures directory.
+const EMAILS_DIR: &str = "emails";
+const TEMPLATES_DIR: &str = "templates";
+// Resources SES has that SES.local doesn't store. Fixtures for them are out of scope, so these
+// are skipped with a warning of their own rather than the generic one.
+const UNSUPPORTED_DIRS: [&str; 3] = ["identities", "configuration-sets", "suppressions"];
+
+#[derive(Error, Debug)]
+pub enum FixtureError {
+    #[error("reading fixture {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("fixture {path} is not {expected}: {source}")]
+    Json {
+        path: PathBuf,
+        expected: &'static str,
+        source: serde_json::Error,
+    },
+    #[error("fixture {0} is not an RFC 5322 message")]
+    Eml(PathBuf),
+}
+
+/// What a fixtures directory seeds the server with.
+#[derive(Debug, Default)]
+pub struct Fixtures {
+    /// Email templates by name, for emails sent with a `TemplateName`.
+    pub templates: HashMap<String, EmailTemplateContent>,
+    pub emails: Vec<Event>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Inputs {
+    Many(Vec<SendEmailInput>),
+    One(Box<SendEmailInput>),
+}
+
+/// The templates in `<dir>/templates` and the emails in `<dir>/emails`, in file name order.
+///
+/// Each template is a `.json` file holding its `TemplateContent` (`Subject`, `Html` and
+/// `Text`), named after the file. Each email is a `.json` file holding a `SendEmailInput` (or
+/// an array of them) or an `.eml` file holding a raw message. Event and message ids are derived
+/// from the file names, so seeding twice doesn't duplicate them and links to an email keep
+/// working across starts.
+///
+/// Identities, configuration sets and suppressions aren't seeded; their directories are
+/// skipped with a warning.
+pub fn load(dir: &Path) -> Result<Fixtures, FixtureError> {
+    for path in read_dir(dir)? {
+        match path.file_name().and_then(|name| name.to_str()) {
+            Some(EMAILS_DIR | TEMPLATES_DIR) => {}
+            Some(name) if UNSUPPORTED_DIRS.contains(&name) => tracing::warn!(
+                "skipping fixtures in {}, SES.local doesn't store {}",
+                path.display(),
+                name.replace('-', " ")
+            ),
+            _ => tracing::warn!(
+                "skipping fixture {}, only {}/ and {}/ are supported",
+                path.display(),
+                TEMPLATES_DIR,
+                EMAILS_DIR
+            ),
+        }
+    }
+    Ok(Fixtures {
+        templates: templates(&dir.join(TEMPLATES_DIR))?,
+        emails: emails(&dir.join(EMAILS_DIR))?,
+    })
+}
+
+fn templates(dir: &Path) -> Result<HashMap<String, EmailTemplateContent>, FixtureError> {
+    let mut templates = HashMap::new();
+    if !dir.is_dir() {
+        return Ok(templates);
+    }
+    for path in read_dir(dir)? {
+        if path.extension().is_none_or(|ext| ext != "json") {
+            tracing::warn!("skipping fixture {}, expected .json", path.display());
+            continue;
+        }
+        let name = file_stem(&path);
+        let content =
+            serde_json::from_slice(&read(&path)?).map_err(|source| FixtureError::Json {
+                path,
+                expected: "an EmailTemplateContent",
+                source,
+            })?;
+        templates.insert(name, content);
+    }
+    Ok(templates)
+}
+
+fn emails(dir: &Path) -> Result<Vec<Event>, FixtureError> {
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut events = vec![];
+    for path in read_dir(dir)? {
+        let name = file_stem(&path);
+        let inputs = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => match serde_json::from_slice(&read(&path)?) {
+                Ok(Inputs::One(input)) => vec![*input],
+                Ok(Inputs::Many(inputs)) => inputs,
+                Err(source) => {
+                    return Err(FixtureError::Json {
+                        path,
+                        expected: "a SendEmailInput",
+                        source,
+                    })
+                }
+            },
+            Some("eml") => vec![eml::parse(&read(&path)?).ok_or(FixtureError::Eml(path))?],
+            _ => {
+                tracing::warn!(
+                    "skipping fixture {}, expected .json or .eml",
+                    path.display()
+                );
+                continue;
+            }
+        };
+        let many = inputs.len() > 1;
+        for (i, input) in inputs.into_iter().enumerate() {
+            let id = match many {
+                true => format!("fixture-{}-{}", name, i),
+                false => format!("fixture-{}", name),
+            };
+            let mut email = SendEmail::new(input);
+            email.response.message_id = Some(id.clone());
+            let mut event = Event::new(EventContent::SendEmail(email));
+            event.id = id;
+            events.push(event);
+        }
+    }
+    Ok(events)
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, FixtureError> {
+    fs::read(path).map_err(|source| FixtureError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn read_dir(dir: &Path) -> Result<Vec<PathBuf>, FixtureError> {
+    let io = |source| FixtureError::Io {
+        path: dir.to_path_buf(),
+        source,
+    };
+    let mut paths = fs::read_dir(dir)
+        .map_err(io)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<PathBuf>, _>>()
+        .map_err(io)?;
+    paths.sort();
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_json_and_eml() {
+        let dir = std::env::temp_dir().join(format!("ses-local-fixtures-{}", std::process::id()));
+        let emails = dir.join(EMAILS_DIR);
+        fs::create_dir_all(&emails).unwrap();
+        let templates = dir.join(TEMPLATES_DIR);
+        fs::create_dir_all(&templates).unwrap();
+        fs::write(
+            templates.join("Welcome.json"),
+            r#"{"Subject": "Welcome {{name}}", "Html": "<p>Hi {{name}}</p>"}"#,
+        )
+        .unwrap();
+        fs::write(
+            emails.join("1-welcome.json"),
+            r#"{"Destination": {"ToAddresses": ["a@example.com"]}, "Content": {"Simple": {"Subject": {"Data": "Welcome"}}}}"#,
+        )
+        .unwrap();
+        fs::write(
+            emails.join("2-batch.json"),
+            r#"[{"Destination": {"ToAddresses": ["b@example.com"]}}, {"Destination": {"ToAddresses": ["c@example.com"]}}]"#,
+        )
+        .unwrap();
+        fs::write(
+            emails.join("3-reset.eml"),
+            "From: s@example.com\r\nTo: d@example.com\r\nSubject: Reset\r\n\r\nHi\r\n",
+        )
+        .unwrap();
+        fs::write(emails.join("README.md"), "ignored").unwrap();
+
+        let fixtures = load(&dir).unwrap();
+        assert_eq!(
+            fixtures.templates["Welcome"].subject.as_deref(),
+            Some("Welcome {{name}}")
+        );
+        let ids = fixtures
+            .emails
+            .iter()
+            .map(|e| e.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            [
+                "fixture-1-welcome",
+                "fixture-2-batch-0",
+                "fixture-2-batch-1",
+                "fixture-3-reset"
+            ]
+        );
+        let message_ids = fixtures
+            .emails
+            .iter()
+            .map(|e| e.get_message_id())
+            .collect::<Vec<_>>();
+        assert_eq!(message_ids, ids.into_iter().map(Some).collect::<Vec<_>>());
+
+        fs::write(emails.join("4-broken.json"), "{").unwrap();
+        assert!(matches!(load(&dir), Err(FixtureError::Json { .. })));
+        fs::remove_file(emails.join("4-broken.json")).unwrap();
+
+        fs::create_dir_all(dir.join("suppressions")).unwrap();
+        assert_eq!(load(&dir).unwrap().emails.len(), 4);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,26 @@
+use std::sync::Arc;
+
+use axum::Router;
+use event_store::EventStore;
+use tokio::sync::RwLock;
+
+pub mod cli;
+pub mod conf;
+pub mod event_store;
+pub mod fixtures;
+pub mod mailbox;
+mod page_template;
+pub mod routes;
+mod server;
+mod tls;
+
+pub use server::{Server, ServerBuilder, ServerHandle};
+
+pub type AppEventStore = Arc<RwLock<EventStore>>;
+#[derive(Clone)]
+pub struct AppState {
+    // that holds some api specific state
+    pub event_store: AppEventStore,
+}
+
+pub type AppStateRouter = Router<AppState>;
@@ -0,0 +1,304 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use jiff::Timestamp;
+use tokio::{sync::broadcast::error::RecvError, task::JoinHandle};
+
+use crate::{
+    event_store::{send_email::eml, Change, Event, EventContent},
+    AppEventStore,
+};
+
+// Envelope sender for the mbox separator line when an email has no From address.
+const UNKNOWN_SENDER: &str = "MAILER-DAEMON";
+
+/// Writes the emails among `events` to `out` in mboxrd format, in the order given.
+pub fn write_mbox<'a>(
+    out: &mut impl Write,
+    events: impl IntoIterator<Item = &'a Event>,
+) -> io::Result<()> {
+    for event in events {
+        let Some(EventContent::SendEmail(email)) = &event.content else {
+            continue;
+        };
+        let date = timestamp(event);
+        let sender = email.request.get_from().unwrap_or(UNKNOWN_SENDER);
+        writeln!(
+            out,
+            "From {} {}",
+            sender,
+            date.strftime("%a %b %e %H:%M:%S %Y")
+        )?;
+        let message = eml::build(email, date);
+        for line in message.split_inclusive(|b| *b == b'\n') {
+            let line = line.strip_suffix(b"\n").unwrap_or(line);
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            if is_from_line(line) {
+                out.write_all(b">")?;
+            }
+            out.write_all(line)?;
+            out.write_all(b"\n")?;
+        }
+        out.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// The messages in an upload: each message of an mbox file, or the upload itself otherwise.
+pub fn messages(upload: &[u8]) -> Vec<Vec<u8>> {
+    if !upload.starts_with(b"From ") {
+        return vec![upload.to_vec()];
+    }
+    let mut messages: Vec<Vec<u8>> = vec![];
+    let mut previous_blank = true;
+    for line in upload.split_inclusive(|b| *b == b'\n') {
+        let content = line.strip_suffix(b"\n").unwrap_or(line);
+        let content = content.strip_suffix(b"\r").unwrap_or(content);
+        if previous_blank && content.starts_with(b"From ") {
+            messages.push(vec![]);
+        } else if let Some(message) = messages.last_mut() {
+            match is_from_line(content) && content.starts_with(b">") {
+                true => message.extend_from_slice(&line[1..]),
+                false => message.extend_from_slice(line),
+            }
+        }
+        previous_blank = content.is_empty();
+    }
+    // Drop the blank line that separates each message from the next one's "From " line.
+    for message in &mut messages {
+        if message.ends_with(b"\r\n\r\n") {
+            message.truncate(message.len() - 2);
+        } else if message.ends_with(b"\n\n") {
+            message.pop();
+        }
+    }
+    messages
+}
+
+/// A Maildir emails are delivered into, created with its `tmp`, `new` and `cur` subdirectories.
+#[derive(Debug, Clone)]
+pub struct Maildir {
+    path: PathBuf,
+}
+
+impl Maildir {
+    pub fn create(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        for dir in ["tmp", "new", "cur"] {
+            fs::create_dir_all(path.join(dir))?;
+        }
+        Ok(Maildir { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Delivers the email in `event` to `new/`, returning where, or `None` if it isn't an email.
+    ///
+    /// File names are derived from the event id, so delivering the same event again replaces it.
+    pub fn deliver(&self, event: &Event) -> io::Result<Option<PathBuf>> {
+        let Some(EventContent::SendEmail(email)) = &event.content else {
+            return Ok(None);
+        };
+        let date = timestamp(event);
+        let name = file_name(event);
+        let tmp = self.path.join("tmp").join(&name);
+        let new = self.path.join("new").join(&name);
+        fs::write(&tmp, eml::build(email, date))?;
+        fs::rename(&tmp, &new)?;
+        Ok(Some(new))
+    }
+
+    /// Delivers the emails among `events` that are in neither `new/` nor `cur/`, where mail
+    /// clients move what they've seen, returning how many.
+    pub fn resync<'a>(&self, events: impl IntoIterator<Item = &'a Event>) -> io::Result<usize> {
+        let mut delivered = HashSet::new();
+        for dir in ["new", "cur"] {
+            for entry in fs::read_dir(self.path.join(dir))? {
+                let name = entry?.file_name().to_string_lossy().into_owned();
+                // Clients append flags after a colon, as in `name:2,S`.
+                let name = name.split(':').next().unwrap_or_default().to_string();
+                delivered.insert(name);
+            }
+        }
+        let mut count = 0;
+        for event in events {
+            if !delivered.contains(&file_name(event)) && self.deliver(event)?.is_some() {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Delivers every email captured from now on, until the store's stream ends.
+    ///
+    /// Imports and restores, and falling too far behind the store to tell which emails were
+    /// missed, resync the Maildir with every stored email instead.
+    pub async fn sink(self, event_store: &AppEventStore) -> JoinHandle<()> {
+        let event_store = event_store.clone();
+        let mut changes = event_store.read().await.subscribe();
+        tokio::spawn(async move {
+            loop {
+                let result = match changes.recv().await {
+                    Ok(Change::Added(event)) => self.blocking(move |m| m.deliver(&event)).await,
+                    Ok(Change::Reloaded) | Err(RecvError::Lagged(_)) => {
+                        tracing::info!("resyncing {}", self.path.display());
+                        let events: Vec<Event> = event_store
+                            .read()
+                            .await
+                            .get_all()
+                            .into_iter()
+                            .cloned()
+                            .collect();
+                        self.blocking(move |m| m.resync(&events).map(|_| None))
+                            .await
+                    }
+                    Ok(_) => continue,
+                    Err(RecvError::Closed) => break,
+                };
+                if let Err(err) = result {
+                    tracing::warn!("delivering to {}: {}", self.path.display(), err);
+                }
+            }
+        })
+    }
+
+    // Runs `f` off the async runtime, since delivering writes files.
+    async fn blocking<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&Maildir) -> io::Result<T> + Send + 'static,
+    ) -> io::Result<T> {
+        let maildir = self.clone();
+        tokio::task::spawn_blocking(move || f(&maildir))
+            .await
+            .map_err(io::Error::other)?
+    }
+}
+
+// mboxrd quotes lines matching `>*From ` with one more '>', so readers can strip exactly one.
+fn is_from_line(line: &[u8]) -> bool {
+    let start = line.iter().position(|b| *b != b'>').unwrap_or(line.len());
+    line[start..].starts_with(b"From ")
+}
+
+fn file_name(event: &Event) -> String {
+    format!("{}.{}.ses-local", timestamp(event).as_second(), event.id)
+}
+
+fn timestamp(event: &Event) -> Timestamp {
+    event.timestamp.parse().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use mail_parser::MessageParser;
+    use tokio::sync::RwLock;
+
+    use super::*;
+    use crate::event_store::{send_email::SendEmail, EventStore};
+
+    fn create_event(eml: &str) -> Event {
+        let input = eml::parse(eml.as_bytes()).unwrap();
+        Event::new(EventContent::SendEmail(SendEmail::new(input)))
+    }
+
+    #[test]
+    fn mbox_escapes_from_lines() {
+        let events = [
+            create_event("From: a@example.com\r\nSubject: One\r\n\r\nFrom here\r\n>From there\r\n"),
+            Event::empty(),
+            create_event("Subject: Two\r\n\r\nHi\r\n"),
+        ];
+        let mut mbox = vec![];
+        write_mbox(&mut mbox, &events).unwrap();
+        let mbox = String::from_utf8(mbox).unwrap();
+
+        let separators = mbox
+            .lines()
+            .filter(|line| line.starts_with("From "))
+            .collect::<Vec<_>>();
+        assert_eq!(separators.len(), 2);
+        assert!(separators[0].starts_with("From a@example.com "));
+        assert!(separators[1].starts_with("From MAILER-DAEMON "));
+        assert!(mbox.contains("\n>From here\n>>From there\n"));
+        assert!(!mbox.contains('\r'));
+    }
+
+    #[test]
+    fn mbox_round_trip() {
+        let events = [
+            create_event("Subject: One\r\n\r\nFrom here\r\n>From there\r\n\r\n"),
+            create_event("Subject: Two\r\n\r\nHi\r\n"),
+        ];
+        let mut mbox = vec![];
+        write_mbox(&mut mbox, &events).unwrap();
+        let messages = messages(&mbox);
+        assert_eq!(
+            messages,
+            [
+                b"Subject: One\n\nFrom here\n>From there\n\n".to_vec(),
+                b"Subject: Two\n\nHi\n".to_vec()
+            ]
+        );
+
+        let eml = b"Subject: Single\r\n\r\nFrom here\r\n";
+        assert_eq!(super::messages(eml), [eml.to_vec()]);
+    }
+
+    #[tokio::test]
+    async fn maildir_sink() {
+        let dir = std::env::temp_dir().join(format!("ses-local-maildir-{}", std::process::id()));
+        let maildir = Maildir::create(&dir).unwrap();
+        let event_store = Arc::new(RwLock::new(EventStore::new()));
+        let sink = maildir.clone().sink(&event_store).await;
+
+        let event = create_event("Subject: Sunk\r\n\r\nHi\r\n");
+        _ = event_store.write().await.push(event.clone()).await;
+        _ = event_store.write().await.push(Event::empty()).await;
+        let delivered = dir.join("new").join(file_name(&event));
+        for _ in 0..50 {
+            if delivered.exists() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let message = fs::read(&delivered).unwrap();
+        let message = MessageParser::default().parse(&message).unwrap();
+        assert_eq!(message.subject(), Some("Sunk"));
+        assert_eq!(fs::read_dir(dir.join("new")).unwrap().count(), 1);
+        assert_eq!(fs::read_dir(dir.join("tmp")).unwrap().count(), 0);
+
+        sink.abort();
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn maildir_resync() {
+        let dir = std::env::temp_dir().join(format!("ses-local-resync-{}", std::process::id()));
+        let maildir = Maildir::create(&dir).unwrap();
+        let events = [
+            create_event("Subject: New\r\n\r\nHi\r\n"),
+            create_event("Subject: Seen\r\n\r\nHi\r\n"),
+            create_event("Subject: Missed\r\n\r\nHi\r\n"),
+            Event::empty(),
+        ];
+        maildir.deliver(&events[0]).unwrap();
+        let seen = maildir.deliver(&events[1]).unwrap().unwrap();
+        let name = format!("{}:2,S", file_name(&events[1]));
+        fs::rename(seen, dir.join("cur").join(name)).unwrap();
+
+        assert_eq!(maildir.resync(&events).unwrap(), 1);
+        assert!(dir.join("new").join(file_name(&events[2])).exists());
+        assert_eq!(fs::read_dir(dir.join("new")).unwrap().count(), 2);
+        assert_eq!(maildir.resync(&events).unwrap(), 0);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,2 +1,3 @@
+#[allow(clippy::module_inception)]
 mod page_template;
 pub use page_template::build;
@@ -5,7 +5,7 @@ struct Route {
     path: &'static str,
 }
 
-const ROUTES: [Route; 2] = [
+const ROUTES: [Route; 4] = [
     Route {
         text: "Emails",
         path: "/emails",
@@ -14,6 +14,14 @@ const ROUTES: [Route; 2] = [
         text: "Events",
         path: "/events",
     },
+    Route {
+        text: "Inbox",
+        path: "/inbox",
+    },
+    Route {
+        text: "Dashboard",
+        path: "/dashboard",
+    },
 ];
 
 fn get_link(url: &str, route: &Route) -> Markup {
@@ -1,29 +1,229 @@
-use crate::event_store::Event;
+use crate::{
+    conf::{Action, Rule},
+    event_store::{
+        send_email::{address, SendEmail},
+        Event, EventContent,
+    },
+    routes::Sending,
+};
 use axum::{
     body::Bytes,
     extract::{OriginalUri, State},
     response::IntoResponse,
     routing::post,
-    Json, Router,
+    Extension, Json, Router,
 };
 use reqwest::StatusCode;
+use serde_json::json;
+use ses_serde::operations::send_email::SendEmailInput;
 
 async fn handler(
     State(crate::AppState { event_store, .. }): State<crate::AppState>,
+    sending: Option<Extension<Sending>>,
     OriginalUri(original_uri): OriginalUri,
     body: Bytes,
 ) -> impl IntoResponse {
-    if let Some(ev) = Event::from_body(body, &original_uri.to_string()) {
+    if let Some(ev) = from_body(body, &original_uri.to_string()) {
         tracing::debug!("{:?}", ev);
-        match event_store.write().await.push(ev).await.ok() {
-            Some(ev) => Json(ev.get_json_response()).into_response(),
-            None => (StatusCode::INTERNAL_SERVER_ERROR).into_response(),
+        let sending = sending
+            .map(|Extension(sending)| sending)
+            .unwrap_or_default();
+        match check(&sending, &ev) {
+            Err(message) => rejected(&message).into_response(),
+            Ok(false) => Json(ev.get_json_response()).into_response(),
+            Ok(true) => match event_store.write().await.push(ev).await.ok() {
+                Some(ev) => Json(ev.get_json_response()).into_response(),
+                None => (StatusCode::INTERNAL_SERVER_ERROR).into_response(),
+            },
         }
     } else {
         (StatusCode::NOT_FOUND).into_response()
     }
 }
 
+fn from_body(body: Bytes, uri: &str) -> Option<Event> {
+    match uri {
+        "/v2/email/outbound-emails" => {
+            let sei: SendEmailInput = serde_json::from_slice(&body).unwrap();
+            Some(EventContent::SendEmail(SendEmail::new(sei)))
+        }
+        _ => None,
+    }
+    .map(Event::new)
+}
+
+// Whether to capture `event`, or the reason SES would reject it.
+fn check(sending: &Sending, event: &Event) -> Result<bool, String> {
+    let Some(EventContent::SendEmail(email)) = &event.content else {
+        return Ok(true);
+    };
+    if !sending.identities.is_empty() {
+        let from = email
+            .request
+            .get_from()
+            .map(|from| address::parse(from).address)
+            .unwrap_or_default();
+        if !sending
+            .identities
+            .iter()
+            .any(|identity| covers(identity, from))
+        {
+            return Err(format!(
+                "Email address is not verified. The following identities failed the check: {}",
+                from
+            ));
+        }
+    }
+    let recipients = email
+        .request
+        .get_to()
+        .into_iter()
+        .flat_map(|d| [&d.to_addresses, &d.cc_addresses, &d.bcc_addresses])
+        .flatten()
+        .flatten()
+        .map(|mailbox| address::parse(mailbox).address)
+        .collect::<Vec<&str>>();
+    let rule = sending.rules.iter().find(|rule| {
+        recipients
+            .iter()
+            .any(|recipient| covers(&rule.recipient, recipient))
+    });
+    match rule {
+        Some(Rule {
+            recipient,
+            action: Action::Reject,
+        }) => Err(format!("Recipient rejected by rule: {}", recipient)),
+        Some(Rule {
+            action: Action::Drop,
+            ..
+        }) => Ok(false),
+        None => Ok(true),
+    }
+}
+
+// Whether `identity`, an address or a domain, covers `address`.
+fn covers(identity: &str, address: &str) -> bool {
+    match identity.contains('@') {
+        true => identity.eq_ignore_ascii_case(address),
+        false => address
+            .rsplit_once('@')
+            .is_some_and(|(_, domain)| domain.eq_ignore_ascii_case(identity)),
+    }
+}
+
+// The error SES returns for a `SendEmail` it refuses, which the SDK reads by its type header.
+fn rejected(message: &str) -> impl IntoResponse {
+    (
+        StatusCode::BAD_REQUEST,
+        [("x-amzn-ErrorType", "MessageRejected")],
+        Json(json!({ "message": message })),
+    )
+}
+
 pub fn create() -> crate::AppStateRouter {
     Router::new().route("/{*wildcard}", post(handler))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{
+        body::{to_bytes, Body},
+        http::{self, Request},
+    };
+    use tokio::sync::RwLock;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::{event_store::EventStore, AppState};
+
+    async fn send(sending: Sending, from: &str, to: &str) -> (StatusCode, usize) {
+        let event_store = Arc::new(RwLock::new(EventStore::new()));
+        let router = create().layer(Extension(sending)).with_state(AppState {
+            event_store: event_store.clone(),
+        });
+        let body = json!({
+            "FromEmailAddress": from,
+            "Destination": { "ToAddresses": [to] },
+            "Content": { "Simple": { "Subject": { "Data": "Hi" } } }
+        });
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/v2/email/outbound-emails")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        if status == StatusCode::BAD_REQUEST {
+            assert_eq!(response.headers()["x-amzn-ErrorType"], "MessageRejected");
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert!(body["message"].is_string());
+        }
+        let captured = event_store.read().await.get_all().len();
+        (status, captured)
+    }
+
+    #[tokio::test]
+    async fn identities() {
+        let sending = Sending {
+            identities: vec![String::from("example.com"), String::from("ops@example.org")],
+            rules: vec![],
+        };
+        for from in ["a@EXAMPLE.com", "Ops <ops@example.org>"] {
+            assert_eq!(
+                send(sending.clone(), from, "to@example.net").await,
+                (StatusCode::OK, 1),
+                "{}",
+                from
+            );
+        }
+        for from in ["a@example.org", "a@sub.example.com"] {
+            assert_eq!(
+                send(sending.clone(), from, "to@example.net").await,
+                (StatusCode::BAD_REQUEST, 0),
+                "{}",
+                from
+            );
+        }
+        assert_eq!(
+            send(Sending::default(), "anyone@example.org", "to@example.net").await,
+            (StatusCode::OK, 1)
+        );
+    }
+
+    #[tokio::test]
+    async fn rules() {
+        let sending = Sending {
+            identities: vec![],
+            rules: vec![
+                Rule {
+                    recipient: String::from("bounce@example.com"),
+                    action: Action::Reject,
+                },
+                Rule {
+                    recipient: String::from("example.org"),
+                    action: Action::Drop,
+                },
+            ],
+        };
+        let from = "s@example.com";
+        assert_eq!(
+            send(sending.clone(), from, "Bounce <bounce@example.com>").await,
+            (StatusCode::BAD_REQUEST, 0)
+        );
+        assert_eq!(
+            send(sending.clone(), from, "a@example.org").await,
+            (StatusCode::OK, 0)
+        );
+        assert_eq!(
+            send(sending, from, "a@example.com").await,
+            (StatusCode::OK, 1)
+        );
+    }
+}
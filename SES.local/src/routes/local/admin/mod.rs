@@ -0,0 +1,152 @@
+use axum::{
+    body::Bytes,
+    extract::{DefaultBodyLimit, State},
+    http::{header::CONTENT_DISPOSITION, StatusCode},
+    response::IntoResponse,
+    routing::post,
+    Json, Router,
+};
+use serde_json::json;
+
+use crate::event_store::Snapshot;
+
+// Snapshots carry every captured email, attachments included, so they outgrow axum's 2 MB
+// default long before the store gets big.
+const RESTORE_LIMIT: usize = 256 * 1024 * 1024;
+
+async fn snapshot(
+    State(crate::AppState { event_store, .. }): State<crate::AppState>,
+) -> impl IntoResponse {
+    let snapshot = event_store.read().await.snapshot();
+    let filename = format!(
+        "ses-local-snapshot-{}.json",
+        snapshot.created_at.strftime("%Y%m%dT%H%M%SZ")
+    );
+    (
+        [(
+            CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", filename),
+        )],
+        Json(snapshot),
+    )
+}
+
+async fn restore(
+    State(crate::AppState { event_store, .. }): State<crate::AppState>,
+    body: Bytes,
+) -> impl IntoResponse {
+    let snapshot = match Snapshot::from_slice(&body) {
+        Ok(snapshot) => snapshot,
+        Err(err) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": err.to_string() })),
+            )
+                .into_response()
+        }
+    };
+    match event_store.write().await.restore(snapshot).await {
+        Ok(restored) => Json(json!({ "restored": restored })).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR).into_response(),
+    }
+}
+
+pub fn create() -> crate::AppStateRouter {
+    Router::new().nest(
+        "/admin",
+        Router::new().route("/snapshot", post(snapshot)).route(
+            "/restore",
+            post(restore).layer(DefaultBodyLimit::max(RESTORE_LIMIT)),
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        event_store::{Event, EventStore},
+        AppState,
+    };
+    use axum::{
+        body::{to_bytes, Body},
+        http::{self, Request},
+    };
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn snapshot_then_restore() {
+        let es = Arc::new(RwLock::new(EventStore::new()));
+        _ = es.write().await.push(Event::empty()).await;
+        let router = create().with_state(AppState {
+            event_store: es.clone(),
+        });
+
+        let response = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/admin/snapshot")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers()[CONTENT_DISPOSITION]
+            .to_str()
+            .unwrap()
+            .starts_with("attachment; filename=\"ses-local-snapshot-"));
+        let snapshot = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        es.write().await.clear();
+        let response = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/admin/restore")
+                    .body(Body::from(snapshot))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(es.read().await.get_all().len(), 1);
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/admin/restore")
+                    .body(Body::from(r#"{"version": 2}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn restore_accepts_large_snapshots() {
+        let es = Arc::new(RwLock::new(EventStore::new()));
+        let mut event = Event::empty();
+        event.timestamp = "x".repeat(4 * 1024 * 1024);
+        let body = serde_json::to_vec(&Snapshot::new(vec![event], Default::default())).unwrap();
+        let response = create()
+            .with_state(AppState { event_store: es })
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/admin/restore")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,8 @@
+use axum::{response::IntoResponse, Json};
+use jiff::Timestamp;
+
+use crate::AppEventStore;
+
+pub async fn stats_json(event_store: &AppEventStore) -> impl IntoResponse {
+    Json(event_store.read().await.stats(Timestamp::now()))
+}
@@ -0,0 +1,92 @@
+use std::{io::Error, time::Duration};
+
+use axum::{
+    extract::OriginalUri,
+    response::{sse::Event, Html, IntoResponse, Sse},
+};
+use futures::{Stream, StreamExt};
+use jiff::Timestamp;
+use tokio::time::{Instant, MissedTickBehavior};
+
+use crate::{event_store::Stats, AppEventStore};
+
+pub mod templates;
+
+// Changes are rendered at most this often, so a burst of sends doesn't redraw per email.
+const THROTTLE: Duration = Duration::from_secs(1);
+// Redraws without changes too, so the per-minute chart moves along.
+const TICK: Duration = Duration::from_secs(30);
+
+pub async fn dashboard_page(event_store: &AppEventStore, uri: OriginalUri) -> impl IntoResponse {
+    let stats = event_store.read().await.stats(Timestamp::now());
+    Html(templates::dashboard::build(&stats, uri.path()).into_string())
+}
+
+/// The stats, rendered again after changes and every so often.
+pub async fn dashboard_sse(
+    event_store: &AppEventStore,
+) -> Sse<impl Stream<Item = Result<Event, Error>>> {
+    let events = stats_stream(event_store).await.map(|stats| {
+        Ok(Event::default()
+            .event("stats")
+            .data(templates::dashboard::stats(&stats).into_string()))
+    });
+    Sse::new(events).keep_alive(
+        axum::response::sse::KeepAlive::new()
+            .interval(Duration::from_secs(1))
+            .text("keep-stream-alive"),
+    )
+}
+
+// Changes only mark the stats stale; they're recomputed on the next throttle tick.
+async fn stats_stream(event_store: &AppEventStore) -> impl Stream<Item = Stats> + use<> {
+    let mut changes = Box::pin(event_store.read().await.get_changes());
+    let event_store = event_store.clone();
+    async_stream::stream! {
+        let mut throttle = tokio::time::interval(THROTTLE);
+        throttle.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut rendered = Instant::now();
+        let mut dirty = false;
+        loop {
+            tokio::select! {
+                biased;
+                change = changes.next() => match change {
+                    Some(_) => dirty = true,
+                    None => break,
+                },
+                _ = throttle.tick() => {
+                    if dirty || rendered.elapsed() >= TICK {
+                        dirty = false;
+                        rendered = Instant::now();
+                        yield event_store.read().await.stats(Timestamp::now());
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::sync::RwLock;
+
+    use super::*;
+    use crate::event_store::{self, EventStore};
+
+    #[tokio::test]
+    async fn stats_throttled() {
+        let es = Arc::new(RwLock::new(EventStore::new()));
+        let stream = stats_stream(&es).await;
+        for _ in 0..20 {
+            _ = es.write().await.push(event_store::Event::empty()).await;
+        }
+        let rendered: Vec<Stats> = stream
+            .take_until(tokio::time::sleep(Duration::from_millis(1500)))
+            .collect()
+            .await;
+        assert_eq!(rendered.len(), 1);
+        assert_eq!(rendered[0].events, 20);
+    }
+}
@@ -0,0 +1,121 @@
+use maud::{html, Markup};
+
+use super::static_content;
+use crate::{
+    event_store::{Breakdown, Bucket, Stats},
+    page_template,
+};
+
+pub fn build(stats: &Stats, uri: &str) -> Markup {
+    page_template::build(
+        html! {
+            div hx-ext="sse" sse-connect="/dashboard" class="overflow-auto p-4" {
+                div id=(static_content::STATS_ID) sse-swap="stats" hx-swap="innerHTML" {
+                    (self::stats(stats))
+                }
+            }
+        },
+        uri,
+    )
+}
+
+/// Everything below the navigation, swapped whole whenever the stats are sent again.
+pub fn stats(stats: &Stats) -> Markup {
+    let last_hour: usize = stats.per_minute.iter().map(|b| b.count).sum();
+    let rate = |rate: Option<f64>| match rate {
+        Some(rate) => format!("{:.1}%", rate * 100.0),
+        None => String::from("n/a"),
+    };
+    html! {
+        div class="flex flex-col gap-6" {
+            div class="grid grid-cols-2 md:grid-cols-5 gap-4" {
+                (card("Emails", stats.emails.to_string()))
+                (card("Events", stats.events.to_string()))
+                (card("Last hour", last_hour.to_string()))
+                (card("Bounce rate", rate(stats.bounce_rate)))
+                (card("Complaint rate", rate(stats.complaint_rate)))
+            }
+            @if stats.bounce_rate.is_none() || stats.complaint_rate.is_none() {
+                p class="-mt-4 text-xs text-gray-500" {
+                    "Bounce and complaint rates aren't available, only sends are captured."
+                }
+            }
+            div class="grid md:grid-cols-2 gap-6" {
+                (chart("Sends per minute, last hour", &stats.per_minute, "%H:%M"))
+                (chart("Sends per hour, last day", &stats.per_hour, "%a %H:00"))
+            }
+            div class="grid md:grid-cols-3 gap-6" {
+                (breakdown("Senders", &stats.by_sender))
+                (breakdown("Recipient domains", &stats.by_recipient_domain))
+                (breakdown("Configuration sets", &stats.by_configuration_set))
+                (breakdown("Tags", &stats.by_tag))
+                (breakdown("Email types", &stats.by_type))
+                (breakdown("Subjects", &stats.top_subjects))
+            }
+            p class="text-xs text-gray-400" { "As of " (stats.generated_at) }
+        }
+    }
+}
+
+fn card(label: &str, value: String) -> Markup {
+    html! {
+        div class="rounded-md shadow-sm p-4" {
+            div class="text-sm text-gray-500" { (label) }
+            div class="text-2xl font-semibold" { (value) }
+        }
+    }
+}
+
+// Bars are scaled to the busiest bucket, labelled in UTC like the timestamps elsewhere.
+fn chart(title: &str, buckets: &[Bucket], format: &str) -> Markup {
+    let max = buckets.iter().map(|b| b.count).max().unwrap_or(0).max(1);
+    html! {
+        section {
+            h3 class="mb-2 font-semibold" { (title) }
+            div class="flex items-end gap-px h-32 border-b border-stone-200" {
+                @for bucket in buckets {
+                    div class="flex-1 bg-indigo-400 hover:bg-indigo-600"
+                        style=(format!("height: {}%", bucket.count * 100 / max))
+                        title=(format!("{}: {}", bucket.start.strftime(format), bucket.count)) {}
+                }
+            }
+            @if let (Some(first), Some(last)) = (buckets.first(), buckets.last()) {
+                div class="flex justify-between text-xs text-gray-500" {
+                    span { (first.start.strftime(format)) }
+                    span { (last.start.strftime(format)) }
+                }
+            }
+        }
+    }
+}
+
+fn breakdown(title: &str, breakdown: &Breakdown) -> Markup {
+    let max = breakdown.top.first().map(|c| c.count).unwrap_or(0).max(1);
+    html! {
+        section {
+            h3 class="mb-2 font-semibold" { (title) }
+            @if breakdown.top.is_empty() {
+                p class="text-sm text-gray-500" { "None" }
+            }
+            table class="w-full text-sm" {
+                @for count in &breakdown.top {
+                    tr {
+                        td class="py-0.5 pr-2 wrap-anywhere" { (count.key) }
+                        td class="w-1/3" {
+                            div class="h-2 rounded bg-indigo-300"
+                                style=(format!("width: {}%", count.count * 100 / max)) {}
+                        }
+                        td class="pl-2 text-right" { (count.count) }
+                    }
+                }
+                @if breakdown.other > 0 {
+                    tr class="text-gray-500" {
+                        td class="py-0.5 pr-2" { "other" }
+                        td {}
+                        td class="pl-2 text-right" { (breakdown.other) }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1 @@
+pub const STATS_ID: &str = "dashboard-stats";
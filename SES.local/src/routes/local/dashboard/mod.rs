@@ -0,0 +1,104 @@
+mod api;
+mod html;
+
+use axum::{
+    body::Body,
+    extract::{OriginalUri, State},
+    http::{Request, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+
+async fn dashboard(
+    State(crate::AppState { event_store, .. }): State<crate::AppState>,
+    uri: OriginalUri,
+    req: Request<Body>,
+) -> impl IntoResponse {
+    if let Some(accept) = super::accept(req.headers()) {
+        return match accept {
+            "text/event-stream" => html::dashboard_sse(&event_store).await.into_response(),
+            "application/json" => api::stats_json(&event_store).await.into_response(),
+            _ => html::dashboard_page(&event_store, uri)
+                .await
+                .into_response(),
+        };
+    }
+    (StatusCode::NOT_FOUND).into_response()
+}
+
+pub fn create() -> crate::AppStateRouter {
+    Router::new().route("/dashboard", get(dashboard))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_store::send_email::SendEmail;
+    use crate::event_store::{Event, EventContent, EventStore};
+    use crate::AppState;
+    use axum::body::to_bytes;
+    use axum::http::header::ACCEPT;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn dashboard_json() {
+        let es = Arc::new(RwLock::new(EventStore::new()));
+        for from in ["a@example.com", "a@example.com", "b@example.com"] {
+            let email = SendEmail::new(
+                serde_json::from_value(serde_json::json!({
+                    "FromEmailAddress": from,
+                    "Destination": { "ToAddresses": ["x@example.org"] },
+                    "Content": { "Simple": { "Subject": { "Data": "Load test" } } }
+                }))
+                .unwrap(),
+            );
+            _ = es
+                .write()
+                .await
+                .push(Event::new(EventContent::SendEmail(email)))
+                .await;
+        }
+        let response = create()
+            .with_state(AppState {
+                event_store: es.clone(),
+            })
+            .oneshot(
+                Request::builder()
+                    .uri("/dashboard")
+                    .header(ACCEPT, "application/json")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let stats: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(stats["emails"], 3);
+        assert_eq!(
+            stats["by_sender"]["top"],
+            serde_json::json!([
+                { "key": "a@example.com", "count": 2 },
+                { "key": "b@example.com", "count": 1 }
+            ])
+        );
+        assert_eq!(
+            stats["by_recipient_domain"]["top"][0],
+            serde_json::json!({ "key": "example.org", "count": 3 })
+        );
+        assert_eq!(
+            stats["top_subjects"]["top"][0],
+            serde_json::json!({ "key": "Load test", "count": 3 })
+        );
+        assert_eq!(
+            stats["per_minute"].as_array().unwrap().last().unwrap()["count"],
+            3
+        );
+        for unavailable in ["bounces", "complaints", "bounce_rate", "complaint_rate"] {
+            assert_eq!(stats.get(unavailable), Some(&serde_json::Value::Null));
+        }
+    }
+}
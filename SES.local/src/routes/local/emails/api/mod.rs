@@ -1,10 +1,78 @@
-use crate::AppEventStore;
-use axum::{response::IntoResponse, Json};
+use std::convert::Infallible;
+
+use super::MAX_WAIT_TIMEOUT;
+use crate::{
+    event_store::{
+        send_email::{attachments, eml, extract, SendEmail},
+        Change, EmailQuery, EventContent,
+    },
+    mailbox, AppEventStore,
+};
+use axum::{
+    http::header::{CONTENT_DISPOSITION, CONTENT_TYPE, X_CONTENT_TYPE_OPTIONS},
+    response::{
+        sse::{Event, KeepAlive},
+        IntoResponse, Sse,
+    },
+    Json,
+};
+use futures::{future, Stream, StreamExt};
+use jiff::SignedDuration;
+use regex::Regex;
 use reqwest::StatusCode;
 use serde_json::json;
 
-pub async fn emails_json(event_store: &AppEventStore) -> impl IntoResponse {
-    Json(json!(event_store.read().await.get_all_emails()))
+pub async fn emails_json(event_store: &AppEventStore, query: &EmailQuery) -> impl IntoResponse {
+    match event_store.read().await.query_emails(query) {
+        Ok(page) => Json(json!(page)).into_response(),
+        Err(err) => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": err.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+/// Newly captured emails matching `query`, as JSON for non-browser consumers such as `app tail`.
+/// Updates and deletes of matching emails follow as `updated` and `deleted` events, clearing
+/// the store as `cleared`, and imports or restores, after which to list the emails again, as
+/// `reloaded`.
+pub async fn emails_stream(
+    event_store: &AppEventStore,
+    query: EmailQuery,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let changes = event_store.read().await.get_changes();
+    let emails = changes.filter_map(move |change| {
+        let (name, ev) = match change {
+            Change::Added(ev) => ("email", ev),
+            Change::Updated(ev) => ("updated", ev),
+            Change::Deleted(ev) => ("deleted", ev),
+            Change::Cleared => {
+                return future::ready(Some(Ok(Event::default().event("cleared").data("{}"))))
+            }
+            Change::Reloaded => {
+                return future::ready(Some(Ok(Event::default().event("reloaded").data("{}"))))
+            }
+        };
+        future::ready(match &ev.content {
+            Some(EventContent::SendEmail(se)) if query.matches(&ev, se) => {
+                Some(Ok(Event::default().event(name).data(json!(se).to_string())))
+            }
+            _ => None,
+        })
+    });
+    Sse::new(emails).keep_alive(KeepAlive::default())
+}
+
+pub async fn threads_json(event_store: &AppEventStore, query: &EmailQuery) -> impl IntoResponse {
+    Json(json!(event_store.read().await.threads(query)))
+}
+
+pub async fn thread_json(event_store: &AppEventStore, id: &str) -> impl IntoResponse {
+    match event_store.read().await.thread(id) {
+        Some(thread) => Json(json!(thread)).into_response(),
+        None => (StatusCode::NOT_FOUND).into_response(),
+    }
 }
 
 pub async fn email_json(event_store: &AppEventStore, id: &str) -> impl IntoResponse {
@@ -14,3 +82,222 @@ pub async fn email_json(event_store: &AppEventStore, id: &str) -> impl IntoRespo
         (StatusCode::NOT_FOUND).into_response()
     }
 }
+
+pub async fn email_eml(event_store: &AppEventStore, id: &str) -> impl IntoResponse {
+    let esr = event_store.read().await;
+    let Some(event) = esr.get_email_event_by_message_id(id) else {
+        return (StatusCode::NOT_FOUND).into_response();
+    };
+    let Some(EventContent::SendEmail(email)) = &event.content else {
+        return (StatusCode::NOT_FOUND).into_response();
+    };
+    let date = event.timestamp.parse().unwrap_or_default();
+    (
+        [
+            (CONTENT_TYPE, String::from("message/rfc822")),
+            (
+                CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}.eml\"", id),
+            ),
+        ],
+        eml::build(email, date),
+    )
+        .into_response()
+}
+
+/// Every email matching `query`, oldest first, as one mbox file. Paging is ignored.
+pub async fn emails_mbox(event_store: &AppEventStore, query: &EmailQuery) -> impl IntoResponse {
+    let esr = event_store.read().await;
+    let events = esr
+        .get_all()
+        .into_iter()
+        .rev()
+        .filter(|ev| match &ev.content {
+            Some(EventContent::SendEmail(se)) => query.matches(ev, se),
+            _ => false,
+        });
+    let mut mbox = vec![];
+    if let Err(err) = mailbox::write_mbox(&mut mbox, events) {
+        return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response();
+    }
+    (
+        [
+            (CONTENT_TYPE, "application/mbox"),
+            (
+                CONTENT_DISPOSITION,
+                "attachment; filename=\"ses-local.mbox\"",
+            ),
+        ],
+        mbox,
+    )
+        .into_response()
+}
+
+/// Stores each message of an uploaded `.eml` or mbox file as an imported raw email.
+pub async fn import_emails(event_store: &AppEventStore, upload: &[u8]) -> impl IntoResponse {
+    let Some(emails) = mailbox::messages(upload)
+        .iter()
+        .map(|message| eml::import(message))
+        .collect::<Option<Vec<SendEmail>>>()
+    else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "expected an RFC 5322 message or an mbox file" })),
+        )
+            .into_response();
+    };
+    let mut esw = event_store.write().await;
+    let mut message_ids = vec![];
+    for email in emails {
+        message_ids.push(email.response.message_id.clone());
+        let event = crate::event_store::Event::new(EventContent::SendEmail(email));
+        if esw.push(event).await.is_err() {
+            return (StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    }
+    Json(json!({ "imported": message_ids.len(), "message_ids": message_ids })).into_response()
+}
+
+pub async fn delete_email(event_store: &AppEventStore, id: &str) -> impl IntoResponse {
+    match event_store.write().await.delete_email(id) {
+        true => Json(json!({ "deleted": 1 })).into_response(),
+        false => (StatusCode::NOT_FOUND).into_response(),
+    }
+}
+
+pub async fn delete_emails(event_store: &AppEventStore, query: &EmailQuery) -> impl IntoResponse {
+    let deleted = event_store.write().await.delete_emails(query);
+    Json(json!({ "deleted": deleted }))
+}
+
+/// The oldest email matching `query`, waiting up to `timeout` for one to arrive if there's
+/// none yet. Paging parameters in `query` are ignored.
+pub async fn wait_for_email(
+    event_store: &AppEventStore,
+    query: &EmailQuery,
+    timeout: SignedDuration,
+) -> impl IntoResponse {
+    if timeout.is_negative() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "timeout must not be negative" })),
+        )
+            .into_response();
+    }
+    let timeout = timeout.min(MAX_WAIT_TIMEOUT).try_into().unwrap_or_default();
+    let query = &EmailQuery {
+        limit: None,
+        cursor: None,
+        ..query.clone()
+    };
+    // subscribe before releasing the lock so nothing pushed in between is missed
+    let stream = {
+        let esr = event_store.read().await;
+        // Without a cursor, there's none to be invalid.
+        let matching = esr.query_emails(query).map(|page| page.items);
+        if let Some(found) = matching.unwrap_or_default().last() {
+            return Json(json!(found)).into_response();
+        }
+        esr.get_changes()
+    };
+    // An import or restore can bring in a match without a change for it, so look again.
+    let mut arrived = Box::pin(stream.filter_map(|change| async move {
+        match change {
+            Change::Added(ev) => match &ev.content {
+                Some(EventContent::SendEmail(se)) if query.matches(&ev, se) => Some(se.clone()),
+                _ => None,
+            },
+            Change::Reloaded => {
+                let esr = event_store.read().await;
+                let matching = esr.query_emails(query).ok()?;
+                matching.items.last().map(|se| (*se).clone())
+            }
+            _ => None,
+        }
+    }));
+    match tokio::time::timeout(timeout, arrived.next()).await {
+        Ok(Some(found)) => Json(json!(found)).into_response(),
+        _ => (
+            StatusCode::REQUEST_TIMEOUT,
+            Json(json!({
+                "near_misses": event_store.read().await.get_near_misses(query)
+            })),
+        )
+            .into_response(),
+    }
+}
+
+pub async fn email_links(event_store: &AppEventStore, id: &str) -> impl IntoResponse {
+    if let Some(found) = event_store.read().await.get_email_by_message_id(id) {
+        Json(json!(extract::links(&found.request))).into_response()
+    } else {
+        (StatusCode::NOT_FOUND).into_response()
+    }
+}
+
+pub async fn email_attachments(event_store: &AppEventStore, id: &str) -> impl IntoResponse {
+    if let Some(found) = event_store.read().await.get_email_by_message_id(id) {
+        Json(json!(attachments::list(&found.request))).into_response()
+    } else {
+        (StatusCode::NOT_FOUND).into_response()
+    }
+}
+
+/// The decoded contents of attachment `n` (counting from 0). Only types that are safe to show
+/// are served inline, and never sniffed as something else.
+pub async fn email_attachment(
+    event_store: &AppEventStore,
+    id: &str,
+    n: usize,
+) -> impl IntoResponse {
+    let Some(attachment) = event_store
+        .read()
+        .await
+        .get_email_by_message_id(id)
+        .and_then(|found| attachments::list(&found.request).into_iter().nth(n))
+    else {
+        return (StatusCode::NOT_FOUND).into_response();
+    };
+    let disposition = match attachment.is_previewable() {
+        true => "inline",
+        false => "attachment",
+    };
+    let content_type = match attachment.is_previewable() {
+        true => attachment.content_type.clone(),
+        false => String::from("application/octet-stream"),
+    };
+    (
+        [
+            (CONTENT_TYPE, content_type),
+            (
+                CONTENT_DISPOSITION,
+                format!(
+                    "{}; filename=\"{}\"",
+                    disposition,
+                    attachment.file_name.replace(['"', '\\', '\r', '\n'], "_")
+                ),
+            ),
+            (X_CONTENT_TYPE_OPTIONS, String::from("nosniff")),
+        ],
+        attachment.contents,
+    )
+        .into_response()
+}
+
+pub async fn email_match(event_store: &AppEventStore, id: &str, regex: &str) -> impl IntoResponse {
+    let regex = match Regex::new(regex) {
+        Ok(regex) => regex,
+        Err(err) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": err.to_string() })),
+            )
+                .into_response()
+        }
+    };
+    if let Some(found) = event_store.read().await.get_email_by_message_id(id) {
+        Json(json!(extract::matches(&found.request, &regex))).into_response()
+    } else {
+        (StatusCode::NOT_FOUND).into_response()
+    }
+}
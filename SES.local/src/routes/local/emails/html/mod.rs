@@ -1,24 +1,43 @@
-use crate::{event_store::EventContent, AppEventStore};
+use crate::{
+    event_store::{
+        send_email::{attachments, SendEmail},
+        Change, EmailQuery, Event as StoredEvent, EventContent,
+    },
+    routes::local::paged,
+    AppEventStore,
+};
 use axum::{
     extract::OriginalUri,
+    http::{
+        header::{CONTENT_SECURITY_POLICY, REFERRER_POLICY, X_CONTENT_TYPE_OPTIONS},
+        StatusCode, Uri,
+    },
     response::{sse::Event, Html, IntoResponse, Sse},
 };
+use axum_htmx::HxPushUrl;
 use futures::{Stream, StreamExt};
 use maud::{html, Markup};
 use std::{io::Error, time::Duration};
 
 pub mod templates;
 
+/// Rows for new emails matching `query`, so a filtered list keeps up with what's captured,
+/// out-of-band swaps for rows that were updated or deleted, and the first page again after an
+/// import or restore.
 pub async fn emails_sse(
     event_store: &AppEventStore,
+    query: EmailQuery,
 ) -> Sse<impl Stream<Item = Result<Event, Error>>> {
-    let stream = event_store.read().await.get_stream();
-    let events = stream.filter_map(|ev| async move {
-        match ev.content {
-            Some(EventContent::SendEmail(se)) => Some(Ok(Event::default()
+    let changes = event_store.read().await.get_changes();
+    let event_store = event_store.clone();
+    let events = changes.filter_map(move |change| {
+        let event_store = event_store.clone();
+        let query = query.clone();
+        async move {
+            let markup = change_markup(change, &query, &event_store).await?;
+            Some(Ok(Event::default()
                 .event("email")
-                .data(templates::email_row::build(&se).into_string()))),
-            _ => None,
+                .data(markup.into_string())))
         }
     });
     Sse::new(events).keep_alive(
@@ -28,14 +47,88 @@ pub async fn emails_sse(
     )
 }
 
+async fn change_markup(
+    change: Change,
+    query: &EmailQuery,
+    event_store: &AppEventStore,
+) -> Option<Markup> {
+    let email = |ev: &StoredEvent| match &ev.content {
+        Some(EventContent::SendEmail(se)) => Some(se.clone()),
+        _ => None,
+    };
+    let removed = |se: &SendEmail| {
+        html! { div id=(templates::email_row::id(se)) hx-swap-oob="delete" {} }
+    };
+    match change {
+        Change::Added(ev) => email(&ev)
+            .filter(|se| query.matches(&ev, se))
+            .map(|se| templates::email_row::build(&se)),
+        Change::Updated(ev) => email(&ev).map(|se| match query.matches(&ev, &se) {
+            true => templates::email_row::updated(&se),
+            false => removed(&se),
+        }),
+        Change::Deleted(ev) => email(&ev).map(|se| removed(&se)),
+        Change::Cleared => Some(html! {
+            div id=(templates::static_content::EMAIL_ROWS_ID) hx-swap-oob="innerHTML" {}
+        }),
+        Change::Reloaded => {
+            let esr = event_store.read().await;
+            let query = paged(query);
+            let ems = esr.query_emails(&query).ok()?;
+            Some(html! {
+                div id=(templates::static_content::EMAIL_ROWS_ID) hx-swap-oob="innerHTML" {
+                    (templates::emails::rows(&ems, &query))
+                }
+            })
+        }
+    }
+}
+
 pub async fn emails_page(
     event_store: &AppEventStore,
+    query: &EmailQuery,
     email: Option<Markup>,
     uri: OriginalUri,
 ) -> impl IntoResponse {
     let esr = event_store.read().await;
-    let ems = esr.get_all_emails();
-    Html(templates::emails::build(&ems, email, uri.path()).into_string())
+    let ems = match esr.query_emails(&paged(query)) {
+        Ok(ems) => ems,
+        Err(err) => return (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    };
+    Html(templates::emails::build(&ems, query, email, uri.path()).into_string()).into_response()
+}
+
+/// Just the list, for the filters to swap in, pushing the URL that brings it back.
+pub async fn emails_results(event_store: &AppEventStore, query: &EmailQuery) -> impl IntoResponse {
+    let esr = event_store.read().await;
+    let ems = match esr.query_emails(&paged(query)) {
+        Ok(ems) => ems,
+        Err(err) => return (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    };
+    (
+        templates::emails::url(query).parse().ok().map(HxPushUrl),
+        Html(templates::emails::results(&ems, query).into_string()),
+    )
+        .into_response()
+}
+
+/// The rows after `query.cursor`, for infinite scroll.
+pub async fn emails_more(event_store: &AppEventStore, query: &EmailQuery) -> impl IntoResponse {
+    let esr = event_store.read().await;
+    let ems = match esr.query_emails(&paged(query)) {
+        Ok(ems) => ems,
+        Err(err) => return (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    };
+    Html(templates::emails::rows(&ems, query).into_string()).into_response()
+}
+
+pub async fn threads_page(
+    event_store: &AppEventStore,
+    query: &EmailQuery,
+    uri: OriginalUri,
+) -> impl IntoResponse {
+    let esr = event_store.read().await;
+    Html(templates::threads::build(&esr.threads(query), query, None, uri.path()).into_string())
 }
 
 pub async fn email_page(
@@ -47,19 +140,28 @@ pub async fn email_page(
     let esr = event_store.read().await;
     match esr.get_email_by_message_id(id) {
         Some(em) => {
-            let email_content = templates::email::build(em);
+            let email_content = templates::email::build(
+                em,
+                esr.thread(id).as_ref(),
+                &esr.get_events_by_message_id(id),
+            );
             match hx_request {
                 true => Html(email_content.into_string()).into_response(),
-                false => emails_page(event_store, Some(email_content), uri)
-                    .await
-                    .into_response(),
+                false => emails_page(
+                    event_store,
+                    &EmailQuery::default(),
+                    Some(email_content),
+                    uri,
+                )
+                .await
+                .into_response(),
             }
         }
         None => {
             let not_found = html! { (format!("Email Not Found: {}", id))};
             match hx_request {
                 true => Html(not_found.into_string()).into_response(),
-                false => emails_page(event_store, Some(not_found), uri)
+                false => emails_page(event_store, &EmailQuery::default(), Some(not_found), uri)
                     .await
                     .into_response(),
             }
@@ -67,7 +169,40 @@ pub async fn email_page(
     }
 }
 
-pub async fn email_content(event_store: &AppEventStore, id: &str) -> impl IntoResponse {
+/// Deletes the email shown in the detail pane, replacing it with a note and leaving its URL.
+pub async fn delete_email(event_store: &AppEventStore, id: &str) -> impl IntoResponse {
+    match event_store.write().await.delete_email(id) {
+        true => (
+            Some(HxPushUrl(Uri::from_static("/emails"))),
+            Html(html! { "Email deleted" }.into_string()),
+        ),
+        false => (
+            None,
+            Html(html! { (format!("Email Not Found: {}", id)) }.into_string()),
+        ),
+    }
+}
+
+/// No scripts, forms or plugins, and only inline or local resources unless `remote`. The
+/// `sandbox` directive also covers bodies opened outside the UI's sandboxed iframe.
+fn content_security_policy(remote: bool) -> String {
+    let sources = match remote {
+        true => "'self' data: http: https:",
+        false => "'self' data:",
+    };
+    format!(
+        "default-src 'none'; img-src {0}; media-src {0}; font-src {0}; \
+         style-src 'unsafe-inline' {0}; base-uri 'none'; form-action 'none'; \
+         sandbox allow-popups allow-popups-to-escape-sandbox",
+        sources
+    )
+}
+
+pub async fn email_content(
+    event_store: &AppEventStore,
+    id: &str,
+    remote: bool,
+) -> impl IntoResponse {
     // let esr = event_store.read().await;
     // if let Some(email) = esr.get_email_by_message_id(id) {
     //     let content = email.request.get_email_content();
@@ -81,10 +216,20 @@ pub async fn email_content(event_store: &AppEventStore, id: &str) -> impl IntoRe
     let esr = event_store.read().await;
     match esr.get_email_by_message_id(id) {
         Some(email) => {
-            let content = email.request.get_email_content();
-            let body = content.body.as_ref().and_then(|x| x.content).unwrap();
-            let x = body.to_owned();
-            Html(x).into_response()
+            let html = email.request.get_html().unwrap_or_default();
+            let attachments = attachments::list(&email.request);
+            let html = attachments::resolve_cids(&html, &attachments, |n| {
+                format!("/emails/{}/attachments/{}", id, n)
+            });
+            (
+                [
+                    (CONTENT_SECURITY_POLICY, content_security_policy(remote)),
+                    (REFERRER_POLICY, String::from("no-referrer")),
+                    (X_CONTENT_TYPE_OPTIONS, String::from("nosniff")),
+                ],
+                Html(html),
+            )
+                .into_response()
         }
         None => Html("").into_response(),
     }
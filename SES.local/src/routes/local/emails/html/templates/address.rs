@@ -0,0 +1,78 @@
+use maud::{html, Markup};
+
+use crate::event_store::send_email::address;
+
+// Recipients beyond this many are folded away behind a "+N more" toggle.
+const SHOWN: usize = 3;
+
+/// A display name with its address on hover, or the bare address.
+pub fn mailbox(mailbox: &str) -> Markup {
+    let mailbox = address::parse(mailbox);
+    html! {
+        @match mailbox.name {
+            Some(name) => span title=(mailbox.address) {
+                (name) " " span class="text-gray-500" { "<" (mailbox.address) ">" }
+            },
+            None => span { (mailbox.address) },
+        }
+    }
+}
+
+pub fn list(addresses: &[String]) -> Markup {
+    let (shown, folded) = addresses.split_at(addresses.len().min(SHOWN));
+    html! {
+        (join(shown))
+        @if !folded.is_empty() {
+            details class="inline" {
+                summary class="inline cursor-pointer text-blue-500" { ", +" (folded.len()) " more" }
+                ", " (join(folded))
+            }
+        }
+    }
+}
+
+/// Names only, for the email list: the first few, then how many more.
+pub fn names(addresses: &[impl AsRef<str>]) -> String {
+    let names = addresses
+        .iter()
+        .take(SHOWN)
+        .map(|mailbox| {
+            let mailbox = address::parse(mailbox.as_ref());
+            mailbox.name.unwrap_or(mailbox.address)
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    match addresses.len().saturating_sub(SHOWN) {
+        0 => names,
+        more => format!("{} +{}", names, more),
+    }
+}
+
+fn join(addresses: &[String]) -> Markup {
+    html! {
+        @for (i, a) in addresses.iter().enumerate() {
+            @if i > 0 { ", " }
+            (mailbox(a))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collapses() {
+        let addresses = [
+            "Ann <a@example.com>",
+            "b@example.com",
+            "c@example.com",
+            "d@example.com",
+        ]
+        .map(String::from);
+        assert_eq!(names(&addresses), "Ann, b@example.com, c@example.com +1");
+        let markup = list(&addresses).into_string();
+        assert!(markup.contains(r#"<span title="a@example.com">Ann <span class="text-gray-500">&lt;a@example.com&gt;</span></span>"#));
+        assert!(markup.contains(", +1 more</summary>, <span>d@example.com</span></details>"));
+    }
+}
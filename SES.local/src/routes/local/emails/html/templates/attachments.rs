@@ -0,0 +1,54 @@
+use maud::{html, Markup};
+
+use crate::event_store::send_email::attachments::EmailAttachment;
+
+pub fn build(message_id: &str, attachments: &[EmailAttachment]) -> Markup {
+    html! {
+        @if !attachments.is_empty() {
+            div class="p-4 pb-0" {
+                div class="text-gray-500" { "attachments:" }
+                ul {
+                    @for (n, attachment) in attachments.iter().enumerate() {
+                        (item(&format!("/emails/{}/attachments/{}", message_id, n), attachment))
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn item(uri: &str, attachment: &EmailAttachment) -> Markup {
+    html! {
+        li class="my-2" {
+            div class="flex gap-2 items-baseline" {
+                a href=(uri) download=(attachment.file_name) class="text-blue-500 hover:text-blue-700 wrap-anywhere" {
+                    (attachment.file_name)
+                }
+                span class="text-gray-500 text-sm" {
+                    (attachment.content_type) ", " (size(attachment.size))
+                    @if attachment.inline { ", inline" } @else { ", attachment" }
+                    @if let Some(cid) = &attachment.content_id { ", cid:" (cid) }
+                    @if let Some(encoding) = &attachment.transfer_encoding { ", " (encoding) }
+                }
+                @if attachment.content_type == "application/pdf" {
+                    a href=(uri) target="_blank" class="text-blue-500 hover:text-blue-700 text-sm" { "open" }
+                }
+            }
+            @if attachment.is_image() {
+                img src=(uri) alt=(attachment.file_name) class="max-h-48 mt-1 border border-stone-100";
+            } @else if attachment.is_text() {
+                pre class="max-h-48 overflow-auto mt-1 p-2 bg-stone-50 text-sm" {
+                    (String::from_utf8_lossy(&attachment.contents))
+                }
+            }
+        }
+    }
+}
+
+fn size(bytes: usize) -> String {
+    match bytes {
+        b if b < 1024 => format!("{} B", b),
+        b if b < 1024 * 1024 => format!("{:.1} KB", b as f64 / 1024.0),
+        b => format!("{:.1} MB", b as f64 / (1024.0 * 1024.0)),
+    }
+}
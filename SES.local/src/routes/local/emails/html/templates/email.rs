@@ -1,78 +1,199 @@
-use base64::{engine::general_purpose::STANDARD_NO_PAD, Engine as _};
 use maud::{html, Markup};
 
-use crate::event_store::send_email::SendEmail;
+use crate::{
+    event_store::{
+        send_email::{self, headers, EmailRequest, SendEmail},
+        Event, Thread,
+    },
+    routes::local::events::html::templates::timeline,
+};
 
-pub fn build(email: &SendEmail) -> Markup {
-    let content = email.request.get_email_content();
-    let c = vec![
+use super::{address, attachments, html_source, static_content, thread};
+
+const CONTENT_FRAME_ID: &str = "email-content";
+const SHOW_TAB: &str = "for (let tab of this.parentNode.children) {
+        tab.classList.toggle('border-b-2', tab === this);
+        tab.classList.toggle('border-blue-500', tab === this);
+        tab.classList.toggle('text-gray-500', tab !== this);
+    }
+    for (let panel of document.querySelectorAll('[data-panel]')) {
+        panel.hidden = panel.dataset.panel !== this.dataset.tab;
+    }";
+
+/// The email with `timeline`, the events about it, and the `thread` it's part of.
+pub fn build(email: &SendEmail, thread: Option<&Thread>, timeline: &[&Event]) -> Markup {
+    let content = send_email::email_content(&email.request);
+    let message_id = email.response.message_id.clone().unwrap_or_default();
+    let mut c = vec![
+        ("id", html! { (message_id) }),
         (
-            "id",
-            html! {
-                (email.response.message_id.clone().unwrap_or("".to_string()))
-            },
+            "from",
+            html! { @if let Some(from) = content.from { (address::mailbox(from)) } },
         ),
-        (
-            "to",
+    ];
+    let to = content.to;
+    for (dt, addresses) in [
+        ("to", to.and_then(|d| d.to_addresses.as_deref())),
+        ("cc", to.and_then(|d| d.cc_addresses.as_deref())),
+        ("bcc", to.and_then(|d| d.bcc_addresses.as_deref())),
+        ("reply-to", content.reply_to),
+    ] {
+        match addresses {
+            Some(addresses) if !addresses.is_empty() => c.push((dt, address::list(addresses))),
+            _ if dt == "to" => c.push((dt, html! {})),
+            _ => {}
+        }
+    }
+    c.push((
+        "subject",
+        html! { (content.subject.as_deref().unwrap_or("")) },
+    ));
+    if let Some(tags) = content.tags.filter(|tags| !tags.is_empty()) {
+        c.push((
+            "tags",
             html! {
-                (
-                    content.from.unwrap_or(&String::from(""))
-                )
+                @for tag in tags {
+                    span class="inline-block mr-1 px-2 rounded-full bg-stone-100 text-sm" {
+                        (tag.name) "=" (tag.value)
+                    }
+                }
             },
-        ),
-        (
-            "from",
+        ));
+    }
+    if let Some(configuration_set) = content.configuration_set {
+        c.push(("config set", html! { (configuration_set) }));
+    }
+    if let Some(address) = content.feedback_forwarding_address {
+        c.push(("feedback to", address::mailbox(address)));
+    }
+    if let Some(list) = content.list_management {
+        c.push((
+            "list",
             html! {
-                (
-                    content.to.map(
-                        |x| x.to_addresses.as_ref().map(|a| a.join(", ")).unwrap_or("".to_string())
-                    ).unwrap_or("".to_string())
-                )
+                (list.contact_list_name)
+                @if let Some(topic) = &list.topic_name { ", topic " (topic) }
             },
-        ),
-        (
-            "subject",
+        ));
+    }
+    let uri = format!("/emails/{}/content", message_id);
+    let html = email.request.get_html();
+    let mut tabs = vec![];
+    if html.is_some() {
+        tabs.push((
+            "html",
+            "HTML",
             html! {
-                (
-                    content.subject.unwrap_or(&String::from(""))
-                )
+                div class="flex flex-col grow" {
+                    label class="px-4 py-2 text-sm text-gray-500" {
+                        input type="checkbox" class="mr-1"
+                            onchange=(format!("document.getElementById('{}').src = '{}' + (this.checked ? '?remote=true' : '')", CONTENT_FRAME_ID, uri));
+                        "Load remote content"
+                    }
+                    // Without allow-scripts or allow-same-origin the body can't touch the UI.
+                    iframe id=(CONTENT_FRAME_ID) class="grow" src=(uri)
+                        sandbox="allow-popups allow-popups-to-escape-sandbox"
+                        referrerpolicy="no-referrer" {}
+                }
             },
-        ),
-    ];
-    let email = match content.body {
-        Some(b) => match b.content {
-            Some(c) => {
-                if b.is_html {
-                    let uri = format!(
-                        "/emails/{}/content",
-                        email.response.message_id.clone().unwrap_or("".to_string())
-                    );
-                    // let src = format!("data:text/html;base64,{}", STANDARD_NO_PAD.encode(c));
-                    html! { iframe class="grow" src=(uri) {} }
-                } else {
-                    html! { (c) }
+        ));
+    }
+    if let Some(text) = email.request.get_text() {
+        tabs.push((
+            "text",
+            "Text",
+            html! { pre class="p-4 whitespace-pre-wrap wrap-anywhere" { (text) } },
+        ));
+    }
+    if let Some(html) = &html {
+        tabs.push(("source", "Source", html_source::build(html)));
+    }
+    if let Some(thread) = thread {
+        tabs.push(("thread", "Thread", thread::build(thread, &message_id)));
+    }
+    tabs.push((
+        "headers",
+        "Headers",
+        html! {
+            dl class="p-4" {
+                @for header in headers::list(&email.request) {
+                    div class="flex" {
+                        dt class="text-gray-500 flex-[200px] grow-0 shrink-0 wrap-anywhere" { (header.name) ":" }
+                        dd class="flex-grow wrap-anywhere" { (header.value) }
+                    }
                 }
             }
-            None => html! { "" },
-        },
-        None => html! {
-            ""
         },
-    };
+    ));
+    tabs.push((
+        "raw",
+        "Raw",
+        html! { pre class="p-4 text-sm whitespace-pre-wrap wrap-anywhere" { (raw(email)) } },
+    ));
     html! {
         dl class="flex flex-col min-h-full" {
-            div class="p-4 pb-0" {
-                @for (dt, dd) in c {
-                    div class="flex" {
-                        dt class="text-gray-500 flex-[80px] grow-0 shrink-0" { (dt) ":" }
-                        dd class="flex-grow wrap-anywhere" { (dd) }
+            div class="flex p-4 pb-0" {
+                div class="flex-grow" {
+                    @for (dt, dd) in c {
+                        div class="flex" {
+                            dt class="text-gray-500 flex-[100px] grow-0 shrink-0" { (dt) ":" }
+                            dd class="flex-grow wrap-anywhere" { (dd) }
+                        }
+                    }
+                }
+                div class="self-start flex gap-2" {
+                    a href=(format!("/emails/{}/eml", message_id))
+                      download=(format!("{}.eml", message_id))
+                      class="bg-blue-500 hover:bg-blue-700 text-white font-bold py-2 px-4 rounded" {
+                        "Download .eml"
+                    }
+                    button hx-delete=(format!("/emails/{}", message_id))
+                      hx-confirm="Delete this email?"
+                      hx-target=(format!("#{}", static_content::EMAIL_DETAIL_ID))
+                      hx-swap="innerHTML"
+                      class="bg-red-500 hover:bg-red-700 text-white font-bold py-2 px-4 rounded" {
+                        "Delete"
                     }
                 }
             }
-            div class="flex grow" {
-                (email)
+            @if !content.warnings.is_empty() {
+                ul class="mx-4 mt-4 p-2 rounded bg-amber-50 text-amber-800 text-sm" {
+                    @for warning in &content.warnings {
+                        li { (warning) }
+                    }
+                }
+            }
+            @if !timeline.is_empty() {
+                details open class="mx-4 mt-4" {
+                    summary class="cursor-pointer text-gray-500" { "Timeline" }
+                    div class="mt-2" { (timeline::build(timeline, None)) }
+                }
+            }
+            (attachments::build(&message_id, &content.attachments))
+            div class="flex gap-4 px-4 mt-4 border-b border-stone-100" {
+                @for (i, (id, label, _)) in tabs.iter().enumerate() {
+                    button type="button" data-tab=(id)
+                        class=(if i == 0 { "py-1 border-b-2 border-blue-500" } else { "py-1 text-gray-500" })
+                        onclick=(SHOW_TAB) { (label) }
+                }
+            }
+            @for (i, (id, _, panel)) in tabs.into_iter().enumerate() {
+                div data-panel=(id) class="flex grow" hidden[i > 0] {
+                    (panel)
+                }
             }
 
         }
     }
 }
+
+/// A raw email's MIME source, otherwise the request as sent to the API.
+fn raw(email: &SendEmail) -> String {
+    let input = email.request.get_input();
+    match (
+        &email.request,
+        input.content.as_ref().and_then(|c| c.raw.as_ref()),
+    ) {
+        (EmailRequest::Raw(_), Some(raw)) => String::from_utf8_lossy(&raw.data.inner).into_owned(),
+        _ => serde_json::to_string_pretty(input).unwrap_or_default(),
+    }
+}
@@ -3,17 +3,41 @@ use ses_serde::types::Destination;
 
 use crate::event_store::send_email::SendEmail;
 
+use super::address;
 use super::static_content;
 use super::tag;
 
 pub fn build(email: &SendEmail) -> Markup {
+    row(email, false)
+}
+
+/// The row of an email that changed, badged, to swap out-of-band for the one shown.
+pub fn updated(email: &SendEmail) -> Markup {
+    row(email, true)
+}
+
+/// The row's element id.
+pub fn id(email: &SendEmail) -> String {
+    format!(
+        "email-row-{}",
+        email.response.message_id.as_deref().unwrap_or_default()
+    )
+}
+
+fn row(email: &SendEmail, updated: bool) -> Markup {
     let summary = email.request.get_summary();
-    let info = vec![
+    let mut info = vec![];
+    if let Some(from) = summary.from {
+        info.push(html! { div class="text-sm text-gray-500" { (address::names(&[from])) } });
+    }
+    info.extend([
         destination(summary.to).unwrap_or(html! { "unknown" }),
-        html! { (summary.subject.unwrap_or(&String::from("unknown"))) },
-    ];
+        html! { (summary.subject.as_deref().unwrap_or("unknown")) },
+    ]);
     html! {
-        a hx-get=(format!("/emails/{}", email.response.message_id.clone().unwrap_or("".to_string())))
+        a id=(id(email))
+            hx-swap-oob=[updated.then_some("outerHTML")]
+            hx-get=(format!("/emails/{}", email.response.message_id.clone().unwrap_or("".to_string())))
             hx-push-url="true"
             hx-target=(format!("#{}", static_content::EMAIL_DETAIL_ID))
             hx-swap="innerHTML"
@@ -30,6 +54,10 @@ pub fn build(email: &SendEmail) -> Markup {
             is-enabled:hover:bg-indigo-50
             is-disabled:shadow-lg
             is-disabled:bg-indigo-100" {
+                // Kept from the row's click, which opens the email.
+                input type="checkbox" name="message_id" class="ml-3" title="Select"
+                    value=(email.response.message_id.as_deref().unwrap_or_default())
+                    onclick="event.stopPropagation()";
                 div class="p-3" {
                     (tag::build(&email.request.get_tag()))
                 }
@@ -37,20 +65,26 @@ pub fn build(email: &SendEmail) -> Markup {
                     @for item in info {
                         (item)
                     }
+                    @if email.imported {
+                        span class="text-xs text-gray-500" { "imported" }
+                    }
+                    @if updated {
+                        span class="text-xs text-amber-600" { "updated" }
+                    }
                 }
         }
     }
 }
 
 fn destination(dest: Option<&Destination>) -> Option<Markup> {
-    match dest {
-        Some(d) => Some(html! {
-            div {
-                @for to in d.clone().to_addresses? {
-                    span { (to) }
-                }
-            }
-        }),
-        _ => None,
+    let dest = dest?;
+    let recipients = [&dest.to_addresses, &dest.cc_addresses, &dest.bcc_addresses]
+        .into_iter()
+        .flatten()
+        .flatten()
+        .collect::<Vec<_>>();
+    match recipients.is_empty() {
+        true => None,
+        false => Some(html! { div { (address::names(&recipients)) } }),
     }
 }
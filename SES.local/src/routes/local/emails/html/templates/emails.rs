@@ -1,11 +1,23 @@
 use super::email_row;
 use super::static_content;
 use super::tag;
+use super::threads;
 use crate::event_store::send_email::{EmailTag, SendEmail};
+use crate::event_store::{EmailQuery, Page};
 use crate::page_template;
-use maud::{html, Markup};
+use jiff::Timestamp;
+use maud::{html, Markup, PreEscaped};
 
-pub fn build(emails: &Vec<&SendEmail>, email: Option<Markup>, uri: &str) -> Markup {
+const FILTERS_ID: &str = "email-filters";
+// The format of a `datetime-local` input's value, which the filters take as UTC.
+const DATETIME_LOCAL: &str = "%Y-%m-%dT%H:%M";
+
+pub fn build(
+    emails: &Page<&SendEmail>,
+    query: &EmailQuery,
+    email: Option<Markup>,
+    uri: &str,
+) -> Markup {
     page_template::build(
         html! {
             script {
@@ -20,6 +32,23 @@ pub fn build(emails: &Vec<&SendEmail>, email: Option<Markup>, uri: &str) -> Mark
                 }
             })"
             }
+            // The SSE stream removes the rows; emails already gone count as deleted.
+            script {
+                (PreEscaped("async function deleteSelected() {
+                    let ids = [...document.querySelectorAll('#emails [name=message_id]:checked')].map((c) => c.value);
+                    if (!ids.length || !confirm(`Delete ${ids.length} selected emails?`)) {
+                        return;
+                    }
+                    let deleted = await Promise.all(ids.map((id) =>
+                        fetch(`/emails/${encodeURIComponent(id)}`, { method: 'DELETE' })
+                            .then((response) => response.ok || response.status === 404, () => false)
+                    ));
+                    let failed = deleted.filter((ok) => !ok).length;
+                    if (failed) {
+                        alert(`Could not delete ${failed} of ${ids.length} emails`);
+                    }
+                }"))
+            }
             div class="flex flex-col items-stretch min-h-full" {
                 div class="flex flex-row flex-grow min-h-full" {
                     div class="shrink-0 flex-[360px] grow-0 flex flex-col overflow-hidden" {
@@ -27,14 +56,9 @@ pub fn build(emails: &Vec<&SendEmail>, email: Option<Markup>, uri: &str) -> Mark
                         div class="border-b-1 border-stone-100 py-2" {
                             (email_type_key())
                         }
-                        div id="emails" class="overflow-auto flex-grow snap-y snap-mandatory snap-center inset-shadow-sm" {
-                            div hx-ext="sse" sse-connect="/emails" {
-                                div sse-swap="email" hx-swap="afterbegin" {}
-                            }
-                            @for em in emails {
-                                (email_row::build(&em))
-                            }
-                        }
+                        (filters(query))
+                        (import_zone())
+                        (results(emails, query))
                     }
                     div id=(static_content::EMAIL_DETAIL_ID) class="border-l-1 border-stone-100 grow shrink overflow-auto" {
                         (email.unwrap_or(html! { "email" }))
@@ -46,6 +70,176 @@ pub fn build(emails: &Vec<&SendEmail>, email: Option<Markup>, uri: &str) -> Mark
     )
 }
 
+/// The emails matching `query`, with chips for its filters, kept up to date over SSE.
+pub fn results(emails: &Page<&SendEmail>, query: &EmailQuery) -> Markup {
+    let filters = active_filters(query);
+    let filtered = !filters.is_empty();
+    html! {
+        div id=(static_content::EMAIL_RESULTS_ID) class="flex flex-col flex-grow overflow-hidden" {
+            @if filtered {
+                div class="flex flex-wrap items-center gap-1 mx-2 mb-2 text-xs" {
+                    @for (name, value) in filters {
+                        span class="inline-flex items-center gap-1 rounded-full bg-indigo-50 px-2 py-0.5" {
+                            (name) ": " (value)
+                            button type="button" title="Remove filter" onclick=(format!("clearFilter('{}')", name)) { "×" }
+                        }
+                    }
+                    span class="text-gray-500" { (emails.total) " matching" }
+                    button type="button" class="text-indigo-600" onclick="clearFilter()" { "clear" }
+                }
+            }
+            (actions(query, filtered))
+            div id="emails" hx-ext="sse" sse-connect=(url(query))
+                class="overflow-auto flex-grow snap-y snap-mandatory snap-center inset-shadow-sm" {
+                    // New rows go first; changes to rows already shown are swapped out-of-band.
+                    div id=(static_content::EMAIL_ROWS_ID) sse-swap="email" hx-swap="afterbegin" {
+                        (rows(emails, query))
+                    }
+            }
+        }
+    }
+}
+
+// Deleting the selected rows, or everything the list matches; the SSE stream removes the rows.
+fn actions(query: &EmailQuery, filtered: bool) -> Markup {
+    let (label, confirm) = match filtered {
+        true => (
+            "Delete matching",
+            "Delete every email matching the filters?",
+        ),
+        false => ("Delete all", "Delete every email?"),
+    };
+    html! {
+        div class="flex justify-end gap-3 mx-2 mb-1 text-xs" {
+            a href=(threads::url(query)) class="mr-auto text-indigo-600" { "Threads" }
+            button type="button" class="text-red-600" onclick="deleteSelected()" { "Delete selected" }
+            button type="button" class="text-red-600" hx-delete=(url(query)) hx-swap="none"
+                hx-confirm=(confirm) { (label) }
+        }
+    }
+}
+
+/// A page of rows, followed by what loads the next one when scrolled into view.
+pub fn rows(emails: &Page<&SendEmail>, query: &EmailQuery) -> Markup {
+    html! {
+        @for em in &emails.items {
+            (email_row::build(em))
+        }
+        @if let Some(cursor) = &emails.next_cursor {
+            @let next = href(&EmailQuery {
+                cursor: Some(cursor.clone()),
+                ..query.clone()
+            });
+            div id=(static_content::EMAILS_MORE_ID) hx-get=(next) hx-trigger="intersect once"
+                hx-swap="outerHTML" class="m-2 text-center" {
+                    button type="button" hx-get=(next)
+                        hx-target=(format!("#{}", static_content::EMAILS_MORE_ID))
+                        hx-swap="outerHTML" class="text-sm text-indigo-600" {
+                            "Load more"
+                    }
+            }
+        }
+    }
+}
+
+/// The emails page URL for `query`, leaving out paging.
+pub fn url(query: &EmailQuery) -> String {
+    href(&EmailQuery {
+        limit: None,
+        cursor: None,
+        ..query.clone()
+    })
+}
+
+fn href(query: &EmailQuery) -> String {
+    match serde_urlencoded::to_string(query) {
+        Ok(query) if !query.is_empty() => format!("/emails?{}", query),
+        _ => String::from("/emails"),
+    }
+}
+
+fn filters(query: &EmailQuery) -> Markup {
+    let text = |value: &Option<String>| value.clone().unwrap_or_default();
+    let datetime = |value: &Option<Timestamp>| {
+        value
+            .map(|ts| ts.strftime(DATETIME_LOCAL).to_string())
+            .unwrap_or_default()
+    };
+    let fields = [
+        ("from", text(&query.from), "sender@example.com"),
+        ("to", text(&query.to), "recipient@example.com"),
+        ("subject", text(&query.subject), "contains"),
+        ("configuration_set", text(&query.configuration_set), "name"),
+        ("tag", text(&query.tag), "name or name=value"),
+    ];
+    let open = active_filters(query).iter().any(|(name, _)| *name != "q");
+    html! {
+        script {
+            "function clearFilter(name) {
+                let form = document.getElementById('" (FILTERS_ID) "');
+                for (let field of form.querySelectorAll(name ? `[name=${name}]` : '[name]')) {
+                    field.value = '';
+                }
+                form.dispatchEvent(new Event('change'));
+            }"
+        }
+        form id=(FILTERS_ID) action="/emails"
+            hx-get="/emails"
+            hx-target=(format!("#{}", static_content::EMAIL_RESULTS_ID))
+            hx-swap="outerHTML"
+            hx-trigger="input delay:300ms, change, submit"
+            class="mx-2 mt-2 flex flex-col gap-1 text-sm" {
+                input type="search" name="q" value=(text(&query.q))
+                    placeholder="Search subject, sender or recipients"
+                    class="border border-stone-200 rounded-md px-2 py-1";
+                details open[open] class="mb-2" {
+                    summary class="cursor-pointer text-gray-500" { "Filters" }
+                    div class="grid grid-cols-[110px_1fr] gap-1 items-center mt-1" {
+                        @for (name, value, placeholder) in fields {
+                            label for=(format!("filter-{}", name)) { (name.replace('_', " ")) }
+                            input id=(format!("filter-{}", name)) name=(name) value=(value)
+                                placeholder=(placeholder)
+                                class="border border-stone-200 rounded-md px-2 py-0.5";
+                        }
+                        label for="filter-type" { "type" }
+                        select id="filter-type" name="type" class="border border-stone-200 rounded-md px-1 py-0.5" {
+                            option value="" { "any" }
+                            @for tag in [EmailTag::Simple, EmailTag::Template, EmailTag::Raw] {
+                                @let tag = tag.to_string();
+                                option value=(tag) selected[query.email_type.as_ref().is_some_and(|t| t.eq_ignore_ascii_case(&tag))] { (tag) }
+                            }
+                        }
+                        label for="filter-since" { "since (UTC)" }
+                        input id="filter-since" type="datetime-local" name="since" value=(datetime(&query.since))
+                            class="border border-stone-200 rounded-md px-2 py-0.5";
+                        label for="filter-until" { "until (UTC)" }
+                        input id="filter-until" type="datetime-local" name="until" value=(datetime(&query.until))
+                            class="border border-stone-200 rounded-md px-2 py-0.5";
+                    }
+                }
+        }
+    }
+}
+
+// The query's criteria as (parameter, value) pairs, in the order the form lists them.
+fn active_filters(query: &EmailQuery) -> Vec<(&'static str, String)> {
+    let datetime = |ts: &Timestamp| ts.strftime("%Y-%m-%d %H:%M").to_string();
+    [
+        ("q", query.q.clone()),
+        ("from", query.from.clone()),
+        ("to", query.to.clone()),
+        ("subject", query.subject.clone()),
+        ("configuration_set", query.configuration_set.clone()),
+        ("tag", query.tag.clone()),
+        ("type", query.email_type.clone()),
+        ("since", query.since.as_ref().map(datetime)),
+        ("until", query.until.as_ref().map(datetime)),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| Some((name, value?)))
+    .collect()
+}
+
 pub fn email_type_key() -> Markup {
     let tags = vec![
         (EmailTag::Simple, "simple"),
@@ -63,3 +257,30 @@ pub fn email_type_key() -> Markup {
         }
     }
 }
+
+fn import_zone() -> Markup {
+    html! {
+        // Unescaped, since maud would turn the arrow function's `>` into `&gt;`.
+        script {
+            (PreEscaped("function importEmails(event) {
+                event.preventDefault();
+                let zone = event.currentTarget;
+                zone.classList.remove('bg-indigo-50');
+                for (let file of event.dataTransfer.files) {
+                    fetch('/emails/import', { method: 'POST', body: file }).then((response) => {
+                        if (!response.ok) {
+                            zone.textContent = `Could not import ${file.name}`;
+                        }
+                    });
+                }
+            }"))
+        }
+        div id="import"
+            ondragover="event.preventDefault(); this.classList.add('bg-indigo-50')"
+            ondragleave="this.classList.remove('bg-indigo-50')"
+            ondrop="importEmails(event)"
+            class="m-2 p-2 border-2 border-dashed border-stone-200 rounded-md text-center text-sm text-gray-500" {
+                "Drop .eml or mbox files here to import"
+        }
+    }
+}
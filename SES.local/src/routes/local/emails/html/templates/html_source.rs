@@ -0,0 +1,75 @@
+use std::sync::LazyLock;
+
+use maud::{html, Markup};
+use regex::Regex;
+
+// Within a tag: its name, quoted attribute values and attribute names followed by '='.
+static TAG_PART: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"^</?[^\s/>]+|"[^"]*"|'[^']*'|[^\s="'<>/]+\s*="#).unwrap());
+
+/// `source` with tags, attributes, values and comments coloured.
+pub fn build(source: &str) -> Markup {
+    let mut segments = vec![];
+    let mut rest = source;
+    while let Some(start) = rest.find('<') {
+        segments.push(("", &rest[..start]));
+        rest = &rest[start..];
+        let end = match rest.starts_with("<!--") {
+            true => rest.find("-->").map_or(rest.len(), |end| end + 3),
+            false => rest.find('>').map_or(rest.len(), |end| end + 1),
+        };
+        match rest.starts_with("<!--") {
+            true => segments.push(("text-gray-400", &rest[..end])),
+            false => tag(&rest[..end], &mut segments),
+        }
+        rest = &rest[end..];
+    }
+    segments.push(("", rest));
+    html! {
+        pre class="p-4 text-sm whitespace-pre-wrap wrap-anywhere" {
+            @for (class, text) in segments.into_iter().filter(|(_, text)| !text.is_empty()) {
+                @if class.is_empty() { (text) } @else { span class=(class) { (text) } }
+            }
+        }
+    }
+}
+
+fn tag<'a>(tag: &'a str, segments: &mut Vec<(&'static str, &'a str)>) {
+    let mut last = 0;
+    for part in TAG_PART.find_iter(tag) {
+        segments.push(("text-indigo-700", &tag[last..part.start()]));
+        let class = match part.as_str().chars().next() {
+            Some('<') => "text-indigo-700",
+            Some('"' | '\'') => "text-green-700",
+            _ => "text-amber-700",
+        };
+        segments.push((class, part.as_str()));
+        last = part.end();
+    }
+    segments.push(("text-indigo-700", &tag[last..]));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn highlights() {
+        assert_eq!(
+            build(r#"<!-- c --><a href="x">1 &lt; 2</a>"#).into_string(),
+            concat!(
+                r#"<pre class="p-4 text-sm whitespace-pre-wrap wrap-anywhere">"#,
+                r#"<span class="text-gray-400">&lt;!-- c --&gt;</span>"#,
+                r#"<span class="text-indigo-700">&lt;a</span>"#,
+                r#"<span class="text-indigo-700"> </span>"#,
+                r#"<span class="text-amber-700">href=</span>"#,
+                r#"<span class="text-green-700">&quot;x&quot;</span>"#,
+                r#"<span class="text-indigo-700">&gt;</span>"#,
+                "1 &amp;lt; 2",
+                r#"<span class="text-indigo-700">&lt;/a</span>"#,
+                r#"<span class="text-indigo-700">&gt;</span>"#,
+                "</pre>"
+            )
+        );
+    }
+}
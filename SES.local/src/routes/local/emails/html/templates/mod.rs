@@ -1,5 +1,10 @@
+pub mod address;
+pub mod attachments;
 pub mod email;
 pub mod email_row;
 pub mod emails;
+pub mod html_source;
 pub mod static_content;
 pub mod tag;
+pub mod thread;
+pub mod threads;
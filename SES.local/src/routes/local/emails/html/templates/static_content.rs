@@ -1 +1,4 @@
 pub const EMAIL_DETAIL_ID: &str = "email";
+pub const EMAIL_RESULTS_ID: &str = "email-results";
+pub const EMAILS_MORE_ID: &str = "emails-more";
+pub const EMAIL_ROWS_ID: &str = "email-rows";
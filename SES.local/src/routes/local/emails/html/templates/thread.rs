@@ -0,0 +1,82 @@
+use maud::{html, Markup};
+
+use crate::event_store::{Thread, ThreadMessage};
+
+use super::{address, static_content};
+
+/// The emails of `thread` as a tree, then the threading headers of the one with `message_id`.
+pub fn build(thread: &Thread, message_id: &str) -> Markup {
+    let current = thread
+        .messages
+        .iter()
+        .find(|m| m.email.response.message_id.as_deref() == Some(message_id));
+    html! {
+        div class="p-4 flex flex-col gap-4 grow" {
+            ol {
+                @for message in &thread.messages {
+                    @let id = message.email.response.message_id.as_deref().unwrap_or_default();
+                    @let summary = message.email.request.get_summary();
+                    li style=(format!("padding-left: {}rem", message.depth * 2)) {
+                        a hx-get=(format!("/emails/{}", id))
+                            hx-push-url="true"
+                            hx-target=(format!("#{}", static_content::EMAIL_DETAIL_ID))
+                            hx-swap="innerHTML"
+                            class=(format!(
+                                "block my-1 px-2 py-1 rounded-md cursor-pointer hover:bg-indigo-50 {}",
+                                if id == message_id { "bg-indigo-100" } else { "" }
+                            )) {
+                                span class="text-sm text-gray-500 mr-2" {
+                                    @match summary.from {
+                                        Some(from) => (address::names(&[from])),
+                                        None => "unknown",
+                                    }
+                                }
+                                (summary.subject.as_deref().unwrap_or("unknown"))
+                        }
+                    }
+                }
+            }
+            @if let Some(current) = current {
+                (headers(current))
+            }
+        }
+    }
+}
+
+// References that match no captured email are flagged, as a typo there breaks the thread.
+fn headers(message: &ThreadMessage) -> Markup {
+    let threading = &message.threading;
+    let ids = |ids: &[String]| {
+        html! {
+            @for id in ids {
+                div {
+                    (id)
+                    @if message.missing.contains(id) {
+                        span class="ml-2 text-xs text-amber-700" { "not captured" }
+                    }
+                }
+            }
+        }
+    };
+    html! {
+        dl class="text-sm" {
+            div class="flex" {
+                dt class="text-gray-500 flex-[140px] grow-0 shrink-0" { "Message-ID:" }
+                dd class="flex-grow wrap-anywhere" {
+                    (threading.message_id.as_deref().unwrap_or_default())
+                    @if let Some(replaced) = &threading.replaced_message_id {
+                        div class="text-xs text-amber-700" { "replaces " (replaced) " as SES would" }
+                    }
+                }
+            }
+            div class="flex" {
+                dt class="text-gray-500 flex-[140px] grow-0 shrink-0" { "In-Reply-To:" }
+                dd class="flex-grow wrap-anywhere" { (ids(&threading.in_reply_to)) }
+            }
+            div class="flex" {
+                dt class="text-gray-500 flex-[140px] grow-0 shrink-0" { "References:" }
+                dd class="flex-grow wrap-anywhere" { (ids(&threading.references)) }
+            }
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use maud::{html, Markup};
+
+use super::{address, emails, static_content};
+use crate::{
+    event_store::{EmailQuery, Thread},
+    page_template,
+};
+
+/// The threads page URL for `query`, leaving out paging.
+pub fn url(query: &EmailQuery) -> String {
+    emails::url(query).replacen("/emails", "/emails/threads", 1)
+}
+
+/// Threads with an email matching `query`, each opening its latest email.
+pub fn build(threads: &[Thread], query: &EmailQuery, email: Option<Markup>, uri: &str) -> Markup {
+    page_template::build(
+        html! {
+            div class="flex flex-row flex-grow min-h-full" {
+                div class="shrink-0 flex-[360px] grow-0 flex flex-col overflow-hidden" {
+                    div class="flex justify-between mx-2 my-2 text-sm" {
+                        span class="text-gray-500" { (threads.len()) " threads" }
+                        a href=(emails::url(query)) class="text-indigo-600" { "List emails" }
+                    }
+                    div id="threads" class="overflow-auto flex-grow inset-shadow-sm" {
+                        @for thread in threads {
+                            (row(thread))
+                        }
+                    }
+                }
+                div id=(static_content::EMAIL_DETAIL_ID) class="border-l-1 border-stone-100 grow shrink overflow-auto" {
+                    (email.unwrap_or(html! { "email" }))
+                }
+            }
+        },
+        uri,
+    )
+}
+
+fn row(thread: &Thread) -> Markup {
+    let latest = thread.messages.last().map(|m| m.email);
+    let latest_id = latest
+        .and_then(|email| email.response.message_id.as_deref())
+        .unwrap_or_default();
+    let mut senders: Vec<&str> = vec![];
+    for message in &thread.messages {
+        if let Some(from) = message.email.request.get_from() {
+            if !senders.contains(&from) {
+                senders.push(from);
+            }
+        }
+    }
+    html! {
+        a hx-get=(format!("/emails/{}", latest_id))
+            hx-push-url="true"
+            hx-target=(format!("#{}", static_content::EMAIL_DETAIL_ID))
+            hx-swap="innerHTML"
+            class="m-1 mb-2 p-3 rounded-md shadow-sm flex flex-col cursor-pointer hover:bg-indigo-50" {
+                div class="flex justify-between gap-2" {
+                    span class="wrap-anywhere" { (thread.subject.as_deref().unwrap_or("unknown")) }
+                    span class="shrink-0 text-sm text-gray-500" { (thread.messages.len()) }
+                }
+                @if !senders.is_empty() {
+                    div class="text-sm text-gray-500" { (address::names(&senders)) }
+                }
+        }
+    }
+}
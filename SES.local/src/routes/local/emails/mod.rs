@@ -1,26 +1,66 @@
 mod api;
-mod html;
+pub(crate) mod html;
 
 use axum::{
-    body::Body,
-    extract::{OriginalUri, Path, State},
-    http::{header::ACCEPT, Request, StatusCode},
-    response::IntoResponse,
-    routing::get,
-    Router,
+    body::{Body, Bytes},
+    extract::{DefaultBodyLimit, OriginalUri, Path, Query, State},
+    http::{header::HOST, Request, StatusCode},
+    response::{IntoResponse, Redirect},
+    routing::{get, post},
+    Extension, Router,
 };
-use axum_htmx::HxRequest;
+use axum_htmx::{HxRequest, HxTarget};
+use jiff::SignedDuration;
+use serde::Deserialize;
+
+use crate::{event_store::EmailQuery, routes::ContentOrigin};
+use html::templates::static_content;
+
+const DEFAULT_WAIT_TIMEOUT: SignedDuration = SignedDuration::from_secs(10);
+// Longer waits are cut to this, so a forgotten `?timeout=` doesn't hold a connection for days.
+const MAX_WAIT_TIMEOUT: SignedDuration = SignedDuration::from_mins(5);
+// mbox uploads can be far larger than axum's 2 MB default.
+const IMPORT_LIMIT: usize = 64 * 1024 * 1024;
+
+#[derive(Deserialize)]
+struct WaitQuery {
+    timeout: Option<SignedDuration>,
+}
+
+#[derive(Deserialize)]
+struct ContentQuery {
+    /// Let the body load images, styles and fonts from other hosts.
+    #[serde(default)]
+    remote: bool,
+}
+
+#[derive(Deserialize)]
+struct MatchQuery {
+    regex: String,
+}
 
 async fn emails(
     State(crate::AppState { event_store, .. }): State<crate::AppState>,
+    Query(query): Query<EmailQuery>,
+    HxTarget(hx_target): HxTarget,
     uri: OriginalUri,
     req: Request<Body>,
 ) -> impl IntoResponse {
-    if let Some(accept) = req.headers().get(ACCEPT) {
-        return match accept.to_str().unwrap() {
-            "text/event-stream" => html::emails_sse(&event_store).await.into_response(),
-            "application/json" => api::emails_json(&event_store).await.into_response(),
-            _ => html::emails_page(&event_store, None, uri)
+    if let Some(accept) = super::accept(req.headers()) {
+        return match accept {
+            "text/event-stream" => html::emails_sse(&event_store, query).await.into_response(),
+            "application/json" => api::emails_json(&event_store, &query).await.into_response(),
+            _ if hx_target.as_deref() == Some(static_content::EMAILS_MORE_ID) => {
+                html::emails_more(&event_store, &query)
+                    .await
+                    .into_response()
+            }
+            _ if hx_target.as_deref() == Some(static_content::EMAIL_RESULTS_ID) => {
+                html::emails_results(&event_store, &query)
+                    .await
+                    .into_response()
+            }
+            _ => html::emails_page(&event_store, &query, None, uri)
                 .await
                 .into_response(),
         };
@@ -35,8 +75,8 @@ async fn email(
     uri: OriginalUri,
     req: Request<Body>,
 ) -> impl IntoResponse {
-    if let Some(accept) = req.headers().get(ACCEPT) {
-        return match accept.to_str().unwrap() {
+    if let Some(accept) = super::accept(req.headers()) {
+        return match accept {
             "application/json" => api::email_json(&event_store, &id).await.into_response(),
             _ => html::email_page(&event_store, &id, hx_request, uri)
                 .await
@@ -46,21 +86,181 @@ async fn email(
     (StatusCode::NOT_FOUND).into_response()
 }
 
+async fn threads(
+    State(crate::AppState { event_store, .. }): State<crate::AppState>,
+    Query(query): Query<EmailQuery>,
+    uri: OriginalUri,
+    req: Request<Body>,
+) -> impl IntoResponse {
+    if let Some(accept) = super::accept(req.headers()) {
+        return match accept {
+            "application/json" => api::threads_json(&event_store, &query)
+                .await
+                .into_response(),
+            _ => html::threads_page(&event_store, &query, uri)
+                .await
+                .into_response(),
+        };
+    }
+    (StatusCode::NOT_FOUND).into_response()
+}
+
+async fn email_thread(
+    State(crate::AppState { event_store, .. }): State<crate::AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    api::thread_json(&event_store, &id).await
+}
+
+async fn delete_email(
+    State(crate::AppState { event_store, .. }): State<crate::AppState>,
+    Path(id): Path<String>,
+    HxRequest(hx_request): HxRequest,
+) -> impl IntoResponse {
+    match hx_request {
+        true => html::delete_email(&event_store, &id).await.into_response(),
+        false => api::delete_email(&event_store, &id).await.into_response(),
+    }
+}
+
+async fn delete_emails(
+    State(crate::AppState { event_store, .. }): State<crate::AppState>,
+    Query(query): Query<EmailQuery>,
+) -> impl IntoResponse {
+    api::delete_emails(&event_store, &query).await
+}
+
+async fn wait_for_email(
+    State(crate::AppState { event_store, .. }): State<crate::AppState>,
+    Query(query): Query<EmailQuery>,
+    Query(WaitQuery { timeout }): Query<WaitQuery>,
+) -> impl IntoResponse {
+    let timeout = timeout.unwrap_or(DEFAULT_WAIT_TIMEOUT);
+    api::wait_for_email(&event_store, &query, timeout).await
+}
+
+async fn import_emails(
+    State(crate::AppState { event_store, .. }): State<crate::AppState>,
+    upload: Bytes,
+) -> impl IntoResponse {
+    api::import_emails(&event_store, &upload).await
+}
+
+async fn emails_mbox(
+    State(crate::AppState { event_store, .. }): State<crate::AppState>,
+    Query(query): Query<EmailQuery>,
+) -> impl IntoResponse {
+    api::emails_mbox(&event_store, &query).await
+}
+
+async fn emails_stream(
+    State(crate::AppState { event_store, .. }): State<crate::AppState>,
+    Query(query): Query<EmailQuery>,
+) -> impl IntoResponse {
+    api::emails_stream(&event_store, query).await
+}
+
+async fn email_links(
+    State(crate::AppState { event_store, .. }): State<crate::AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    api::email_links(&event_store, &id).await
+}
+
+async fn email_attachments(
+    State(crate::AppState { event_store, .. }): State<crate::AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    api::email_attachments(&event_store, &id).await
+}
+
+async fn email_attachment(
+    State(crate::AppState { event_store, .. }): State<crate::AppState>,
+    Path((id, n)): Path<(String, usize)>,
+) -> impl IntoResponse {
+    api::email_attachment(&event_store, &id, n).await
+}
+
+async fn email_match(
+    State(crate::AppState { event_store, .. }): State<crate::AppState>,
+    Path(id): Path<String>,
+    Query(MatchQuery { regex }): Query<MatchQuery>,
+) -> impl IntoResponse {
+    api::email_match(&event_store, &id, &regex).await
+}
+
+async fn email_eml(
+    State(crate::AppState { event_store, .. }): State<crate::AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    api::email_eml(&event_store, &id).await
+}
+
+// With a content port the UI's origin only redirects there, so nothing in an email body ever
+// runs on the origin serving the UI.
 async fn email_content(
     State(crate::AppState { event_store, .. }): State<crate::AppState>,
     Path(id): Path<String>,
-    // req: Request<Body>,
+    Query(ContentQuery { remote }): Query<ContentQuery>,
+    content_origin: Option<Extension<ContentOrigin>>,
+    uri: OriginalUri,
+    req: Request<Body>,
 ) -> impl IntoResponse {
-    html::email_content(&event_store, &id).await.into_response()
+    if let Some(Extension(ContentOrigin { port, url, https })) = content_origin {
+        let origin = match url {
+            Some(url) => url.trim_end_matches('/').to_string(),
+            None => {
+                let host = req
+                    .headers()
+                    .get(HOST)
+                    .and_then(|host| host.to_str().ok())
+                    .unwrap_or("localhost");
+                let hostname = match host.rsplit_once(':') {
+                    Some((hostname, port)) if !port.ends_with(']') => hostname,
+                    _ => host,
+                };
+                let scheme = if https { "https" } else { "http" };
+                format!("{}://{}:{}", scheme, hostname, port)
+            }
+        };
+        let path = uri.path_and_query().map_or(uri.path(), |pq| pq.as_str());
+        return Redirect::temporary(&format!("{}{}", origin, path)).into_response();
+    }
+    html::email_content(&event_store, &id, remote)
+        .await
+        .into_response()
 }
 
 pub fn create() -> crate::AppStateRouter {
     Router::new().nest(
         "/emails",
         Router::new()
-            .route("/", get(emails))
-            .route("/{id}", get(email))
-            .route("/{id}/content", get(email_content)),
+            .route("/", get(emails).delete(delete_emails))
+            .route("/wait", get(wait_for_email))
+            .route("/stream", get(emails_stream))
+            .route("/mbox", get(emails_mbox))
+            .route("/threads", get(threads))
+            .route(
+                "/import",
+                post(import_emails).layer(DefaultBodyLimit::max(IMPORT_LIMIT)),
+            )
+            .route("/{id}", get(email).delete(delete_email))
+            .route("/{id}/content", get(email_content))
+            .route("/{id}/eml", get(email_eml))
+            .route("/{id}/thread", get(email_thread))
+            .route("/{id}/attachments", get(email_attachments))
+            .route("/{id}/attachments/{n}", get(email_attachment))
+            .route("/{id}/links", get(email_links))
+            .route("/{id}/match", get(email_match)),
+    )
+}
+
+pub fn content() -> crate::AppStateRouter {
+    Router::new().nest(
+        "/emails",
+        Router::new()
+            .route("/{id}/content", get(email_content))
+            .route("/{id}/attachments/{n}", get(email_attachment)),
     )
 }
 
@@ -68,7 +268,7 @@ pub fn create() -> crate::AppStateRouter {
 mod tests {
     use super::*;
     use crate::event_store::send_email::SendEmail;
-    use crate::event_store::{Event, EventContent, EventStore};
+    use crate::event_store::{Event, EventContent, EventStore, Page};
     use crate::AppState;
     use axum::{
         body::{to_bytes, Body},
@@ -146,19 +346,276 @@ mod tests {
         assert_eq!(response.status(), StatusCode::OK);
 
         let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
-        let resp: Vec<SendEmail> = serde_json::from_slice(&body_bytes).unwrap();
+        let resp: Page<SendEmail> = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(resp.next_cursor, None);
         for (e, a) in es
             .read()
             .await
             .get_all_emails()
             .into_iter()
-            .zip(resp)
+            .zip(resp.items)
             .collect::<Vec<(&SendEmail, SendEmail)>>()
         {
             assert_eq!(&a, e);
         }
     }
 
+    #[tokio::test]
+    async fn emails_json_query() {
+        let router = create();
+        let es = Arc::new(RwLock::new(EventStore::new()));
+        let mut expected = vec![];
+        {
+            let mut esw = es.write().await;
+            for to in ["a@example.com", "b@example.com", "a@example.com"] {
+                let se = SendEmail::new(create_send_email_input(Some(String::from(to))));
+                _ = esw
+                    .push(Event::new(EventContent::SendEmail(se.clone())))
+                    .await;
+                if to == "a@example.com" {
+                    expected.insert(0, se);
+                }
+            }
+        }
+        let mut router = router.with_state(AppState {
+            event_store: es.clone(),
+        });
+
+        let mut uri = String::from("/emails?to=A@example.com&limit=1");
+        let mut found = vec![];
+        loop {
+            let response = router
+                .call(
+                    Request::builder()
+                        .method(http::Method::GET)
+                        .header(http::header::ACCEPT, "application/json")
+                        .uri(&uri)
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let resp: Page<SendEmail> = serde_json::from_slice(&body_bytes).unwrap();
+            assert_eq!(resp.items.len(), 1);
+            found.extend(resp.items);
+            match resp.next_cursor {
+                Some(cursor) => uri = format!("/emails?to=A@example.com&limit=1&cursor={}", cursor),
+                None => break,
+            }
+        }
+        assert_eq!(found, expected);
+
+        // Paging carries on past an email deleted after its page was read.
+        let first = {
+            let esr = es.read().await;
+            let first = esr.get_all()[0];
+            format!("{}_{}", first.timestamp, first.id)
+        };
+        es.write()
+            .await
+            .delete_email(expected[0].response.message_id.as_deref().unwrap());
+        let response = router
+            .call(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .header(http::header::ACCEPT, "application/json")
+                    .uri(format!(
+                        "/emails?to=A@example.com&{}",
+                        serde_urlencoded::to_string([("cursor", first)]).unwrap()
+                    ))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let resp: Page<SendEmail> = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(resp.items, expected[1..]);
+
+        let response = router
+            .call(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .header(http::header::ACCEPT, "application/json")
+                    .uri("/emails?cursor=deleted")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let resp: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(resp["error"], "invalid cursor");
+    }
+
+    #[tokio::test]
+    async fn delete_emails_query() {
+        let router = create();
+        let es = Arc::new(RwLock::new(EventStore::new()));
+        {
+            let mut esw = es.write().await;
+            for to in ["a@example.com", "b@example.com", "a@example.com"] {
+                _ = esw
+                    .push(Event::new(EventContent::SendEmail(SendEmail::new(
+                        create_send_email_input(Some(String::from(to))),
+                    ))))
+                    .await;
+            }
+        }
+        let response = router
+            .with_state(AppState {
+                event_store: es.clone(),
+            })
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::DELETE)
+                    .uri("/emails?to=a@example.com")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let resp: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(resp["deleted"], 2);
+        let esr = es.read().await;
+        let remaining = esr.get_all_emails();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(
+            remaining[0].request.get_to().unwrap().to_addresses,
+            Some(vec![String::from("b@example.com")])
+        );
+    }
+
+    #[tokio::test]
+    async fn delete_email() {
+        let es = Arc::new(RwLock::new(EventStore::new()));
+        let se = SendEmail::new(create_send_email_input(Some(String::from("a@example.com"))));
+        _ = es
+            .write()
+            .await
+            .push(Event::new(EventContent::SendEmail(se.clone())))
+            .await;
+        let router = create().with_state(AppState {
+            event_store: es.clone(),
+        });
+        let uri = format!("/emails/{}", se.response.message_id.unwrap());
+        let delete = |hx_request: bool| {
+            let mut request = Request::builder().method(http::Method::DELETE).uri(&uri);
+            if hx_request {
+                request = request.header("HX-Request", "true");
+            }
+            router.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+
+        let response = delete(false).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(es.read().await.get_all_emails().is_empty());
+        let response = delete(false).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = delete(true).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8(body_bytes.to_vec())
+            .unwrap()
+            .starts_with("Email Not Found"));
+    }
+
+    #[tokio::test]
+    async fn threads_json() {
+        let es = Arc::new(RwLock::new(EventStore::new()));
+        let root = SendEmail::new(create_send_email_input(Some(String::from("a@example.com"))));
+        let reply = SendEmail::new(
+            serde_json::from_value(serde_json::json!({
+                "Content": {
+                    "Simple": {
+                        "Subject": { "Data": "Re: Hello" },
+                        "Headers": [{
+                            "Name": "In-Reply-To",
+                            "Value": format!(
+                                "<{}@email.amazonses.com>",
+                                root.response.message_id.as_ref().unwrap()
+                            )
+                        }]
+                    }
+                }
+            }))
+            .unwrap(),
+        );
+        for se in [&root, &reply] {
+            _ = es
+                .write()
+                .await
+                .push(Event::new(EventContent::SendEmail(se.clone())))
+                .await;
+        }
+        let router = create().with_state(AppState {
+            event_store: es.clone(),
+        });
+        let get = |uri: String| {
+            router.clone().oneshot(
+                Request::builder()
+                    .uri(uri)
+                    .header(http::header::ACCEPT, "application/json")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+        let root_id = root.response.message_id.clone().unwrap();
+        let reply_id = reply.response.message_id.clone().unwrap();
+
+        let response = get(String::from("/emails/threads")).await.unwrap();
+        let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let threads: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(threads.as_array().unwrap().len(), 1);
+        assert_eq!(threads[0]["id"], root_id.as_str());
+        assert_eq!(threads[0]["messages"][1]["parent"], root_id.as_str());
+        assert_eq!(threads[0]["messages"][1]["depth"], 1);
+
+        let response = get(format!("/emails/{}/thread", reply_id)).await.unwrap();
+        let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let thread: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(thread["id"], root_id.as_str());
+        assert_eq!(
+            thread["messages"][1]["threading"]["in_reply_to"][0],
+            format!("<{}@email.amazonses.com>", root_id)
+        );
+
+        let response = get(format!("/emails/{}/thread", Uuid::new_v4()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn emails_html_scripts_unescaped() {
+        let response = create()
+            .with_state(AppState {
+                event_store: Arc::new(RwLock::new(EventStore::new())),
+            })
+            .oneshot(
+                Request::builder()
+                    .header(http::header::ACCEPT, "text/html")
+                    .uri("/emails")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let resp = String::from_utf8(body_bytes.to_vec()).unwrap();
+        assert!(resp.contains(".then((response) => {"));
+        assert!(resp.contains(".map((c) => c.value)"));
+        assert!(!resp.contains("=&gt;"));
+    }
+
     #[tokio::test]
     async fn emails_html() {
         let router = create();
@@ -199,13 +656,474 @@ mod tests {
         assert_eq!(
             resp,
             crate::routes::local::emails::html::templates::emails::build(
-                &esr.get_all_emails(),
-                None
+                &esr.query_emails(&EmailQuery::default()).unwrap(),
+                &EmailQuery::default(),
+                None,
+                "/emails"
             )
             .into_string()
         );
     }
 
+    #[tokio::test]
+    async fn emails_html_filtered() {
+        let es = Arc::new(RwLock::new(EventStore::new()));
+        {
+            let mut esw = es.write().await;
+            for to in ["a@example.com", "b@example.com"] {
+                _ = esw
+                    .push(Event::new(EventContent::SendEmail(SendEmail::new(
+                        create_send_email_input(Some(String::from(to))),
+                    ))))
+                    .await;
+            }
+        }
+        let response = create()
+            .with_state(AppState {
+                event_store: es.clone(),
+            })
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .header(http::header::ACCEPT, "text/html")
+                    .header("HX-Request", "true")
+                    .header("HX-Target", static_content::EMAIL_RESULTS_ID)
+                    .uri("/emails?q=B%40example&from=&since=")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["HX-Push-Url"], "/emails?q=B%40example");
+        let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let resp = String::from_utf8(body_bytes.into_iter().collect()).unwrap();
+        assert!(!resp.contains("<html"));
+        assert!(resp.contains("b@example.com"));
+        assert!(!resp.contains("a@example.com"));
+        assert!(resp.contains(r#"sse-connect="/emails?q=B%40example""#));
+    }
+
+    #[tokio::test]
+    async fn emails_html_paged() {
+        let es = Arc::new(RwLock::new(EventStore::new()));
+        for n in 0..55 {
+            _ = es
+                .write()
+                .await
+                .push(Event::new(EventContent::SendEmail(SendEmail::new(
+                    create_send_email_input(Some(format!("{}@example.com", n))),
+                ))))
+                .await;
+        }
+        let router = create().with_state(AppState {
+            event_store: es.clone(),
+        });
+        let get = |uri: &str, target: Option<&str>| {
+            let mut request = Request::builder()
+                .method(http::Method::GET)
+                .header(http::header::ACCEPT, "text/html")
+                .uri(uri);
+            if let Some(target) = target {
+                request = request
+                    .header("HX-Request", "true")
+                    .header("HX-Target", target);
+            }
+            let request = request.body(Body::empty()).unwrap();
+            let router = router.clone();
+            async move {
+                let response = router.oneshot(request).await.unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                String::from_utf8(body_bytes.into_iter().collect()).unwrap()
+            }
+        };
+        let rows = |html: &str| html.matches(r#"hx-push-url="true""#).count();
+
+        let page = get("/emails", None).await;
+        assert_eq!(rows(&page), 50);
+        assert!(page.contains("54@example.com"));
+        assert!(!page.contains(">4@example.com"));
+        let cursor = {
+            let esr = es.read().await;
+            let last = esr.get_all()[49];
+            format!("{}_{}", last.timestamp, last.id)
+        };
+        let next = format!(
+            "/emails?{}",
+            serde_urlencoded::to_string([("cursor", cursor)]).unwrap()
+        );
+        assert!(page.contains(&format!(r#"hx-get="{}""#, next)));
+
+        let more = get(&next, Some(static_content::EMAILS_MORE_ID)).await;
+        assert_eq!(rows(&more), 5);
+        assert!(more.contains(">4@example.com"));
+        assert!(!more.contains(static_content::EMAILS_MORE_ID));
+
+        // The count covers every page, not just the rows shown.
+        let filtered = get("/emails?q=example", None).await;
+        assert_eq!(rows(&filtered), 50);
+        assert!(filtered.contains("55 matching"));
+
+        // Deleting the last row shown doesn't stop the next page loading.
+        let last = es.read().await.get_all()[49]
+            .get_message_id()
+            .unwrap()
+            .to_string();
+        assert!(es.write().await.delete_email(&last));
+        let more = get(&next, Some(static_content::EMAILS_MORE_ID)).await;
+        assert_eq!(rows(&more), 5);
+        assert!(more.contains(">4@example.com"));
+    }
+
+    #[tokio::test]
+    async fn wait_for_email_arrives() {
+        let es = Arc::new(RwLock::new(EventStore::new()));
+        let router = create().with_state(AppState {
+            event_store: es.clone(),
+        });
+        let waiting = tokio::spawn(
+            router.oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/emails/wait?to=a@example.com&timeout=5s")
+                    .body(Body::empty())
+                    .unwrap(),
+            ),
+        );
+        let se = SendEmail::new(create_send_email_input(Some(String::from("a@example.com"))));
+        {
+            let mut esw = es.write().await;
+            _ = esw
+                .push(Event::new(EventContent::SendEmail(SendEmail::new(
+                    create_send_email_input(Some(String::from("b@example.com"))),
+                ))))
+                .await;
+            _ = esw
+                .push(Event::new(EventContent::SendEmail(se.clone())))
+                .await;
+        }
+        let response = waiting.await.unwrap().unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let resp: SendEmail = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(resp, se);
+    }
+
+    #[tokio::test]
+    async fn wait_for_email_timeout_408_with_near_misses() {
+        let router = create();
+        let es = Arc::new(RwLock::new(EventStore::new()));
+        let se = SendEmail::new(create_send_email_input(Some(String::from("a@example.com"))));
+        {
+            let mut esw = es.write().await;
+            _ = esw
+                .push(Event::new(EventContent::SendEmail(se.clone())))
+                .await;
+        }
+        let response = router
+            .with_state(AppState {
+                event_store: es.clone(),
+            })
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/emails/wait?to=a@example.com&subject_contains=reset&timeout=50ms")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::REQUEST_TIMEOUT);
+
+        let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let resp: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(resp["near_misses"], serde_json::json!([se]));
+    }
+
+    #[tokio::test]
+    async fn wait_for_email_timeout_408_with_one_criterion() {
+        let es = Arc::new(RwLock::new(EventStore::new()));
+        let same_domain =
+            SendEmail::new(create_send_email_input(Some(String::from("b@example.com"))));
+        let other_domain =
+            SendEmail::new(create_send_email_input(Some(String::from("a@example.org"))));
+        {
+            let mut esw = es.write().await;
+            for se in [&same_domain, &other_domain] {
+                _ = esw
+                    .push(Event::new(EventContent::SendEmail(se.clone())))
+                    .await;
+            }
+        }
+        let response = create()
+            .with_state(AppState { event_store: es })
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/emails/wait?to=a@example.com&timeout=50ms")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::REQUEST_TIMEOUT);
+
+        let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let resp: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(resp["near_misses"], serde_json::json!([same_domain]));
+    }
+
+    #[tokio::test]
+    async fn wait_for_email_oldest_first() {
+        let es = Arc::new(RwLock::new(EventStore::new()));
+        let mut sent = vec![];
+        for _ in 0..2 {
+            let se = SendEmail::new(create_send_email_input(Some(String::from("a@example.com"))));
+            _ = es
+                .write()
+                .await
+                .push(Event::new(EventContent::SendEmail(se.clone())))
+                .await;
+            sent.push(se);
+        }
+        let router = create().with_state(AppState {
+            event_store: es.clone(),
+        });
+        let wait = |uri: &'static str| {
+            router.clone().oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri(uri)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        let response = wait("/emails/wait?to=a@example.com&limit=1").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let resp: SendEmail = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(resp, sent[0]);
+
+        let response = wait("/emails/wait?to=a@example.com&timeout=-1s")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let resp: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(resp["error"], "timeout must not be negative");
+    }
+
+    #[tokio::test]
+    async fn email_match_invalid_regex_400() {
+        let router = create();
+        let es = Arc::new(RwLock::new(EventStore::new()));
+        let message_id = {
+            let mut esw = es.write().await;
+            let se = SendEmail::new(create_send_email_input(Some(String::from("a@example.com"))));
+            _ = esw
+                .push(Event::new(EventContent::SendEmail(se.clone())))
+                .await;
+            se.response.message_id.unwrap()
+        };
+        let response = router
+            .with_state(AppState {
+                event_store: es.clone(),
+            })
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri(format!("/emails/{}/match?regex=(", message_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let resp: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+        assert!(resp["error"]
+            .as_str()
+            .unwrap()
+            .contains("regex parse error"));
+    }
+
+    #[tokio::test]
+    async fn email_links_email_not_found_404() {
+        let router = create();
+        let es = Arc::new(RwLock::new(EventStore::new()));
+        let response = router
+            .with_state(AppState {
+                event_store: es.clone(),
+            })
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .uri(format!("/emails/{}/links", Uuid::new_v4()))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn email_eml() {
+        let raw = b"From: s@example.com\r\nTo: a@example.com\r\nSubject: Raw\r\n\r\nAs is\r\n";
+        let se = SendEmail::new(crate::event_store::send_email::eml::parse(raw).unwrap());
+        let message_id = se.response.message_id.clone().unwrap();
+        let es = Arc::new(RwLock::new(EventStore::new()));
+        _ = es
+            .write()
+            .await
+            .push(Event::new(EventContent::SendEmail(se)))
+            .await;
+        let router = create().with_state(AppState { event_store: es });
+
+        let response = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/emails/{}/eml", message_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[http::header::CONTENT_TYPE],
+            "message/rfc822"
+        );
+        assert_eq!(
+            response.headers()[http::header::CONTENT_DISPOSITION],
+            format!("attachment; filename=\"{}.eml\"", message_id).as_str()
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], raw);
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/emails/{}/eml", Uuid::new_v4()))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn email_attachment() {
+        let input: SendEmailInput = serde_json::from_value(serde_json::json!({
+            "Content": {
+                "Simple": {
+                    "Attachments": [
+                        { "RawContent": "aGk=", "FileName": "notes.txt", "ContentType": "text/plain" },
+                        { "RawContent": "PGI+", "FileName": "page.html", "ContentType": "text/html" }
+                    ]
+                }
+            }
+        }))
+        .unwrap();
+        let se = SendEmail::new(input);
+        let message_id = se.response.message_id.clone().unwrap();
+        let es = Arc::new(RwLock::new(EventStore::new()));
+        _ = es
+            .write()
+            .await
+            .push(Event::new(EventContent::SendEmail(se)))
+            .await;
+        let router = create().with_state(AppState { event_store: es });
+        let get = |n: usize| {
+            router.clone().oneshot(
+                Request::builder()
+                    .uri(format!("/emails/{}/attachments/{}", message_id, n))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        let response = get(0).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[http::header::CONTENT_TYPE], "text/plain");
+        assert_eq!(
+            response.headers()[http::header::CONTENT_DISPOSITION],
+            "inline; filename=\"notes.txt\""
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"hi");
+
+        // html could run script on this origin, so it's only ever downloaded
+        let response = get(1).await.unwrap();
+        assert_eq!(
+            response.headers()[http::header::CONTENT_TYPE],
+            "application/octet-stream"
+        );
+        assert_eq!(
+            response.headers()[http::header::CONTENT_DISPOSITION],
+            "attachment; filename=\"page.html\""
+        );
+
+        assert_eq!(get(2).await.unwrap().status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn import_emails() {
+        let es = Arc::new(RwLock::new(EventStore::new()));
+        let router = create().with_state(AppState {
+            event_store: es.clone(),
+        });
+        let import = |body: &'static str| {
+            router.clone().oneshot(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/emails/import")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+        };
+
+        let response = import("From: s@example.com\r\nTo: a@example.com\r\n\r\nHi\r\n")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = import(
+            "From s@example.com Mon Jan  1 00:00:00 2024\nTo: b@example.com\n\nOne\n\n\
+             From s@example.com Mon Jan  1 00:00:00 2024\nTo: c@example.com\n\nTwo\n\n",
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["imported"], 2);
+
+        let esr = es.read().await;
+        let emails = esr.get_all_emails();
+        assert_eq!(emails.len(), 3);
+        assert!(emails.iter().all(|email| email.imported));
+        assert_eq!(emails[0].request.get_tag().to_string(), "Raw");
+        assert_eq!(
+            emails[0].request.get_to().unwrap().to_addresses,
+            Some(vec![String::from("c@example.com")])
+        );
+        drop(esr);
+
+        let response = import("").await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 3)]
     async fn emails_sse() {
         // setup app with routes running in tokio thread
@@ -292,6 +1210,67 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn emails_sse_changes() {
+        let es = Arc::new(RwLock::new(EventStore::new()));
+        let se1 = SendEmail::new(create_send_email_input(Some("a@example.com".to_string())));
+        let se2 = SendEmail::new(create_send_email_input(Some("b@example.com".to_string())));
+        _ = es
+            .write()
+            .await
+            .push(Event::new(EventContent::SendEmail(se1.clone())))
+            .await;
+        let response = create()
+            .with_state(AppState {
+                event_store: es.clone(),
+            })
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::GET)
+                    .header(http::header::ACCEPT, "text/event-stream")
+                    .uri("/emails")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let events = response
+            .into_body()
+            .into_data_stream()
+            .eventsource()
+            .take(3);
+
+        {
+            let mut esw = es.write().await;
+            _ = esw
+                .push(Event::new(EventContent::SendEmail(se2.clone())))
+                .await;
+            esw.delete_emails(&EmailQuery {
+                to: Some(String::from("a@example.com")),
+                ..Default::default()
+            });
+            esw.clear();
+        }
+        let events = events
+            .map(|event| event.unwrap().data)
+            .collect::<Vec<String>>()
+            .await;
+        assert_eq!(
+            events,
+            [
+                crate::routes::local::emails::html::templates::email_row::build(&se2).into_string(),
+                format!(
+                    r#"<div id="email-row-{}" hx-swap-oob="delete"></div>"#,
+                    se1.response.message_id.unwrap()
+                ),
+                format!(
+                    r#"<div id="{}" hx-swap-oob="innerHTML"></div>"#,
+                    static_content::EMAIL_ROWS_ID
+                ),
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn emails_no_accept_header_404() {
         let router = create();
@@ -365,7 +1344,7 @@ mod tests {
                 Request::builder()
                     .method(http::Method::GET)
                     .header(http::header::ACCEPT, "application/json")
-                    .uri(format!("/emails/{}", Uuid::new_v4().to_string()))
+                    .uri(format!("/emails/{}", Uuid::new_v4()))
                     .body(Body::empty())
                     .unwrap(),
             )
@@ -410,10 +1389,14 @@ mod tests {
         assert_eq!(
             resp,
             crate::routes::local::emails::html::templates::emails::build(
-                &esr.get_all_emails(),
+                &esr.query_emails(&EmailQuery::default()).unwrap(),
+                &EmailQuery::default(),
                 Some(crate::routes::local::emails::html::templates::email::build(
-                    esr.get_email_by_message_id(&message_id).unwrap()
+                    esr.get_email_by_message_id(&message_id).unwrap(),
+                    esr.thread(&message_id).as_ref(),
+                    &esr.get_events_by_message_id(&message_id)
                 )),
+                &format!("/emails/{}", message_id),
             )
             .into_string()
         );
@@ -446,8 +1429,13 @@ mod tests {
         assert_eq!(
             resp,
             crate::routes::local::emails::html::templates::emails::build(
-                &es.read().await.get_all_emails(),
+                &es.read()
+                    .await
+                    .query_emails(&EmailQuery::default())
+                    .unwrap(),
+                &EmailQuery::default(),
                 Some(html! { (format!("Email Not Found: {}", message_id))}),
+                &format!("/emails/{}", message_id),
             )
             .into_string()
         );
@@ -489,7 +1477,9 @@ mod tests {
         assert_eq!(
             resp,
             crate::routes::local::emails::html::templates::email::build(
-                esr.get_email_by_message_id(&message_id).unwrap()
+                esr.get_email_by_message_id(&message_id).unwrap(),
+                esr.thread(&message_id).as_ref(),
+                &esr.get_events_by_message_id(&message_id)
             )
             .into_string()
         );
@@ -1,11 +1,67 @@
-use axum::{response::IntoResponse, Json};
+use std::convert::Infallible;
+
+use axum::{
+    response::{
+        sse::{self, KeepAlive},
+        IntoResponse, Sse,
+    },
+    Json,
+};
+use futures::{future, Stream, StreamExt};
 use reqwest::StatusCode;
 use serde_json::json;
 
-use crate::AppEventStore;
+use crate::{
+    event_store::{Change, Event, EventQuery},
+    AppEventStore,
+};
+
+pub async fn events_json(event_store: &AppEventStore, query: &EventQuery) -> impl IntoResponse {
+    match event_store.read().await.query_events(query) {
+        Ok(page) => Json(json!(page)).into_response(),
+        Err(err) => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": err.to_string() })),
+        )
+            .into_response(),
+    }
+}
 
-pub async fn events_json(event_store: &AppEventStore) -> impl IntoResponse {
-    Json(json!(event_store.read().await.get_all()))
+/// Newly stored events matching `query`, as JSON for non-browser consumers such as `app tail`.
+/// Updates and deletes of matching events follow as `updated` and `deleted` events, clearing
+/// the store as `cleared`, and imports or restores, after which to list the events again, as
+/// `reloaded`.
+pub async fn events_stream(
+    event_store: &AppEventStore,
+    query: EventQuery,
+) -> Sse<impl Stream<Item = Result<sse::Event, Infallible>>> {
+    let changes = event_store.read().await.get_changes();
+    let events = changes.filter_map(move |change| {
+        let event = match change {
+            Change::Added(ev) => query.matches(&ev).then_some(("event", ev)),
+            Change::Updated(ev) => query.matches(&ev).then_some(("updated", ev)),
+            Change::Deleted(ev) => query.matches(&ev).then_some(("deleted", ev)),
+            Change::Cleared => {
+                return future::ready(Some(Ok(sse::Event::default().event("cleared").data("{}"))))
+            }
+            Change::Reloaded => {
+                return future::ready(Some(Ok(sse::Event::default().event("reloaded").data("{}"))))
+            }
+        };
+        future::ready(event.map(|(name, ev)| {
+            Ok(sse::Event::default()
+                .event(name)
+                .data(json!(ev).to_string()))
+        }))
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+pub async fn import_events(event_store: &AppEventStore, events: Vec<Event>) -> impl IntoResponse {
+    match event_store.write().await.import(events).await {
+        Ok(imported) => Json(json!({ "imported": imported })).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR).into_response(),
+    }
 }
 
 pub async fn event_json(event_store: &AppEventStore, id: &str) -> impl IntoResponse {
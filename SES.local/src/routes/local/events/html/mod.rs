@@ -1,5 +1,10 @@
-use crate::AppEventStore;
+use crate::{
+    event_store::{Change, EventQuery},
+    routes::local::paged,
+    AppEventStore,
+};
 use axum::extract::OriginalUri;
+use axum::http::StatusCode;
 use axum::response::{sse::Event, Html, IntoResponse, Sse};
 use futures::{Stream, StreamExt};
 use maud::{html, Markup};
@@ -7,15 +12,20 @@ use std::{io::Error, time::Duration};
 
 pub mod templates;
 
+/// Rows for new events, out-of-band swaps for rows that were updated or deleted, and the first
+/// page again after an import or restore.
 pub async fn events_sse(
     event_store: &AppEventStore,
 ) -> Sse<impl Stream<Item = Result<Event, Error>>> {
-    let stream = event_store.read().await.get_stream();
-    let events = stream.map(|re| {
-        tracing::debug!("{:?}", re);
-        Ok(Event::default()
-            .event("event")
-            .data(templates::event_row::build(&re).into_string()))
+    let changes = event_store.read().await.get_changes();
+    let event_store = event_store.clone();
+    let events = changes.then(move |change| {
+        let event_store = event_store.clone();
+        async move {
+            Ok(Event::default()
+                .event("event")
+                .data(change_markup(change, &event_store).await.into_string()))
+        }
     });
     Sse::new(events).keep_alive(
         axum::response::sse::KeepAlive::new()
@@ -24,14 +34,53 @@ pub async fn events_sse(
     )
 }
 
+async fn change_markup(change: Change, event_store: &AppEventStore) -> Markup {
+    tracing::debug!("{:?}", change);
+    match change {
+        Change::Added(re) => templates::event_row::build(&re),
+        Change::Updated(re) => templates::event_row::updated(&re),
+        Change::Deleted(re) => {
+            html! { div id=(templates::event_row::id(&re)) hx-swap-oob="delete" {} }
+        }
+        Change::Cleared => html! {
+            div id=(templates::static_content::EVENTS_DETAIL_ID) hx-swap-oob="innerHTML" {}
+        },
+        Change::Reloaded => {
+            let esr = event_store.read().await;
+            let query = paged(&EventQuery::default());
+            html! {
+                div id=(templates::static_content::EVENTS_DETAIL_ID) hx-swap-oob="innerHTML" {
+                    @if let Ok(evs) = esr.query_events(&query) {
+                        (templates::events::rows(&evs, &query))
+                    }
+                }
+            }
+        }
+    }
+}
+
 pub async fn events_page(
     event_store: &AppEventStore,
+    query: &EventQuery,
     event: Option<Markup>,
     uri: OriginalUri,
 ) -> impl IntoResponse {
     let esr = event_store.read().await;
-    let evs = esr.get_all();
-    Html(templates::events::build(&evs, event, uri.path()).into_string())
+    let evs = match esr.query_events(&paged(query)) {
+        Ok(evs) => evs,
+        Err(err) => return (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    };
+    Html(templates::events::build(&evs, query, event, uri.path()).into_string()).into_response()
+}
+
+/// The rows after `query.cursor`, for infinite scroll.
+pub async fn events_more(event_store: &AppEventStore, query: &EventQuery) -> impl IntoResponse {
+    let esr = event_store.read().await;
+    let evs = match esr.query_events(&paged(query)) {
+        Ok(evs) => evs,
+        Err(err) => return (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    };
+    Html(templates::events::rows(&evs, query).into_string()).into_response()
 }
 
 pub async fn event_page(
@@ -43,19 +92,28 @@ pub async fn event_page(
     let esr = event_store.read().await;
     match esr.get_by_event_id(id) {
         Some(em) => {
-            let event_content = templates::event::build(em);
+            let timeline = em
+                .get_message_id()
+                .map(|id| esr.get_events_by_message_id(id))
+                .unwrap_or_default();
+            let event_content = templates::event::build(em, &timeline);
             match hx_request {
                 true => Html(event_content.into_string()).into_response(),
-                false => events_page(event_store, Some(event_content), uri)
-                    .await
-                    .into_response(),
+                false => events_page(
+                    event_store,
+                    &EventQuery::default(),
+                    Some(event_content),
+                    uri,
+                )
+                .await
+                .into_response(),
             }
         }
         None => {
             let not_found = html! { (format!("Event Not Found: {}", id))};
             match hx_request {
                 true => Html(not_found.into_string()).into_response(),
-                false => events_page(event_store, Some(not_found), uri)
+                false => events_page(event_store, &EventQuery::default(), Some(not_found), uri)
                     .await
                     .into_response(),
             }
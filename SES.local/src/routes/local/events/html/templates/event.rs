@@ -1,11 +1,120 @@
 use maud::{html, Markup};
 
-use crate::event_store::Event;
+use super::timeline;
+use crate::{
+    event_store::{
+        send_email::{self, SendEmail},
+        Event, EventContent,
+    },
+    routes::local::emails::html::templates::{address, tag},
+};
 
-pub fn build(event: &Event) -> Markup {
+/// The event's fields laid out for its type, the timeline of the message it's about, and the
+/// stored JSON.
+pub fn build(event: &Event, timeline: &[&Event]) -> Markup {
+    let mut fields = vec![
+        ("id", html! { (event.id) }),
+        ("type", html! { (event.get_name()) }),
+        ("timestamp", html! { (event.timestamp) }),
+    ];
+    if let Some(message_id) = event.get_message_id() {
+        fields.push((
+            "email",
+            html! {
+                a href=(format!("/emails/{}", message_id)) class="text-indigo-700 wrap-anywhere" {
+                    (message_id)
+                }
+            },
+        ));
+    }
+    html! {
+        div class="flex flex-col gap-4" {
+            (list(fields))
+            @match &event.content {
+                Some(EventContent::SendEmail(email)) => (send_email(email)),
+                None => p class="text-gray-500" { "This event has no content." },
+            }
+            @if !timeline.is_empty() {
+                section {
+                    h3 class="mb-2 font-semibold" { "Timeline" }
+                    (timeline::build(timeline, Some(&event.id)))
+                }
+            }
+            details {
+                summary class="cursor-pointer text-gray-500" { "JSON" }
+                pre class="text-xs" {
+                    (serde_json::to_string_pretty(event).unwrap())
+                }
+            }
+        }
+    }
+}
+
+fn send_email(email: &SendEmail) -> Markup {
+    let content = send_email::email_content(&email.request);
+    let mut fields = vec![(
+        "content",
+        html! {
+            span class="inline-flex items-center gap-2" {
+                (tag::build(&email.request.get_tag()))
+                (email.request.get_tag())
+                @if email.imported { " (imported)" }
+            }
+        },
+    )];
+    if let Some(from) = content.from {
+        fields.push(("from", address::mailbox(from)));
+    }
+    if let Some(to) = content.to {
+        for (dt, addresses) in [
+            ("to", &to.to_addresses),
+            ("cc", &to.cc_addresses),
+            ("bcc", &to.bcc_addresses),
+        ] {
+            if let Some(addresses) = addresses.as_ref().filter(|a| !a.is_empty()) {
+                fields.push((dt, address::list(addresses)));
+            }
+        }
+    }
+    fields.push((
+        "subject",
+        html! { (content.subject.as_deref().unwrap_or("")) },
+    ));
+    if let Some(configuration_set) = content.configuration_set {
+        fields.push(("config set", html! { (configuration_set) }));
+    }
+    if let Some(tags) = content.tags.filter(|tags| !tags.is_empty()) {
+        fields.push((
+            "tags",
+            html! {
+                @for tag in tags {
+                    span class="inline-block mr-1 px-2 rounded-full bg-stone-100 text-sm" {
+                        (tag.name) "=" (tag.value)
+                    }
+                }
+            },
+        ));
+    }
+    if !content.attachments.is_empty() {
+        fields.push(("attachments", html! { (content.attachments.len()) }));
+    }
+    html! {
+        section {
+            h3 class="mb-2 font-semibold" { "SendEmail" }
+            (list(fields))
+        }
+    }
+}
+
+fn list(fields: Vec<(&str, Markup)>) -> Markup {
     html! {
-        pre class="text-xs" {
-            (serde_json::to_string_pretty(event).unwrap())
+        dl {
+            @for (dt, dd) in fields {
+                div class="flex" {
+                    dt class="text-gray-500 flex-[100px] grow-0 shrink-0" { (dt) ":" }
+                    dd class="flex-grow wrap-anywhere" { (dd) }
+                }
+            }
         }
     }
 }
@@ -3,12 +3,26 @@ use crate::event_store::Event;
 use maud::{html, Markup};
 
 pub fn build(event: &Event) -> Markup {
+    row(event, false)
+}
+
+/// The row of an event that changed, badged, to swap out-of-band for the one shown.
+pub fn updated(event: &Event) -> Markup {
+    row(event, true)
+}
+
+/// The row's element id.
+pub fn id(event: &Event) -> String {
+    format!("event-row-{}", event.id)
+}
+
+fn row(event: &Event, updated: bool) -> Markup {
     let dl = vec![
         ("name", event.get_name()),
         ("timestamp", event.timestamp.clone()),
     ];
     html! {
-        div class="
+        div id=(id(event)) hx-swap-oob=[updated.then_some("outerHTML")] class="
             flex
             items-start
             m-1
@@ -32,6 +46,9 @@ pub fn build(event: &Event) -> Markup {
                             dd { (dd) }
                         }
                     }
+                    @if updated {
+                        span class="text-xs text-amber-600" { "updated" }
+                    }
                 }
             }
             button
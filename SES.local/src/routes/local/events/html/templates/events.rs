@@ -1,9 +1,17 @@
 use super::event_row;
 use super::static_content;
-use crate::{event_store::Event, page_template};
+use crate::{
+    event_store::{Event, EventQuery, Page},
+    page_template,
+};
 use maud::{html, Markup};
 
-pub fn build(events: &Vec<&Event>, event: Option<Markup>, uri: &str) -> Markup {
+pub fn build(
+    events: &Page<&Event>,
+    query: &EventQuery,
+    event: Option<Markup>,
+    uri: &str,
+) -> Markup {
     page_template::build(
         html! {
             script {
@@ -24,9 +32,7 @@ pub fn build(events: &Vec<&Event>, event: Option<Markup>, uri: &str) -> Markup {
                         div hx-ext="sse" sse-connect="/events" sse-swap="event" hx-swap="afterbegin" hx-target=(format!("#{}", static_content::EVENTS_DETAIL_ID)) {
                         }
                         div id=(static_content::EVENTS_DETAIL_ID) class="overflow-auto flex-grow snap-y snap-mandatory inset-shadow-sm" {
-                            @for ev in events {
-                                (event_row::build(&ev))
-                            }
+                            (rows(events, query))
                         }
                         div class="p-4 flex justify-end border-t-1 border-stone-100" {
                             button
@@ -46,3 +52,33 @@ pub fn build(events: &Vec<&Event>, event: Option<Markup>, uri: &str) -> Markup {
         uri,
     )
 }
+
+/// A page of rows, followed by what loads the next one when scrolled into view.
+pub fn rows(events: &Page<&Event>, query: &EventQuery) -> Markup {
+    html! {
+        @for ev in &events.items {
+            (event_row::build(ev))
+        }
+        @if let Some(cursor) = &events.next_cursor {
+            @let next = href(&EventQuery {
+                cursor: Some(cursor.clone()),
+                ..query.clone()
+            });
+            div id=(static_content::EVENTS_MORE_ID) hx-get=(next) hx-trigger="intersect once"
+                hx-swap="outerHTML" class="m-2 text-center" {
+                    button type="button" hx-get=(next)
+                        hx-target=(format!("#{}", static_content::EVENTS_MORE_ID))
+                        hx-swap="outerHTML" class="text-sm text-indigo-600" {
+                            "Load more"
+                    }
+            }
+        }
+    }
+}
+
+fn href(query: &EventQuery) -> String {
+    match serde_urlencoded::to_string(query) {
+        Ok(query) if !query.is_empty() => format!("/events?{}", query),
+        _ => String::from("/events"),
+    }
+}
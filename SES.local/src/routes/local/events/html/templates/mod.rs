@@ -2,3 +2,4 @@ pub mod event;
 pub mod event_row;
 pub mod events;
 pub mod static_content;
+pub mod timeline;
@@ -1,2 +1,3 @@
 pub const EVENT_DETAIL_ID: &str = "event";
 pub const EVENTS_DETAIL_ID: &str = "events";
+pub const EVENTS_MORE_ID: &str = "events-more";
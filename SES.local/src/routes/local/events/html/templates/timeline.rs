@@ -0,0 +1,81 @@
+use jiff::Timestamp;
+use maud::{html, Markup};
+
+use crate::event_store::{Event, EventContent};
+
+/// One thing that happened to a message, as recorded by an event.
+struct Step<'a> {
+    name: &'static str,
+    detail: String,
+    event: &'a Event,
+    timestamp: Option<Timestamp>,
+}
+
+/// What SES.local recorded for one message, oldest first, each step with the time since the
+/// first and the one before, and a link to the event that recorded it. The event with `current`
+/// as its id is highlighted. Only requests are captured, so the rest of an email's lifecycle
+/// (delivery, bounces, complaints, opens and clicks) is noted as missing rather than shown.
+pub fn build(events: &[&Event], current: Option<&str>) -> Markup {
+    let steps: Vec<Step> = events.iter().filter_map(|event| step(event)).collect();
+    let first = steps.first().and_then(|step| step.timestamp);
+    html! {
+        ol class="border-l-2 border-indigo-200 ml-2" {
+            @for (n, step) in steps.iter().enumerate() {
+                @let previous = n.checked_sub(1).and_then(|p| steps[p].timestamp);
+                li class=(format!(
+                    "relative pl-4 pb-2 {}",
+                    if current == Some(step.event.id.as_str()) { "bg-indigo-50" } else { "" }
+                )) {
+                    span class="absolute -left-[5px] top-2 w-2 h-2 rounded-full bg-indigo-400" {}
+                    div class="flex flex-wrap items-baseline gap-2" {
+                        a href=(format!("/events/{}", step.event.id)) class="font-semibold text-indigo-700" {
+                            (step.name)
+                        }
+                        span class="text-sm text-gray-500" { (step.event.timestamp) }
+                        @if let (Some(first), Some(at)) = (first, step.timestamp) {
+                            @if n > 0 {
+                                span class="text-xs text-gray-500" {
+                                    "+" (since(first, at))
+                                    @if let Some(previous) = previous {
+                                        " (" (since(previous, at)) " after " (steps[n - 1].name) ")"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    div class="text-sm" { (step.detail) }
+                }
+            }
+        }
+        p class="mt-1 text-xs text-gray-500" {
+            "Delivery, bounce, complaint, open and click events aren't captured."
+        }
+    }
+}
+
+fn step(event: &Event) -> Option<Step<'_>> {
+    let (name, detail) = match &event.content {
+        Some(EventContent::SendEmail(email)) if email.imported => {
+            ("Import", String::from("Uploaded as an RFC 5322 message"))
+        }
+        Some(EventContent::SendEmail(email)) => (
+            "Send",
+            format!(
+                "SendEmail with {} content, accepted as {}",
+                email.request.get_tag(),
+                email.response.message_id.as_deref().unwrap_or("unknown")
+            ),
+        ),
+        None => return None,
+    };
+    Some(Step {
+        name,
+        detail,
+        event,
+        timestamp: event.timestamp.parse().ok(),
+    })
+}
+
+fn since(from: Timestamp, to: Timestamp) -> String {
+    format!("{:#}", to.duration_since(from))
+}
@@ -1,26 +1,36 @@
 mod api;
-mod html;
+pub(crate) mod html;
 
 use axum::{
     body::Body,
-    extract::{OriginalUri, Path, State},
-    http::{header::ACCEPT, Request, StatusCode},
+    extract::{OriginalUri, Path, Query, State},
+    http::{Request, StatusCode},
     response::IntoResponse,
     routing::get,
-    Router,
+    Json, Router,
 };
-use axum_htmx::HxRequest;
+use axum_htmx::{HxRequest, HxTarget};
+
+use crate::event_store::{Event, EventQuery};
+use html::templates::static_content;
 
 async fn list_events(
     State(crate::AppState { event_store, .. }): State<crate::AppState>,
+    Query(query): Query<EventQuery>,
+    HxTarget(hx_target): HxTarget,
     uri: OriginalUri,
     req: Request<Body>,
 ) -> impl IntoResponse {
-    if let Some(accept) = req.headers().get(ACCEPT) {
-        return match accept.to_str().unwrap() {
+    if let Some(accept) = super::accept(req.headers()) {
+        return match accept {
             "text/event-stream" => html::events_sse(&event_store).await.into_response(),
-            "application/json" => api::events_json(&event_store).await.into_response(),
-            _ => html::events_page(&event_store, None, uri)
+            "application/json" => api::events_json(&event_store, &query).await.into_response(),
+            _ if hx_target.as_deref() == Some(static_content::EVENTS_MORE_ID) => {
+                html::events_more(&event_store, &query)
+                    .await
+                    .into_response()
+            }
+            _ => html::events_page(&event_store, &query, None, uri)
                 .await
                 .into_response(),
         };
@@ -35,8 +45,8 @@ async fn get_event(
     uri: OriginalUri,
     req: Request<Body>,
 ) -> impl IntoResponse {
-    if let Some(accept) = req.headers().get(ACCEPT) {
-        return match accept.to_str().unwrap() {
+    if let Some(accept) = super::accept(req.headers()) {
+        return match accept {
             "application/json" => api::event_json(&event_store, &id).await.into_response(),
             _ => html::event_page(&event_store, &id, hx_request, uri)
                 .await
@@ -46,6 +56,20 @@ async fn get_event(
     (StatusCode::NOT_FOUND).into_response()
 }
 
+async fn events_stream(
+    State(crate::AppState { event_store, .. }): State<crate::AppState>,
+    Query(query): Query<EventQuery>,
+) -> impl IntoResponse {
+    api::events_stream(&event_store, query).await
+}
+
+async fn import_events(
+    State(crate::AppState { event_store, .. }): State<crate::AppState>,
+    Json(events): Json<Vec<Event>>,
+) -> impl IntoResponse {
+    api::import_events(&event_store, events).await
+}
+
 async fn clear_events(
     State(crate::AppState { event_store, .. }): State<crate::AppState>,
 ) -> impl IntoResponse {
@@ -63,7 +87,11 @@ pub fn create() -> crate::AppStateRouter {
     Router::new().nest(
         "/events",
         Router::new()
-            .route("/", get(list_events).delete(clear_events))
+            .route(
+                "/",
+                get(list_events).post(import_events).delete(clear_events),
+            )
+            .route("/stream", get(events_stream))
             .route("/{id}", get(get_event).delete(delete_event)),
     )
 }
@@ -72,7 +100,7 @@ pub fn create() -> crate::AppStateRouter {
 mod tests {
     use super::*;
     use crate::{
-        event_store::{Event, EventStore},
+        event_store::{Event, EventStore, Page},
         AppState,
     };
     use axum::{
@@ -85,7 +113,7 @@ mod tests {
     use std::sync::Arc;
     use tokio::pin;
     use tokio::{net::TcpListener, sync::RwLock};
-    use tower::{Service, ServiceExt};
+    use tower::ServiceExt;
     use uuid::Uuid;
 
     fn create_event() -> Event {
@@ -119,15 +147,16 @@ mod tests {
         assert_eq!(response.status(), StatusCode::OK);
 
         let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
-        let resp: Vec<Event> = serde_json::from_slice(&body_bytes).unwrap();
+        let resp: Page<Event> = serde_json::from_slice(&body_bytes).unwrap();
         let expected = evs
             .read()
             .await
             .get_all()
             .into_iter()
-            .map(|x| x.clone())
+            .cloned()
             .collect::<Vec<Event>>();
-        assert_eq!(resp, expected);
+        assert_eq!(resp.items, expected);
+        assert_eq!(resp.next_cursor, None);
     }
 
     #[tokio::test]
@@ -161,8 +190,13 @@ mod tests {
         let evsr = evs.read().await;
         assert_eq!(
             resp,
-            crate::routes::local::events::html::templates::events::build(&evsr.get_all(), None)
-                .into_string()
+            crate::routes::local::events::html::templates::events::build(
+                &evsr.query_events(&EventQuery::default()).unwrap(),
+                &EventQuery::default(),
+                None,
+                "/events"
+            )
+            .into_string()
         );
     }
 
@@ -314,7 +348,7 @@ mod tests {
                 Request::builder()
                     .method(http::Method::GET)
                     .header(http::header::ACCEPT, "application/json")
-                    .uri(format!("/events/{}", Uuid::new_v4().to_string()))
+                    .uri(format!("/events/{}", Uuid::new_v4()))
                     .body(Body::empty())
                     .unwrap(),
             )
@@ -356,15 +390,62 @@ mod tests {
         assert_eq!(
             resp,
             crate::routes::local::events::html::templates::events::build(
-                &evsr.get_all(),
+                &evsr.query_events(&EventQuery::default()).unwrap(),
+                &EventQuery::default(),
                 Some(crate::routes::local::events::html::templates::event::build(
-                    evsr.get_by_event_id(&id).unwrap()
+                    evsr.get_by_event_id(&id).unwrap(),
+                    &[]
                 )),
+                &format!("/events/{}", id),
             )
             .into_string()
         );
     }
 
+    #[tokio::test]
+    async fn event_html_send_email_links_timeline() {
+        use crate::event_store::{send_email::SendEmail, EventContent};
+
+        let evs = Arc::new(RwLock::new(EventStore::new()));
+        let email = SendEmail::new(
+            serde_json::from_value(serde_json::json!({
+                "FromEmailAddress": "s@example.com",
+                "Content": { "Simple": { "Subject": { "Data": "Hello" } } }
+            }))
+            .unwrap(),
+        );
+        let message_id = email.response.message_id.clone().unwrap();
+        let id = evs
+            .write()
+            .await
+            .push(Event::new(EventContent::SendEmail(email)))
+            .await
+            .unwrap()
+            .id;
+        let response = create()
+            .with_state(AppState {
+                event_store: evs.clone(),
+            })
+            .oneshot(
+                Request::builder()
+                    .header(http::header::ACCEPT, "text/html")
+                    .header("HX-Request", "true")
+                    .uri(format!("/events/{}", id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let resp = String::from_utf8(body_bytes.to_vec()).unwrap();
+        assert!(resp.contains(&format!(r#"href="/emails/{}""#, message_id)));
+        assert!(resp.contains(&format!(r#"href="/events/{}""#, id)));
+        assert!(resp.contains("SendEmail with Simple content, accepted as"));
+        assert_eq!(resp.matches("<li ").count(), 1);
+        assert!(resp.contains("Hello"));
+    }
+
     #[tokio::test]
     async fn event_html_email_not_found_contains_event_not_found_content() {
         let router = create();
@@ -393,8 +474,10 @@ mod tests {
         assert_eq!(
             resp,
             crate::routes::local::events::html::templates::events::build(
-                &evsr.get_all(),
+                &evsr.query_events(&EventQuery::default()).unwrap(),
+                &EventQuery::default(),
                 Some(html! { (format!("Event Not Found: {}", id))}),
+                &format!("/events/{}", id),
             )
             .into_string()
         );
@@ -433,7 +516,8 @@ mod tests {
         assert_eq!(
             resp,
             crate::routes::local::events::html::templates::event::build(
-                evsr.get_by_event_id(&id).unwrap()
+                evsr.get_by_event_id(&id).unwrap(),
+                &[]
             )
             .into_string()
         );
@@ -0,0 +1,51 @@
+use axum::{http::StatusCode, response::IntoResponse, Json};
+use serde_json::json;
+
+use crate::{event_store::EmailQuery, AppEventStore};
+
+pub async fn mailboxes_json(event_store: &AppEventStore) -> impl IntoResponse {
+    Json(json!(event_store.read().await.mailboxes()))
+}
+
+pub async fn inbox_json(
+    event_store: &AppEventStore,
+    address: &str,
+    query: &EmailQuery,
+) -> impl IntoResponse {
+    match event_store.read().await.inbox(address, query) {
+        Ok(page) => Json(json!(page)).into_response(),
+        Err(err) => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": err.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+/// The email as `address` sees it; unlike opening it in the UI, this leaves it unread.
+pub async fn inbox_email_json(
+    event_store: &AppEventStore,
+    address: &str,
+    id: &str,
+) -> impl IntoResponse {
+    let esr = event_store.read().await;
+    let found = esr
+        .get_email_by_message_id(id)
+        .and_then(|email| esr.inbox_email(address, email));
+    match found {
+        Some(item) => Json(json!(item)).into_response(),
+        None => (StatusCode::NOT_FOUND).into_response(),
+    }
+}
+
+pub async fn set_read_json(
+    event_store: &AppEventStore,
+    address: &str,
+    id: &str,
+    read: bool,
+) -> impl IntoResponse {
+    match event_store.write().await.set_read(address, id, read) {
+        true => Json(json!({ "read": read })).into_response(),
+        false => (StatusCode::NOT_FOUND).into_response(),
+    }
+}
@@ -0,0 +1,214 @@
+use std::{io::Error, time::Duration};
+
+use axum::{
+    extract::OriginalUri,
+    http::StatusCode,
+    response::{sse::Event, Html, IntoResponse, Sse},
+};
+use futures::{Stream, StreamExt};
+use maud::{html, Markup};
+
+use crate::{
+    event_store::{
+        self, send_email::SendEmail, Change, EmailQuery, EventContent, EventStore, InboxEmail, Page,
+    },
+    routes::local::{emails::html::templates::email, paged},
+    AppEventStore,
+};
+
+pub mod templates;
+
+pub async fn mailboxes_page(event_store: &AppEventStore, uri: OriginalUri) -> impl IntoResponse {
+    let esr = event_store.read().await;
+    let page = Page {
+        items: vec![],
+        next_cursor: None,
+        total: 0,
+    };
+    Html(templates::inbox::build(&esr.mailboxes(), None, &page, None, uri.path()).into_string())
+}
+
+pub async fn inbox_page(
+    event_store: &AppEventStore,
+    address: &str,
+    query: &EmailQuery,
+    email: Option<Markup>,
+    uri: OriginalUri,
+) -> impl IntoResponse {
+    let esr = event_store.read().await;
+    let page = match esr.inbox(address, &paged(query)) {
+        Ok(page) => page,
+        Err(err) => return (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    };
+    Html(
+        templates::inbox::build(&esr.mailboxes(), Some(address), &page, email, uri.path())
+            .into_string(),
+    )
+    .into_response()
+}
+
+/// The rows after `query.cursor`, for infinite scroll.
+pub async fn inbox_more(
+    event_store: &AppEventStore,
+    address: &str,
+    query: &EmailQuery,
+) -> impl IntoResponse {
+    let esr = event_store.read().await;
+    let page = match esr.inbox(address, &paged(query)) {
+        Ok(page) => page,
+        Err(err) => return (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    };
+    Html(templates::inbox::rows(address, &page).into_string()).into_response()
+}
+
+/// New rows for `address`, rows marked read or unread in another tab updated, deleted rows
+/// swapped out, the first page again after an import or restore, and the switcher's counts
+/// after each change to an email.
+pub async fn inbox_sse(
+    event_store: &AppEventStore,
+    address: String,
+) -> Sse<impl Stream<Item = Result<Event, Error>>> {
+    let changes = event_store.read().await.get_changes();
+    let event_store = event_store.clone();
+    let events = changes.filter_map(move |change| {
+        let event_store = event_store.clone();
+        let address = address.clone();
+        async move {
+            let esr = event_store.read().await;
+            let row = match &change {
+                Change::Added(ev) | Change::Updated(ev) => {
+                    let se = sent_email(ev)?;
+                    esr.inbox_email(&address, se).map(|item| match change {
+                        Change::Added(_) => templates::inbox_row::build(&address, &item),
+                        _ => templates::inbox_row::updated(&address, &item),
+                    })
+                }
+                Change::Deleted(ev) => {
+                    let se = sent_email(ev)?;
+                    se.response.message_id.as_deref().map(|id| {
+                        html! { div id=(templates::inbox_row::id(id)) hx-swap-oob="delete" {} }
+                    })
+                }
+                Change::Cleared => Some(html! {
+                    div id=(templates::static_content::INBOX_ROWS_ID) hx-swap-oob="innerHTML" {}
+                }),
+                Change::Reloaded => {
+                    let query = paged(&EmailQuery::default());
+                    esr.inbox(&address, &query).ok().map(|page| html! {
+                        div id=(templates::static_content::INBOX_ROWS_ID) hx-swap-oob="innerHTML" {
+                            (templates::inbox::rows(&address, &page))
+                        }
+                    })
+                }
+            };
+            let markup = html! {
+                @if let Some(row) = row { (row) }
+                (templates::inbox::switcher(&esr.mailboxes(), Some(&address), true))
+            };
+            Some(Ok(Event::default()
+                .event("email")
+                .data(markup.into_string())))
+        }
+    });
+    Sse::new(events).keep_alive(
+        axum::response::sse::KeepAlive::new()
+            .interval(Duration::from_secs(1))
+            .text("keep-stream-alive"),
+    )
+}
+
+/// Opens an email in `address`'s inbox, marking it read there.
+pub async fn inbox_email_page(
+    event_store: &AppEventStore,
+    address: &str,
+    id: &str,
+    hx_request: bool,
+    uri: OriginalUri,
+) -> impl IntoResponse {
+    let mut esw = event_store.write().await;
+    let item = match esw.set_read(address, id, true) {
+        true => find(&esw, address, id),
+        false => None,
+    };
+    let Some(item) = item else {
+        let not_found = html! { (format!("Email Not Found: {}", id)) };
+        return match hx_request {
+            true => Html(not_found.into_string()).into_response(),
+            false => {
+                drop(esw);
+                inbox_page(
+                    event_store,
+                    address,
+                    &EmailQuery::default(),
+                    Some(not_found),
+                    uri,
+                )
+                .await
+                .into_response()
+            }
+        };
+    };
+    let detail = email::build(
+        item.email,
+        esw.thread(id).as_ref(),
+        &esw.get_events_by_message_id(id),
+    );
+    let content = templates::inbox::email(address, &item, detail);
+    match hx_request {
+        // The row and switcher are already on the page, so bring them up to date alongside.
+        true => Html(
+            html! {
+                (content)
+                (templates::inbox_row::updated(address, &item))
+                (templates::inbox::switcher(&esw.mailboxes(), Some(address), true))
+            }
+            .into_string(),
+        )
+        .into_response(),
+        false => {
+            let content = content.into_string();
+            drop(esw);
+            inbox_page(
+                event_store,
+                address,
+                &EmailQuery::default(),
+                Some(maud::PreEscaped(content)),
+                uri,
+            )
+            .await
+            .into_response()
+        }
+    }
+}
+
+/// Marks an email read or unread, updating its row, the buttons and the switcher in place.
+pub async fn set_read(
+    event_store: &AppEventStore,
+    address: &str,
+    id: &str,
+    read: bool,
+) -> impl IntoResponse {
+    let mut esw = event_store.write().await;
+    if !esw.set_read(address, id, read) {
+        return Html(String::new());
+    }
+    let markup = find(&esw, address, id).map(|item| {
+        html! {
+            (templates::inbox_row::updated(address, &item))
+            (templates::inbox::actions(address, &item, true))
+            (templates::inbox::switcher(&esw.mailboxes(), Some(address), true))
+        }
+    });
+    Html(markup.unwrap_or_default().into_string())
+}
+
+fn find<'a>(event_store: &'a EventStore, address: &str, id: &str) -> Option<InboxEmail<'a>> {
+    event_store.inbox_email(address, event_store.get_email_by_message_id(id)?)
+}
+
+fn sent_email(event: &event_store::Event) -> Option<&SendEmail> {
+    match &event.content {
+        Some(EventContent::SendEmail(se)) => Some(se),
+        _ => None,
+    }
+}
@@ -0,0 +1,159 @@
+use maud::{html, Markup};
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+
+use super::{inbox_row, static_content};
+use crate::{
+    event_store::{EmailQuery, InboxEmail, Mailbox, Page},
+    page_template,
+    routes::local::emails::html::templates::static_content as emails,
+};
+
+// Leaves the `@` and `+` of addresses readable in inbox URLs.
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'?')
+    .add(b'<')
+    .add(b'>')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// `/inbox/{address}`, or the path of one of its emails.
+pub fn path(address: &str, message_id: Option<&str>) -> String {
+    let address = utf8_percent_encode(address, PATH_SEGMENT);
+    match message_id {
+        Some(id) => format!(
+            "/inbox/{}/{}",
+            address,
+            utf8_percent_encode(id, PATH_SEGMENT)
+        ),
+        None => format!("/inbox/{}", address),
+    }
+}
+
+/// The mailbox switcher, with the inbox of `address` below it when one is picked.
+pub fn build(
+    mailboxes: &[Mailbox],
+    address: Option<&str>,
+    emails: &Page<InboxEmail>,
+    email: Option<Markup>,
+    uri: &str,
+) -> Markup {
+    page_template::build(
+        html! {
+            div class="flex flex-row flex-grow min-h-full" {
+                div class="shrink-0 flex-[360px] grow-0 flex flex-col overflow-hidden" {
+                    (switcher(mailboxes, address, false))
+                    @if let Some(address) = address {
+                        div id="inbox" hx-ext="sse" sse-connect=(path(address, None))
+                            class="overflow-auto flex-grow snap-y snap-mandatory inset-shadow-sm" {
+                                div id=(static_content::INBOX_ROWS_ID) sse-swap="email" hx-swap="afterbegin" {
+                                    (rows(address, emails))
+                                }
+                        }
+                    } @else {
+                        p class="m-4 text-gray-500" { "Pick a mailbox to see what it received." }
+                    }
+                }
+                div id=(emails::EMAIL_DETAIL_ID) class="border-l-1 border-stone-100 grow shrink overflow-auto" {
+                    (email.unwrap_or(html! { "email" }))
+                }
+            }
+        },
+        uri,
+    )
+}
+
+/// Every recipient address with its unread count, linking to its inbox.
+pub fn switcher(mailboxes: &[Mailbox], current: Option<&str>, oob: bool) -> Markup {
+    let current = current.map(str::to_lowercase);
+    html! {
+        nav id=(static_content::MAILBOXES_ID) hx-swap-oob=[oob.then_some("outerHTML")] class="border-b-1 border-stone-100 max-h-[40%] overflow-auto py-2" {
+            @for mailbox in mailboxes {
+                @let selected = current.as_deref() == Some(mailbox.address.as_str());
+                a href=(path(&mailbox.address, None))
+                    class=(format!(
+                        "flex justify-between mx-2 px-2 py-1 rounded-md hover:bg-indigo-50 {}",
+                        if selected { "bg-indigo-100" } else { "" }
+                    )) {
+                        span class="wrap-anywhere" { (mailbox.address) }
+                        span class="text-sm text-gray-500" title=(format!("{} unread of {}", mailbox.unread, mailbox.total)) {
+                            @if mailbox.unread > 0 {
+                                span class="font-semibold text-blue-600" { (mailbox.unread) }
+                                " / "
+                            }
+                            (mailbox.total)
+                        }
+                }
+            }
+            @if mailboxes.is_empty() {
+                p class="mx-4 text-gray-500" { "No emails captured yet." }
+            }
+        }
+    }
+}
+
+/// A page of rows, followed by what loads the next one when scrolled into view.
+pub fn rows(address: &str, emails: &Page<InboxEmail>) -> Markup {
+    html! {
+        @for item in &emails.items {
+            (inbox_row::build(address, item))
+        }
+        @if let Some(cursor) = &emails.next_cursor {
+            @let next = format!(
+                "{}?{}",
+                path(address, None),
+                serde_urlencoded::to_string(EmailQuery {
+                    cursor: Some(cursor.clone()),
+                    ..Default::default()
+                })
+                .unwrap_or_default()
+            );
+            div id=(static_content::INBOX_MORE_ID) hx-get=(next) hx-trigger="intersect once"
+                hx-swap="outerHTML" class="m-2 text-center" {
+                    button type="button" hx-get=(next)
+                        hx-target=(format!("#{}", static_content::INBOX_MORE_ID))
+                        hx-swap="outerHTML" class="text-sm text-indigo-600" {
+                            "Load more"
+                    }
+            }
+        }
+    }
+}
+
+/// The email in the detail pane, below buttons for its read state.
+pub fn email(address: &str, item: &InboxEmail, detail: Markup) -> Markup {
+    html! {
+        (actions(address, item, false))
+        (detail)
+    }
+}
+
+/// Buttons for the read state of the email shown, out-of-band once it changes.
+pub fn actions(address: &str, item: &InboxEmail, oob: bool) -> Markup {
+    let message_id = item
+        .email
+        .response
+        .message_id
+        .as_deref()
+        .unwrap_or_default();
+    let read = format!("{}/read", path(address, Some(message_id)));
+    html! {
+        div id=(static_content::INBOX_ACTIONS_ID) hx-swap-oob=[oob.then_some("outerHTML")]
+            class="flex justify-end gap-3 px-4 pt-2 text-sm" {
+                @if item.read {
+                    button type="button" class="text-indigo-600" hx-delete=(read) hx-swap="none" {
+                        "Mark unread"
+                    }
+                } @else {
+                    button type="button" class="text-indigo-600" hx-put=(read) hx-swap="none" {
+                        "Mark read"
+                    }
+                }
+        }
+    }
+}
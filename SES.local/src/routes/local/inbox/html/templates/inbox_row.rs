@@ -0,0 +1,61 @@
+use maud::{html, Markup};
+
+use crate::{
+    event_store::InboxEmail,
+    routes::local::emails::html::templates::{address, static_content as emails, tag},
+};
+
+use super::inbox::path;
+
+pub fn build(address: &str, item: &InboxEmail) -> Markup {
+    row(address, item, false)
+}
+
+/// The row with its read state changed, to swap out-of-band for the one shown.
+pub fn updated(address: &str, item: &InboxEmail) -> Markup {
+    row(address, item, true)
+}
+
+/// The row's element id.
+pub fn id(message_id: &str) -> String {
+    format!("inbox-row-{}", message_id)
+}
+
+fn row(address: &str, item: &InboxEmail, oob: bool) -> Markup {
+    let message_id = item
+        .email
+        .response
+        .message_id
+        .as_deref()
+        .unwrap_or_default();
+    let summary = item.email.request.get_summary();
+    html! {
+        a id=(id(message_id))
+            hx-swap-oob=[oob.then_some("outerHTML")]
+            hx-get=(path(address, Some(message_id)))
+            hx-push-url="true"
+            hx-target=(format!("#{}", emails::EMAIL_DETAIL_ID))
+            hx-swap="innerHTML"
+            class=(format!(
+                "m-1 mb-2 rounded-md shadow-sm flex flex-row items-center snap-center hover:bg-indigo-50 {}",
+                if item.read { "text-gray-600" } else { "font-semibold" }
+            )) {
+                span class=(format!(
+                    "ml-3 w-2 h-2 shrink-0 rounded-full {}",
+                    if item.read { "bg-transparent" } else { "bg-blue-500" }
+                )) title=(if item.read { "read" } else { "unread" }) {}
+                div class="p-3" {
+                    (tag::build(&item.email.request.get_tag()))
+                }
+                div class="p-3 flex flex-col" {
+                    div class="text-sm" {
+                        @match summary.from {
+                            Some(from) => (address::names(&[from])),
+                            None => "unknown",
+                        }
+                    }
+                    span { (summary.subject.as_deref().unwrap_or("unknown")) }
+                }
+        }
+    }
+}
@@ -0,0 +1,3 @@
+pub mod inbox;
+pub mod inbox_row;
+pub mod static_content;
@@ -0,0 +1,4 @@
+pub const MAILBOXES_ID: &str = "mailboxes";
+pub const INBOX_ROWS_ID: &str = "inbox-rows";
+pub const INBOX_MORE_ID: &str = "inbox-more";
+pub const INBOX_ACTIONS_ID: &str = "inbox-actions";
@@ -0,0 +1,260 @@
+mod api;
+mod html;
+
+use axum::{
+    body::Body,
+    extract::{OriginalUri, Path, Query, State},
+    http::{Request, StatusCode},
+    response::IntoResponse,
+    routing::{get, put},
+    Router,
+};
+use axum_htmx::{HxRequest, HxTarget};
+
+use crate::event_store::EmailQuery;
+use html::templates::static_content;
+
+async fn mailboxes(
+    State(crate::AppState { event_store, .. }): State<crate::AppState>,
+    uri: OriginalUri,
+    req: Request<Body>,
+) -> impl IntoResponse {
+    if let Some(accept) = super::accept(req.headers()) {
+        return match accept {
+            "application/json" => api::mailboxes_json(&event_store).await.into_response(),
+            _ => html::mailboxes_page(&event_store, uri)
+                .await
+                .into_response(),
+        };
+    }
+    (StatusCode::NOT_FOUND).into_response()
+}
+
+async fn inbox(
+    State(crate::AppState { event_store, .. }): State<crate::AppState>,
+    Path(address): Path<String>,
+    Query(query): Query<EmailQuery>,
+    HxTarget(hx_target): HxTarget,
+    uri: OriginalUri,
+    req: Request<Body>,
+) -> impl IntoResponse {
+    if let Some(accept) = super::accept(req.headers()) {
+        return match accept {
+            "text/event-stream" => html::inbox_sse(&event_store, address).await.into_response(),
+            "application/json" => api::inbox_json(&event_store, &address, &query)
+                .await
+                .into_response(),
+            _ if hx_target.as_deref() == Some(static_content::INBOX_MORE_ID) => {
+                html::inbox_more(&event_store, &address, &query)
+                    .await
+                    .into_response()
+            }
+            _ => html::inbox_page(&event_store, &address, &query, None, uri)
+                .await
+                .into_response(),
+        };
+    }
+    (StatusCode::NOT_FOUND).into_response()
+}
+
+async fn inbox_email(
+    State(crate::AppState { event_store, .. }): State<crate::AppState>,
+    Path((address, id)): Path<(String, String)>,
+    HxRequest(hx_request): HxRequest,
+    uri: OriginalUri,
+    req: Request<Body>,
+) -> impl IntoResponse {
+    if let Some(accept) = super::accept(req.headers()) {
+        return match accept {
+            "application/json" => api::inbox_email_json(&event_store, &address, &id)
+                .await
+                .into_response(),
+            _ => html::inbox_email_page(&event_store, &address, &id, hx_request, uri)
+                .await
+                .into_response(),
+        };
+    }
+    (StatusCode::NOT_FOUND).into_response()
+}
+
+async fn mark_read(
+    State(crate::AppState { event_store, .. }): State<crate::AppState>,
+    Path((address, id)): Path<(String, String)>,
+    HxRequest(hx_request): HxRequest,
+) -> impl IntoResponse {
+    set_read(&event_store, &address, &id, true, hx_request).await
+}
+
+async fn mark_unread(
+    State(crate::AppState { event_store, .. }): State<crate::AppState>,
+    Path((address, id)): Path<(String, String)>,
+    HxRequest(hx_request): HxRequest,
+) -> impl IntoResponse {
+    set_read(&event_store, &address, &id, false, hx_request).await
+}
+
+async fn set_read(
+    event_store: &crate::AppEventStore,
+    address: &str,
+    id: &str,
+    read: bool,
+    hx_request: bool,
+) -> axum::response::Response {
+    match hx_request {
+        true => html::set_read(event_store, address, id, read)
+            .await
+            .into_response(),
+        false => api::set_read_json(event_store, address, id, read)
+            .await
+            .into_response(),
+    }
+}
+
+pub fn create() -> crate::AppStateRouter {
+    Router::new().nest(
+        "/inbox",
+        Router::new()
+            .route("/", get(mailboxes))
+            .route("/{address}", get(inbox))
+            .route("/{address}/{id}", get(inbox_email))
+            .route("/{address}/{id}/read", put(mark_read).delete(mark_unread)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_store::send_email::SendEmail;
+    use crate::event_store::{Event, EventContent, EventStore};
+    use crate::AppState;
+    use axum::http::header::ACCEPT;
+    use axum::{
+        body::{to_bytes, Body},
+        http::{self, Request},
+    };
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+    use tower::ServiceExt;
+
+    async fn store() -> (crate::AppEventStore, String) {
+        let es = Arc::new(RwLock::new(EventStore::new()));
+        let email = SendEmail::new(
+            serde_json::from_value(serde_json::json!({
+                "Destination": {
+                    "ToAddresses": ["Ann <ann@example.com>"],
+                    "CcAddresses": ["bob@example.com"]
+                },
+                "Content": { "Simple": { "Subject": { "Data": "Hello" } } }
+            }))
+            .unwrap(),
+        );
+        let id = email.response.message_id.clone().unwrap();
+        _ = es
+            .write()
+            .await
+            .push(Event::new(EventContent::SendEmail(email)))
+            .await;
+        (es, id)
+    }
+
+    async fn json(
+        router: &crate::AppStateRouter,
+        es: &crate::AppEventStore,
+        method: http::Method,
+        uri: &str,
+    ) -> (StatusCode, serde_json::Value) {
+        let response = router
+            .clone()
+            .with_state(AppState {
+                event_store: es.clone(),
+            })
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header(ACCEPT, "application/json")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (
+            status,
+            serde_json::from_slice(&body_bytes).unwrap_or_default(),
+        )
+    }
+
+    #[tokio::test]
+    async fn inbox_json() {
+        let (es, id) = store().await;
+        let router = create();
+
+        let (_, mailboxes) = json(&router, &es, http::Method::GET, "/inbox").await;
+        assert_eq!(
+            mailboxes,
+            serde_json::json!([
+                { "address": "ann@example.com", "total": 1, "unread": 1 },
+                { "address": "bob@example.com", "total": 1, "unread": 1 }
+            ])
+        );
+
+        let read = format!("/inbox/Ann%40example.com/{}/read", id);
+        let (status, body) = json(&router, &es, http::Method::PUT, &read).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, serde_json::json!({ "read": true }));
+        let (_, inbox) = json(&router, &es, http::Method::GET, "/inbox/ann@example.com").await;
+        assert_eq!(inbox["items"][0]["read"], true);
+        assert_eq!(inbox["items"][0]["response"]["MessageId"], id.as_str());
+        let (_, inbox) = json(&router, &es, http::Method::GET, "/inbox/bob@example.com").await;
+        assert_eq!(inbox["items"][0]["read"], false);
+
+        let (status, _) = json(&router, &es, http::Method::DELETE, &read).await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, email) = json(
+            &router,
+            &es,
+            http::Method::GET,
+            &format!("/inbox/ann@example.com/{}", id),
+        )
+        .await;
+        assert_eq!(email["read"], false);
+
+        let missing = format!("/inbox/carol@example.com/{}/read", id);
+        let (status, _) = json(&router, &es, http::Method::PUT, &missing).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn inbox_email_html_marks_read() {
+        let (es, id) = store().await;
+        let response = create()
+            .with_state(AppState {
+                event_store: es.clone(),
+            })
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/inbox/ann@example.com/{}", id))
+                    .header(ACCEPT, "text/html")
+                    .header("HX-Request", "true")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body_bytes.to_vec()).unwrap();
+        assert!(body.contains("Mark unread"));
+        assert!(body.contains(r#"hx-swap-oob="outerHTML""#));
+        let esr = es.read().await;
+        assert_eq!(
+            esr.mailboxes()
+                .iter()
+                .map(|mailbox| mailbox.unread)
+                .collect::<Vec<_>>(),
+            [0, 1]
+        );
+    }
+}
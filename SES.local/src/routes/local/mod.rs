@@ -1,10 +1,108 @@
+mod admin;
+mod dashboard;
 mod emails;
 mod events;
-use axum::{response::Redirect, routing::get, Router};
+mod inbox;
+use axum::{
+    http::{header::ACCEPT, HeaderMap},
+    response::Redirect,
+    routing::get,
+    Router,
+};
+
+use crate::event_store::{EmailQuery, EventQuery};
+
+// Rows rendered per request; the rest load as the list is scrolled.
+const PAGE_SIZE: usize = 50;
 
 pub fn create() -> crate::AppStateRouter {
     Router::new()
         .route("/", get(|| async { Redirect::permanent("/emails") }))
         .merge(emails::create())
         .merge(events::create())
+        .merge(inbox::create())
+        .merge(dashboard::create())
+        .merge(admin::create())
+}
+
+/// The Accept header the routes dispatch on, empty if it isn't visible ASCII so such requests
+/// get the HTML page rather than a panic.
+fn accept(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(ACCEPT)
+        .map(|accept| accept.to_str().unwrap_or_default())
+}
+
+/// A query the HTML lists page through.
+trait Paged: Clone {
+    fn limit_mut(&mut self) -> &mut Option<usize>;
+}
+
+impl Paged for EmailQuery {
+    fn limit_mut(&mut self) -> &mut Option<usize> {
+        &mut self.limit
+    }
+}
+
+impl Paged for EventQuery {
+    fn limit_mut(&mut self) -> &mut Option<usize> {
+        &mut self.limit
+    }
+}
+
+/// `query` limited to a page of rows, unless it sets a limit of its own.
+fn paged<Q: Paged>(query: &Q) -> Q {
+    let mut query = query.clone();
+    query.limit_mut().get_or_insert(PAGE_SIZE);
+    query
+}
+
+pub fn content() -> crate::AppStateRouter {
+    emails::content()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{
+        body::Body,
+        http::{HeaderValue, Request},
+    };
+    use tokio::sync::RwLock;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::{event_store::EventStore, AppState};
+
+    #[tokio::test]
+    async fn non_ascii_accept() {
+        let app = create().with_state(AppState {
+            event_store: Arc::new(RwLock::new(EventStore::new())),
+        });
+        for uri in [
+            "/emails",
+            "/emails/missing",
+            "/emails/threads",
+            "/events",
+            "/events/missing",
+            "/dashboard",
+            "/inbox",
+            "/inbox/a@example.com",
+            "/inbox/a@example.com/missing",
+        ] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .uri(uri)
+                        .header(ACCEPT, HeaderValue::from_bytes(b"text/h\xe9ml").unwrap())
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert!(!response.status().is_server_error(), "{}", uri);
+        }
+    }
 }
@@ -1,6 +1,31 @@
+use crate::conf::Rule;
+
 mod aws_ses;
 mod local;
 
+/// Where email bodies are served from when they're isolated from the UI's origin.
+#[derive(Clone, Debug)]
+pub struct ContentOrigin {
+    pub port: u16,
+    /// The public URL `port` is reached at, if not the UI's hostname on `port`.
+    pub url: Option<String>,
+    /// Whether `port` serves HTTPS, for building the default URL.
+    pub https: bool,
+}
+
+/// The configured identities and rules `SendEmail` is checked against.
+#[derive(Clone, Debug, Default)]
+pub struct Sending {
+    /// Verified senders. Empty means any sender is accepted.
+    pub identities: Vec<String>,
+    pub rules: Vec<Rule>,
+}
+
 pub fn create() -> crate::AppStateRouter {
     aws_ses::create().merge(local::create())
 }
+
+/// Just the email body and attachment routes, for serving from the [`ContentOrigin`].
+pub fn content() -> crate::AppStateRouter {
+    local::content()
+}
@@ -0,0 +1,472 @@
+use std::{
+    future::{Future, IntoFuture},
+    io,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
+use axum::{serve, Extension, Router};
+use futures::{future::BoxFuture, FutureExt};
+use tokio::{
+    net::TcpListener,
+    sync::{oneshot, RwLock},
+    task::JoinHandle,
+};
+use tokio_rustls::TlsAcceptor;
+use tower_http::{services::ServeDir, trace::TraceLayer};
+
+use crate::{
+    conf::Rule,
+    event_store::EventStore,
+    routes::{self, ContentOrigin, Sending},
+    tls::{self, TlsListener},
+    AppEventStore, AppState,
+};
+
+// How long in-flight requests get to finish on shutdown. SSE streams and `/emails/wait` don't
+// end by themselves, so whatever is left after this is cut off.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(2);
+
+/// Runs SES.local inside the current tokio runtime.
+///
+/// Defaults to an ephemeral port on `127.0.0.1` with a fresh event store, so each test can
+/// start its own isolated instance:
+///
+/// ```no_run
+/// # async fn run() -> std::io::Result<()> {
+/// let server = ses_local::Server::builder().start().await?;
+/// let endpoint_url = server.endpoint_url();
+/// // ...point the SES client at endpoint_url...
+/// server.shutdown().await;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Server;
+
+pub struct ServerBuilder {
+    ip: IpAddr,
+    port: u16,
+    assets_path: Option<PathBuf>,
+    content_port: Option<u16>,
+    content_url: Option<String>,
+    tls: Option<(PathBuf, PathBuf)>,
+    event_store: Option<AppEventStore>,
+    sending: Sending,
+}
+
+pub struct ServerHandle {
+    addr: SocketAddr,
+    https: bool,
+    content_addr: Option<SocketAddr>,
+    event_store: AppEventStore,
+    shutdown: Option<oneshot::Sender<()>>,
+    task: Option<JoinHandle<io::Result<()>>>,
+}
+
+impl Server {
+    pub fn builder() -> ServerBuilder {
+        ServerBuilder {
+            ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            port: 0,
+            assets_path: None,
+            content_port: None,
+            content_url: None,
+            tls: None,
+            event_store: None,
+            sending: Sending::default(),
+        }
+    }
+}
+
+impl ServerBuilder {
+    pub fn ip(mut self, ip: IpAddr) -> Self {
+        self.ip = ip;
+        self
+    }
+
+    /// Port to listen on, `0` (the default) picks a free one.
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// Serves the UI's compiled assets from `path` under `/assets`.
+    pub fn assets_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.assets_path = Some(path.into());
+        self
+    }
+
+    /// Serves email bodies and attachments from this port instead, so they're on a different
+    /// origin to the UI. `0` picks a free one.
+    pub fn content_port(mut self, port: u16) -> Self {
+        self.content_port = Some(port);
+        self
+    }
+
+    /// The public URL the content port is reached at, such as `https://mail.example.test`
+    /// behind a proxy. Defaults to the UI's hostname on the content port.
+    pub fn content_url(mut self, url: impl Into<String>) -> Self {
+        self.content_url = Some(url.into());
+        self
+    }
+
+    /// Serves HTTPS, on the content port too, with the PEM certificate chain in `cert` and
+    /// private key in `key`.
+    pub fn tls(mut self, cert: impl Into<PathBuf>, key: impl Into<PathBuf>) -> Self {
+        self.tls = Some((cert.into(), key.into()));
+        self
+    }
+
+    /// Only accepts `SendEmail` from these senders, addresses or domains, rejecting the rest as
+    /// SES does for unverified identities.
+    pub fn identities(mut self, identities: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.sending.identities = identities.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Rejects or drops `SendEmail` to the recipients the rules match.
+    pub fn rules(mut self, rules: impl IntoIterator<Item = Rule>) -> Self {
+        self.sending.rules = rules.into_iter().collect();
+        self
+    }
+
+    /// Shares an existing event store instead of starting with an empty one.
+    pub fn event_store(mut self, event_store: AppEventStore) -> Self {
+        self.event_store = Some(event_store);
+        self
+    }
+
+    pub async fn start(self) -> io::Result<ServerHandle> {
+        let tls = match &self.tls {
+            Some((cert, key)) => Some(tls::acceptor(cert, key)?),
+            None => None,
+        };
+        let event_store = self
+            .event_store
+            .unwrap_or_else(|| Arc::new(RwLock::new(EventStore::new())));
+        let state = AppState {
+            event_store: event_store.clone(),
+        };
+        let mut app = routes::create().layer(Extension(self.sending));
+        if let Some(path) = self.assets_path {
+            app = app.nest_service("/assets", ServeDir::new(path));
+        }
+
+        let listener = TcpListener::bind(SocketAddr::new(self.ip, self.port)).await?;
+        let addr = listener.local_addr()?;
+        tracing::debug!("listening on {}", addr);
+        let content = match self.content_port {
+            Some(port) => {
+                let listener = TcpListener::bind(SocketAddr::new(self.ip, port)).await?;
+                let addr = listener.local_addr()?;
+                tracing::debug!("serving email content on {}", addr);
+                app = app.layer(Extension(ContentOrigin {
+                    port: addr.port(),
+                    url: self.content_url,
+                    https: tls.is_some(),
+                }));
+                let content = routes::content()
+                    .layer(TraceLayer::new_for_http())
+                    .with_state(state.clone());
+                Some((listener, addr, content))
+            }
+            None => None,
+        };
+        let content_addr = content.as_ref().map(|(_, addr, _)| *addr);
+        let app = app.layer(TraceLayer::new_for_http()).with_state(state);
+
+        let (shutdown, signal) = oneshot::channel::<()>();
+        let signal = async {
+            _ = signal.await;
+        }
+        .shared();
+        let https = tls.is_some();
+        let task = tokio::spawn(async move {
+            let main = serving(listener, app, tls.clone(), signal.clone());
+            match content {
+                Some((listener, _, content)) => {
+                    let content = serving(listener, content, tls, signal);
+                    tokio::try_join!(main, content).map(|_| ())
+                }
+                None => main.await,
+            }
+        });
+        Ok(ServerHandle {
+            addr,
+            https,
+            content_addr,
+            event_store,
+            shutdown: Some(shutdown),
+            task: Some(task),
+        })
+    }
+}
+
+// Serves `app` on `listener` until `signal`, over TLS if there's an acceptor.
+fn serving(
+    listener: TcpListener,
+    app: Router,
+    tls: Option<TlsAcceptor>,
+    signal: impl Future<Output = ()> + Send + 'static,
+) -> BoxFuture<'static, io::Result<()>> {
+    match tls {
+        Some(acceptor) => serve(TlsListener::new(listener, acceptor), app)
+            .with_graceful_shutdown(signal)
+            .into_future()
+            .boxed(),
+        None => serve(listener, app)
+            .with_graceful_shutdown(signal)
+            .into_future()
+            .boxed(),
+    }
+}
+
+impl ServerHandle {
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Base URL to configure the AWS SDK (or `ses-local-client`) with.
+    pub fn endpoint_url(&self) -> String {
+        let ip = match self.addr.ip() {
+            ip if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+            ip => ip,
+        };
+        let scheme = if self.https { "https" } else { "http" };
+        format!("{}://{}", scheme, SocketAddr::new(ip, self.addr.port()))
+    }
+
+    /// Where email bodies are served from, if on their own port.
+    pub fn content_addr(&self) -> Option<SocketAddr> {
+        self.content_addr
+    }
+
+    pub fn event_store(&self) -> &AppEventStore {
+        &self.event_store
+    }
+
+    /// Runs until the server stops on its own.
+    pub async fn wait(mut self) -> io::Result<()> {
+        match self.task.take() {
+            Some(task) => task.await.map_err(io::Error::other)?,
+            None => Ok(()),
+        }
+    }
+
+    /// Stops accepting connections and waits briefly for in-flight requests to finish,
+    /// closing any still open after that.
+    pub async fn shutdown(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            _ = shutdown.send(());
+        }
+        if let Some(mut task) = self.task.take() {
+            if tokio::time::timeout(SHUTDOWN_GRACE, &mut task)
+                .await
+                .is_err()
+            {
+                task.abort();
+                _ = task.await;
+            }
+        }
+    }
+}
+
+impl Drop for ServerHandle {
+    // Dropping the handle (e.g. at the end of a test) stops the server in the background.
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            _ = shutdown.send(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_store::{send_email::SendEmail, Event, EventContent};
+
+    #[tokio::test]
+    async fn isolated_servers() {
+        let a = Server::builder().start().await.unwrap();
+        let b = Server::builder().start().await.unwrap();
+        assert_ne!(a.endpoint_url(), b.endpoint_url());
+
+        _ = a.event_store().write().await.push(Event::empty()).await;
+        assert_eq!(a.event_store().read().await.get_all().len(), 1);
+        assert!(b.event_store().read().await.get_all().is_empty());
+
+        let response = reqwest::Client::new()
+            .get(format!("{}/events", a.endpoint_url()))
+            .header("accept", "application/json")
+            .send()
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+
+        let url = a.endpoint_url();
+        a.shutdown().await;
+        assert!(reqwest::get(url).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn shutdown_with_open_stream() {
+        let server = Server::builder().start().await.unwrap();
+        let url = server.endpoint_url();
+        let stream = reqwest::Client::new()
+            .get(format!("{}/events", url))
+            .header("accept", "text/event-stream")
+            .send()
+            .await
+            .unwrap();
+        assert!(stream.status().is_success());
+
+        let started = tokio::time::Instant::now();
+        server.shutdown().await;
+        assert!(started.elapsed() < SHUTDOWN_GRACE + Duration::from_secs(1));
+        assert!(reqwest::get(url).await.is_err());
+        drop(stream);
+    }
+
+    #[tokio::test]
+    async fn content_port() {
+        let server = Server::builder().content_port(0).start().await.unwrap();
+        let content_port = server.content_addr().unwrap().port();
+        let input = serde_json::from_str(
+            r#"{"Content": {"Simple": {"Body": {"Html": {"Data": "<p>Hi</p>"}}}}}"#,
+        )
+        .unwrap();
+        let email = SendEmail::new(input);
+        let message_id = email.response.message_id.clone().unwrap();
+        _ = server
+            .event_store()
+            .write()
+            .await
+            .push(Event::new(EventContent::SendEmail(email)))
+            .await;
+
+        let http = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
+        let response = http
+            .get(format!(
+                "{}/emails/{}/content?remote=true",
+                server.endpoint_url(),
+                message_id
+            ))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::TEMPORARY_REDIRECT);
+        let location = response.headers()["location"].to_str().unwrap().to_string();
+        assert_eq!(
+            location,
+            format!(
+                "http://127.0.0.1:{}/emails/{}/content?remote=true",
+                content_port, message_id
+            )
+        );
+
+        let response = http.get(location).send().await.unwrap();
+        assert!(response.headers()["content-security-policy"]
+            .to_str()
+            .unwrap()
+            .starts_with("default-src 'none'; img-src 'self' data: http: https:;"));
+        assert_eq!(response.text().await.unwrap(), "<p>Hi</p>");
+
+        let response = http
+            .get(format!("http://127.0.0.1:{}/emails", content_port))
+            .header("accept", "application/json")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn content_url() {
+        let server = Server::builder()
+            .content_port(0)
+            .content_url("https://mail.example.test/")
+            .start()
+            .await
+            .unwrap();
+        let response = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap()
+            .get(format!("{}/emails/abc/content", server.endpoint_url()))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::TEMPORARY_REDIRECT);
+        assert_eq!(
+            response.headers()["location"],
+            "https://mail.example.test/emails/abc/content"
+        );
+    }
+
+    #[tokio::test]
+    async fn tls() {
+        let dir = std::env::temp_dir().join(format!("ses-local-tls-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (cert, key) = (dir.join("cert.pem"), dir.join("key.pem"));
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        std::fs::write(&cert, certified.cert.pem()).unwrap();
+        std::fs::write(&key, certified.signing_key.serialize_pem()).unwrap();
+
+        let err = Server::builder()
+            .tls(&key, &key)
+            .start()
+            .await
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string().ends_with("no certificates found"),
+            "{}",
+            err
+        );
+
+        let server = Server::builder()
+            .tls(&cert, &key)
+            .content_port(0)
+            .start()
+            .await
+            .unwrap();
+        let content_port = server.content_addr().unwrap().port();
+        assert!(server.endpoint_url().starts_with("https://"));
+        // A client that never starts its handshake doesn't hold up the others.
+        let _idle = tokio::net::TcpStream::connect(server.local_addr())
+            .await
+            .unwrap();
+
+        let http = reqwest::Client::builder()
+            .danger_accept_invalid_certs(true)
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
+        let response = http
+            .get(format!("{}/events", server.endpoint_url()))
+            .header("accept", "application/json")
+            .send()
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+        let response = http
+            .get(format!("{}/emails/abc/content", server.endpoint_url()))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers()["location"],
+            format!("https://127.0.0.1:{}/emails/abc/content", content_port)
+        );
+        let plain = format!("http://{}/events", server.local_addr());
+        assert!(reqwest::get(plain).await.is_err());
+
+        server.shutdown().await;
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,105 @@
+use std::{
+    fs::File,
+    io::{self, BufReader},
+    net::SocketAddr,
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
+
+use axum::serve::Listener;
+use tokio::{
+    net::{TcpListener, TcpStream},
+    task::JoinSet,
+};
+use tokio_rustls::{
+    rustls::{crypto::aws_lc_rs, ServerConfig},
+    server::TlsStream,
+    TlsAcceptor,
+};
+
+// Clients that connect but never finish the handshake are dropped after this.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An acceptor for the PEM certificate chain in `cert` and private key in `key`.
+pub fn acceptor(cert: &Path, key: &Path) -> io::Result<TlsAcceptor> {
+    let certs = rustls_pemfile::certs(&mut open(cert)?)
+        .collect::<io::Result<Vec<_>>>()
+        .map_err(|err| invalid(cert, err))?;
+    if certs.is_empty() {
+        return Err(invalid(cert, "no certificates found"));
+    }
+    let private_key = rustls_pemfile::private_key(&mut open(key)?)
+        .map_err(|err| invalid(key, err))?
+        .ok_or_else(|| invalid(key, "no private key found"))?;
+    let mut config = ServerConfig::builder_with_provider(Arc::new(aws_lc_rs::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(io::Error::other)?
+        .with_no_client_auth()
+        .with_single_cert(certs, private_key)
+        .map_err(|err| invalid(key, err))?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+fn open(path: &Path) -> io::Result<BufReader<File>> {
+    let file = File::open(path)
+        .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path.display(), err)))?;
+    Ok(BufReader::new(file))
+}
+
+fn invalid(path: &Path, err: impl std::fmt::Display) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {}", path.display(), err),
+    )
+}
+
+/// Accepts TLS connections, running handshakes concurrently so a slow client doesn't hold up
+/// the others.
+pub struct TlsListener {
+    tcp: TcpListener,
+    acceptor: TlsAcceptor,
+    handshakes: JoinSet<(io::Result<TlsStream<TcpStream>>, SocketAddr)>,
+}
+
+impl TlsListener {
+    pub fn new(tcp: TcpListener, acceptor: TlsAcceptor) -> Self {
+        TlsListener {
+            tcp,
+            acceptor,
+            handshakes: JoinSet::new(),
+        }
+    }
+}
+
+impl Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        loop {
+            tokio::select! {
+                (stream, addr) = Listener::accept(&mut self.tcp) => {
+                    let handshake = self.acceptor.accept(stream);
+                    self.handshakes.spawn(async move {
+                        let stream = match tokio::time::timeout(HANDSHAKE_TIMEOUT, handshake).await {
+                            Ok(stream) => stream,
+                            Err(elapsed) => Err(io::Error::new(io::ErrorKind::TimedOut, elapsed)),
+                        };
+                        (stream, addr)
+                    });
+                }
+                Some(handshake) = self.handshakes.join_next() => match handshake {
+                    Ok((Ok(stream), addr)) => return (stream, addr),
+                    Ok((Err(err), addr)) => tracing::debug!("TLS handshake with {} failed: {}", addr, err),
+                    Err(err) => tracing::warn!("TLS handshake task failed: {}", err),
+                },
+            }
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        self.tcp.local_addr()
+    }
+}
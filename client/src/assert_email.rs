@@ -0,0 +1,303 @@
+use std::{
+    fmt::{self, Display},
+    future::{Future, IntoFuture},
+    pin::Pin,
+    time::Duration,
+};
+
+use jiff::Timestamp;
+use ses_local_serde::{send_email::SendEmail, EmailQuery};
+use thiserror::Error;
+
+use crate::{error::ClientError, Client};
+
+// How many recently captured emails to show when an assertion fails.
+const CANDIDATES: usize = 10;
+
+/// Builder for `Client::assert_email_sent`.
+///
+/// Awaiting it panics with a diff against the recently captured emails when nothing matches;
+/// use [`EmailAssertion::check`] to get the failure as an error instead.
+pub struct EmailAssertion<'a> {
+    client: &'a Client,
+    query: EmailQuery,
+    within: Option<Duration>,
+}
+
+#[derive(Error, Debug)]
+pub enum AssertionError {
+    #[error(transparent)]
+    Client(ClientError),
+    #[error("{0}")]
+    NoMatch(Box<NoMatch>),
+}
+
+#[derive(Debug)]
+pub struct NoMatch {
+    pub query: EmailQuery,
+    pub captured: Vec<SendEmail>,
+}
+
+impl<'a> EmailAssertion<'a> {
+    pub(crate) fn new(client: &'a Client) -> Self {
+        EmailAssertion {
+            client,
+            query: EmailQuery::default(),
+            within: None,
+        }
+    }
+
+    pub fn to(mut self, address: impl Into<String>) -> Self {
+        self.query.to = Some(address.into());
+        self
+    }
+
+    pub fn subject_contains(mut self, subject: impl Into<String>) -> Self {
+        self.query.subject = Some(subject.into());
+        self
+    }
+
+    pub fn since(mut self, since: Timestamp) -> Self {
+        self.query.since = Some(since);
+        self
+    }
+
+    /// Waits up to `timeout` for the email to arrive rather than failing straight away.
+    pub fn within(mut self, timeout: Duration) -> Self {
+        self.within = Some(timeout);
+        self
+    }
+
+    pub async fn check(self) -> Result<SendEmail, AssertionError> {
+        let found = match self.within {
+            Some(timeout) => match self.client.wait_for_email(&self.query, timeout).await {
+                Ok(email) => Some(email),
+                Err(ClientError::Timeout { .. }) => None,
+                Err(err) => return Err(AssertionError::Client(err)),
+            },
+            None => {
+                let query = EmailQuery {
+                    limit: Some(1),
+                    ..self.query.clone()
+                };
+                let page = self
+                    .client
+                    .emails(&query)
+                    .await
+                    .map_err(AssertionError::Client)?;
+                page.items.into_iter().next()
+            }
+        };
+        if let Some(email) = found {
+            return Ok(email);
+        }
+        let recent = EmailQuery {
+            since: self.query.since,
+            limit: Some(CANDIDATES),
+            ..Default::default()
+        };
+        let captured = self
+            .client
+            .emails(&recent)
+            .await
+            .map_err(AssertionError::Client)?
+            .items;
+        Err(AssertionError::NoMatch(Box::new(NoMatch {
+            query: self.query,
+            captured,
+        })))
+    }
+}
+
+impl<'a> IntoFuture for EmailAssertion<'a> {
+    type Output = SendEmail;
+    type IntoFuture = Pin<Box<dyn Future<Output = SendEmail> + Send + 'a>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(async move {
+            match self.check().await {
+                Ok(email) => email,
+                Err(err) => panic!("{}", err),
+            }
+        })
+    }
+}
+
+impl Display for NoMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "expected an email to have been sent matching:")?;
+        if let Some(to) = &self.query.to {
+            writeln!(f, "    to: {}", to)?;
+        }
+        if let Some(subject) = &self.query.subject {
+            writeln!(f, "    subject contains: {}", subject)?;
+        }
+        if let Some(since) = &self.query.since {
+            writeln!(f, "    since: {}", since)?;
+        }
+        if self.captured.is_empty() {
+            return write!(f, "but no emails were captured");
+        }
+        writeln!(
+            f,
+            "but none of the {} most recently captured emails did (- expected, + actual):",
+            self.captured.len()
+        )?;
+        for (i, email) in self.captured.iter().enumerate() {
+            writeln!(
+                f,
+                "  [{}] {}",
+                i,
+                email
+                    .response
+                    .message_id
+                    .as_deref()
+                    .unwrap_or("<no message id>")
+            )?;
+            // Every recipient `to` is matched against, Cc and Bcc included.
+            let to = email
+                .request
+                .get_to()
+                .map(|d| {
+                    [
+                        ("", &d.to_addresses),
+                        ("cc: ", &d.cc_addresses),
+                        ("bcc: ", &d.bcc_addresses),
+                    ]
+                    .into_iter()
+                    .flat_map(|(kind, addresses)| {
+                        addresses
+                            .iter()
+                            .flatten()
+                            .map(move |address| format!("{}{}", kind, address))
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", ")
+                })
+                .unwrap_or_default();
+            diff_line(
+                f,
+                "to",
+                self.query.to.as_deref(),
+                self.query.matches_to(email),
+                &to,
+            )?;
+            diff_line(
+                f,
+                "subject",
+                self.query.subject.as_deref(),
+                self.query.matches_subject(email),
+                &email.request.get_rendered_subject().unwrap_or_default(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn diff_line(
+    f: &mut fmt::Formatter<'_>,
+    name: &str,
+    expected: Option<&str>,
+    matched: Option<bool>,
+    actual: &str,
+) -> fmt::Result {
+    match (expected, matched) {
+        (Some(expected), Some(false)) => {
+            writeln!(f, "      - {}: {}", name, expected)?;
+            writeln!(f, "      + {}: {}", name, actual)
+        }
+        _ => writeln!(f, "        {}: {}", name, actual),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::tests::{send, send_email, start_app};
+
+    #[tokio::test]
+    async fn email_sent() {
+        let (_server, client) = start_app().await;
+        send_email(&client, "a@example.com", "Welcome aboard", "hi").await;
+
+        let email = client
+            .assert_email_sent()
+            .to("A@example.com")
+            .subject_contains("welcome")
+            .await;
+        assert_eq!(
+            email.request.get_rendered_subject().as_deref(),
+            Some("Welcome aboard")
+        );
+    }
+
+    #[tokio::test]
+    async fn email_sent_within() {
+        let (_server, client) = start_app().await;
+        let sender = client.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            send_email(&sender, "a@example.com", "Later", "hi").await;
+        });
+
+        client
+            .assert_email_sent()
+            .to("a@example.com")
+            .within(Duration::from_secs(5))
+            .await;
+    }
+
+    #[tokio::test]
+    async fn no_match_diff() {
+        let (_server, client) = start_app().await;
+        send_email(&client, "b@example.com", "Welcome aboard", "hi").await;
+
+        let err = client
+            .assert_email_sent()
+            .to("a@example.com")
+            .subject_contains("welcome")
+            .check()
+            .await
+            .unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("      - to: a@example.com\n      + to: b@example.com\n"));
+        assert!(message.contains("        subject: Welcome aboard\n"));
+    }
+
+    #[tokio::test]
+    async fn template_subject_and_cc() {
+        let (_server, client) = start_app().await;
+        send(
+            &client,
+            &serde_json::json!({
+                "FromEmailAddress": "sender@example.com",
+                "Destination": {
+                    "ToAddresses": ["b@example.com"],
+                    "CcAddresses": ["c@example.com"]
+                },
+                "Content": {
+                    "Template": {
+                        "TemplateContent": { "Subject": "Welcome {{name}}" },
+                        "TemplateData": "{\"name\": \"Ann\"}"
+                    }
+                }
+            }),
+        )
+        .await;
+
+        client
+            .assert_email_sent()
+            .to("c@example.com")
+            .subject_contains("welcome ann")
+            .await;
+        let err = client
+            .assert_email_sent()
+            .to("a@example.com")
+            .check()
+            .await
+            .unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("      + to: b@example.com, cc: c@example.com\n"));
+        assert!(message.contains("        subject: Welcome Ann\n"));
+    }
+}
@@ -0,0 +1,283 @@
+use std::time::Duration;
+
+use reqwest::{header::ACCEPT, RequestBuilder, Response, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize};
+use ses_local_serde::{
+    send_email::{
+        extract::{Link, Match},
+        SendEmail,
+    },
+    EmailQuery, Event, EventQuery, Page,
+};
+
+use crate::{assert_email::EmailAssertion, error::ClientError};
+
+/// Typed access to the `/emails` and `/events` APIs of a running SES.local.
+#[derive(Clone, Debug)]
+pub struct Client {
+    http: reqwest::Client,
+    endpoint_url: String,
+}
+
+#[derive(Deserialize)]
+struct Deleted {
+    deleted: usize,
+}
+
+#[derive(Deserialize)]
+struct NearMisses {
+    near_misses: Vec<SendEmail>,
+}
+
+impl Client {
+    pub fn new(endpoint_url: impl Into<String>) -> Self {
+        Client {
+            http: reqwest::Client::new(),
+            endpoint_url: endpoint_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub fn endpoint_url(&self) -> &str {
+        &self.endpoint_url
+    }
+
+    pub async fn emails(&self, query: &EmailQuery) -> Result<Page<SendEmail>, ClientError> {
+        json(self.get("/emails").query(query)).await
+    }
+
+    /// Every email matching `query`, following `next_cursor` until the last page.
+    pub async fn all_emails(&self, query: &EmailQuery) -> Result<Vec<SendEmail>, ClientError> {
+        let mut query = query.clone();
+        let mut emails = vec![];
+        loop {
+            let page = self.emails(&query).await?;
+            emails.extend(page.items);
+            match page.next_cursor {
+                Some(cursor) => query.cursor = Some(cursor),
+                None => return Ok(emails),
+            }
+        }
+    }
+
+    pub async fn email(&self, message_id: &str) -> Result<Option<SendEmail>, ClientError> {
+        let response = self.get(&format!("/emails/{}", message_id)).send().await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            _ => Ok(Some(parse(response).await?)),
+        }
+    }
+
+    pub async fn events(&self, query: &EventQuery) -> Result<Page<Event>, ClientError> {
+        json(self.get("/events").query(query)).await
+    }
+
+    /// Waits up to `timeout`, which the server caps at five minutes, for an email matching
+    /// `query`. The oldest match already captured is returned straight away.
+    pub async fn wait_for_email(
+        &self,
+        query: &EmailQuery,
+        timeout: Duration,
+    ) -> Result<SendEmail, ClientError> {
+        let response = self
+            .get("/emails/wait")
+            .query(query)
+            .query(&[("timeout", format!("{}ms", timeout.as_millis()))])
+            .send()
+            .await?;
+        match response.status() {
+            StatusCode::REQUEST_TIMEOUT => {
+                let NearMisses { near_misses } = response.json().await?;
+                Err(ClientError::Timeout { near_misses })
+            }
+            _ => parse(response).await,
+        }
+    }
+
+    pub async fn links(&self, message_id: &str) -> Result<Vec<Link>, ClientError> {
+        json(self.get(&format!("/emails/{}/links", message_id))).await
+    }
+
+    pub async fn matches(&self, message_id: &str, regex: &str) -> Result<Vec<Match>, ClientError> {
+        json(
+            self.get(&format!("/emails/{}/match", message_id))
+                .query(&[("regex", regex)]),
+        )
+        .await
+    }
+
+    pub async fn delete_emails(&self, query: &EmailQuery) -> Result<usize, ClientError> {
+        let request = self.http.delete(self.url("/emails")).query(query);
+        let Deleted { deleted } = json(request).await?;
+        Ok(deleted)
+    }
+
+    /// Deletes an email and its events, returning whether it was found.
+    pub async fn delete_email(&self, message_id: &str) -> Result<bool, ClientError> {
+        let url = self.url(&format!("/emails/{}", message_id));
+        let response = self.http.delete(url).send().await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(false),
+            _ => check(response).await.map(|_| true),
+        }
+    }
+
+    /// Clears every captured email and event.
+    pub async fn reset(&self) -> Result<(), ClientError> {
+        check(self.http.delete(self.url("/events")).send().await?).await?;
+        Ok(())
+    }
+
+    /// Starts an assertion that an email was sent; await it to run the check.
+    pub fn assert_email_sent(&self) -> EmailAssertion<'_> {
+        EmailAssertion::new(self)
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.endpoint_url, path)
+    }
+
+    fn get(&self, path: &str) -> RequestBuilder {
+        self.http
+            .get(self.url(path))
+            .header(ACCEPT, "application/json")
+    }
+}
+
+async fn json<T: DeserializeOwned>(request: RequestBuilder) -> Result<T, ClientError> {
+    parse(request.send().await?).await
+}
+
+async fn parse<T: DeserializeOwned>(response: Response) -> Result<T, ClientError> {
+    Ok(check(response).await?.json().await?)
+}
+
+async fn check(response: Response) -> Result<Response, ClientError> {
+    let status = response.status();
+    if status.is_success() {
+        Ok(response)
+    } else {
+        Err(ClientError::Status {
+            status,
+            body: response.text().await.unwrap_or_default(),
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use ses_local::{Server, ServerHandle};
+
+    use super::*;
+
+    pub(crate) async fn start_app() -> (ServerHandle, Client) {
+        let server = Server::builder().start().await.unwrap();
+        let client = Client::new(server.endpoint_url());
+        (server, client)
+    }
+
+    pub(crate) async fn send_email(client: &Client, to: &str, subject: &str, text: &str) -> String {
+        let body = serde_json::json!({
+            "FromEmailAddress": "sender@example.com",
+            "Destination": { "ToAddresses": [to] },
+            "Content": {
+                "Simple": {
+                    "Subject": { "Data": subject },
+                    "Body": { "Text": { "Data": text } }
+                }
+            }
+        });
+        send(client, &body).await
+    }
+
+    /// Sends the `SendEmailInput` JSON `body`, returning its message id.
+    pub(crate) async fn send(client: &Client, body: &serde_json::Value) -> String {
+        // The handler responds with the SendEmailOutput JSON as a string.
+        let response: String = client
+            .http
+            .post(client.url("/v2/email/outbound-emails"))
+            .json(body)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+        response["MessageId"].as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn query_emails() {
+        let (_server, client) = start_app().await;
+        send_email(&client, "a@example.com", "Welcome", "hi").await;
+        let id = send_email(&client, "b@example.com", "Reset password", "hi").await;
+        send_email(&client, "b@example.com", "Welcome", "hi").await;
+
+        let query = EmailQuery {
+            to: Some(String::from("b@example.com")),
+            subject: Some(String::from("reset")),
+            ..Default::default()
+        };
+        let page = client.emails(&query).await.unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(
+            page.items[0].response.message_id.as_deref(),
+            Some(id.as_str())
+        );
+
+        let query = EmailQuery {
+            limit: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(client.emails(&query).await.unwrap().items.len(), 2);
+        assert_eq!(client.all_emails(&query).await.unwrap().len(), 3);
+
+        assert!(client.email(&id).await.unwrap().is_some());
+        assert!(client.email("missing").await.unwrap().is_none());
+
+        assert!(client.delete_email(&id).await.unwrap());
+        assert!(!client.delete_email(&id).await.unwrap());
+        assert!(client.email(&id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn links_wait_and_reset() {
+        let (_server, client) = start_app().await;
+        let id = send_email(
+            &client,
+            "a@example.com",
+            "Verify",
+            "Go to https://app.example.com/verify?code=123456",
+        )
+        .await;
+
+        let links = client.links(&id).await.unwrap();
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].href, "https://app.example.com/verify?code=123456");
+        let matches = client.matches(&id, r"code=(\d+)").await.unwrap();
+        assert_eq!(matches[0].groups[1].as_deref(), Some("123456"));
+
+        let query = EmailQuery {
+            to: Some(String::from("nobody@example.com")),
+            ..Default::default()
+        };
+        match client
+            .wait_for_email(&query, Duration::from_millis(50))
+            .await
+        {
+            // Sent to the same domain, so it comes back as a near miss.
+            Err(ClientError::Timeout { near_misses }) => assert_eq!(
+                near_misses
+                    .iter()
+                    .map(|email| email.response.message_id.as_deref())
+                    .collect::<Vec<_>>(),
+                [Some(id.as_str())]
+            ),
+            other => panic!("expected a timeout, got {:?}", other),
+        }
+
+        client.reset().await.unwrap();
+        let all = client.all_emails(&EmailQuery::default()).await.unwrap();
+        assert!(all.is_empty());
+    }
+}
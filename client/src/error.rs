@@ -0,0 +1,13 @@
+use reqwest::StatusCode;
+use ses_local_serde::send_email::SendEmail;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("request to SES.local failed: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("SES.local responded {status}: {body}")]
+    Status { status: StatusCode, body: String },
+    #[error("no matching email arrived before the timeout")]
+    Timeout { near_misses: Vec<SendEmail> },
+}
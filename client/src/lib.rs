@@ -0,0 +1,14 @@
+mod assert_email;
+mod client;
+mod error;
+
+pub use assert_email::{AssertionError, EmailAssertion};
+pub use client::Client;
+pub use error::ClientError;
+pub use ses_local_serde::{
+    send_email::{
+        extract::{Link, Match, Part},
+        SendEmail,
+    },
+    EmailQuery, Event, EventQuery, Page,
+};
@@ -1,9 +1,40 @@
-use serde::{Deserialize, Serialize};
+use aws_smithy_types::base64;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 // use aws_smithy_types::Blob;
 // #[serde(remote = "Blob")]
-#[serde(rename_all = "PascalCase")]
 pub struct Blob {
     pub inner: Vec<u8>,
 }
+
+// Blobs travel over the SES JSON protocol as base64 encoded strings.
+impl Serialize for Blob {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::encode(&self.inner))
+    }
+}
+
+impl<'de> Deserialize<'de> for Blob {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        base64::decode(encoded)
+            .map(|inner| Blob { inner })
+            .map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_as_base64() {
+        let blob = Blob {
+            inner: b"Subject: hi\r\n\r\nbody".to_vec(),
+        };
+        let json = serde_json::to_string(&blob).unwrap();
+        assert_eq!(json, "\"U3ViamVjdDogaGkNCg0KYm9keQ==\"");
+        assert_eq!(serde_json::from_str::<Blob>(&json).unwrap(), blob);
+    }
+}
@@ -0,0 +1,64 @@
+/// An address as written in the SES API or a header, e.g. `"Doe, Jane" <jane@example.com>`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mailbox<'a> {
+    pub name: Option<&'a str>,
+    pub address: &'a str,
+}
+
+pub fn parse(mailbox: &str) -> Mailbox<'_> {
+    let mailbox = mailbox.trim();
+    match (mailbox.rfind('<'), mailbox.strip_suffix('>')) {
+        (Some(open), Some(rest)) => {
+            let name = mailbox[..open].trim();
+            let name = name
+                .strip_prefix('"')
+                .and_then(|name| name.strip_suffix('"'))
+                .unwrap_or(name);
+            Mailbox {
+                name: Some(name).filter(|name| !name.is_empty()),
+                address: rest[open + 1..].trim(),
+            }
+        }
+        _ => Mailbox {
+            name: None,
+            address: mailbox,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_names() {
+        assert_eq!(
+            parse("Jane Doe <jane@example.com>"),
+            Mailbox {
+                name: Some("Jane Doe"),
+                address: "jane@example.com"
+            }
+        );
+        assert_eq!(
+            parse(r#" "Doe, Jane" <jane@example.com> "#),
+            Mailbox {
+                name: Some("Doe, Jane"),
+                address: "jane@example.com"
+            }
+        );
+        assert_eq!(
+            parse("<jane@example.com>"),
+            Mailbox {
+                name: None,
+                address: "jane@example.com"
+            }
+        );
+        assert_eq!(
+            parse("jane@example.com"),
+            Mailbox {
+                name: None,
+                address: "jane@example.com"
+            }
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use std::{borrow::Cow, fmt::Debug};
+
+use ses_serde::{
+    operations::send_email::SendEmailInput,
+    types::{Attachment, Destination, MessageHeader},
+};
+
+mod raw_email;
+mod simple_email;
+mod template_email;
+mod unknown_email;
+pub use raw_email::RawEmail;
+pub use simple_email::SimpleEmail;
+pub use template_email::TemplateEmail;
+pub use unknown_email::UnknownEmail;
+
+#[derive(Debug, PartialEq)]
+pub struct Body<'a> {
+    pub content: Option<&'a str>,
+    pub is_html: bool,
+}
+
+pub trait EmailWrapper: Debug {
+    fn get_subject(_: &SendEmailInput) -> Option<Cow<'_, str>> {
+        None
+    }
+    /// The subject as delivered, i.e. with any template placeholders filled in.
+    fn get_rendered_subject(email: &SendEmailInput) -> Option<Cow<'_, str>> {
+        Self::get_subject(email)
+    }
+    fn get_to(email: &SendEmailInput) -> Option<&Destination> {
+        email.destination.as_ref()
+    }
+    fn get_from(_: &SendEmailInput) -> Option<&str> {
+        None
+    }
+    fn get_reply_to(email: &SendEmailInput) -> Option<&[String]> {
+        email.reply_to_addresses.as_deref()
+    }
+    fn get_headers(_: &SendEmailInput) -> &[MessageHeader] {
+        &[]
+    }
+    fn get_attachments(_: &SendEmailInput) -> &[Attachment] {
+        &[]
+    }
+    fn get_body(_: &SendEmailInput) -> Option<Body<'_>> {
+        None
+    }
+    fn get_html(_: &SendEmailInput) -> Option<Cow<'_, str>> {
+        None
+    }
+    fn get_text(_: &SendEmailInput) -> Option<Cow<'_, str>> {
+        None
+    }
+}
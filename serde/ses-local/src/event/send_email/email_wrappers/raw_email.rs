@@ -0,0 +1,40 @@
+use std::borrow::Cow;
+
+use mail_parser::{HeaderName, Message, MessageParser};
+use ses_serde::operations::send_email::SendEmailInput;
+
+use super::EmailWrapper;
+
+#[derive(Debug)]
+pub struct RawEmail {}
+
+impl RawEmail {
+    pub fn parse(email: &SendEmailInput) -> Option<Message<'_>> {
+        MessageParser::default().parse(&email.content.as_ref()?.raw.as_ref()?.data.inner)
+    }
+}
+
+impl EmailWrapper for RawEmail {
+    fn get_subject(email: &SendEmailInput) -> Option<Cow<'_, str>> {
+        RawEmail::parse(email)?
+            .remove_header(HeaderName::Subject)?
+            .into_text()
+    }
+    fn get_from(email: &SendEmailInput) -> Option<&str> {
+        email.from_email_address.as_deref()
+    }
+    fn get_html(email: &SendEmailInput) -> Option<Cow<'_, str>> {
+        RawEmail::parse(email)?
+            .html_bodies()
+            .find(|part| part.is_text_html())
+            .and_then(|part| part.text_contents())
+            .map(|html| Cow::Owned(html.to_string()))
+    }
+    fn get_text(email: &SendEmailInput) -> Option<Cow<'_, str>> {
+        RawEmail::parse(email)?
+            .text_bodies()
+            .find(|part| part.is_text() && !part.is_text_html())
+            .and_then(|part| part.text_contents())
+            .map(|text| Cow::Owned(text.to_string()))
+    }
+}
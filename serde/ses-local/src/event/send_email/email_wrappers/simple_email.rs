@@ -0,0 +1,75 @@
+use std::borrow::Cow;
+
+use super::{Body, EmailWrapper};
+use ses_serde::{
+    operations::send_email::SendEmailInput,
+    types::{Attachment, Body as SESBody, Message, MessageHeader},
+};
+
+#[derive(Debug)]
+pub struct SimpleEmail {}
+
+impl EmailWrapper for SimpleEmail {
+    fn get_subject(email: &SendEmailInput) -> Option<Cow<'_, str>> {
+        email
+            .content
+            .as_ref()?
+            .simple
+            .as_ref()?
+            .subject
+            .as_ref()
+            .map(|x| Cow::Borrowed(x.data.as_str()))
+    }
+    fn get_from(email: &SendEmailInput) -> Option<&str> {
+        email.from_email_address.as_deref()
+    }
+    fn get_body(email: &SendEmailInput) -> Option<Body<'_>> {
+        email
+            .content
+            .as_ref()?
+            .simple
+            .as_ref()?
+            .body
+            .as_ref()
+            .map(|x| match &x.html {
+                Some(html) => Body {
+                    content: Some(html.data.as_str()),
+                    is_html: true,
+                },
+                None => Body {
+                    content: x.text.as_ref().map(|x| x.data.as_str()),
+                    is_html: false,
+                },
+            })
+    }
+    fn get_headers(email: &SendEmailInput) -> &[MessageHeader] {
+        get_message(email)
+            .and_then(|m| m.headers.as_deref())
+            .unwrap_or_default()
+    }
+    fn get_attachments(email: &SendEmailInput) -> &[Attachment] {
+        get_message(email)
+            .and_then(|m| m.attachments.as_deref())
+            .unwrap_or_default()
+    }
+    fn get_html(email: &SendEmailInput) -> Option<Cow<'_, str>> {
+        get_ses_body(email)?
+            .html
+            .as_ref()
+            .map(|x| Cow::Borrowed(x.data.as_str()))
+    }
+    fn get_text(email: &SendEmailInput) -> Option<Cow<'_, str>> {
+        get_ses_body(email)?
+            .text
+            .as_ref()
+            .map(|x| Cow::Borrowed(x.data.as_str()))
+    }
+}
+
+fn get_message(email: &SendEmailInput) -> Option<&Message> {
+    email.content.as_ref()?.simple.as_ref()
+}
+
+fn get_ses_body(email: &SendEmailInput) -> Option<&SESBody> {
+    get_message(email)?.body.as_ref()
+}
@@ -0,0 +1,137 @@
+use std::{borrow::Cow, sync::LazyLock};
+
+use super::{Body, EmailWrapper};
+use regex::{Captures, Regex};
+use serde_json::Value;
+use ses_serde::{
+    operations::send_email::SendEmailInput,
+    types::{Attachment, MessageHeader, Template},
+};
+
+// `{{{name}}}` first, so its braces aren't taken for an escaped `{{name}}` inside.
+static PLACEHOLDER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{\{\s*([\w.-]+)\s*\}\}\}|\{\{\s*([\w.-]+)\s*\}\}").unwrap());
+
+#[derive(Debug)]
+pub struct TemplateEmail {}
+
+impl EmailWrapper for TemplateEmail {
+    fn get_subject(email: &SendEmailInput) -> Option<Cow<'_, str>> {
+        email
+            .content
+            .as_ref()?
+            .template
+            .as_ref()?
+            .template_content
+            .as_ref()?
+            .subject
+            .as_deref()
+            .map(Cow::Borrowed)
+    }
+    fn get_rendered_subject(email: &SendEmailInput) -> Option<Cow<'_, str>> {
+        let template = get_template(email)?;
+        let subject = template.template_content.as_ref()?.subject.as_deref()?;
+        Some(render(subject, template.template_data.as_deref(), false))
+    }
+    fn get_from(email: &SendEmailInput) -> Option<&str> {
+        email.from_email_address.as_deref()
+    }
+    fn get_headers(email: &SendEmailInput) -> &[MessageHeader] {
+        get_template(email)
+            .and_then(|t| t.headers.as_deref())
+            .unwrap_or_default()
+    }
+    fn get_attachments(email: &SendEmailInput) -> &[Attachment] {
+        get_template(email)
+            .and_then(|t| t.attachments.as_deref())
+            .unwrap_or_default()
+    }
+    fn get_body(email: &SendEmailInput) -> Option<Body<'_>> {
+        email
+            .content
+            .as_ref()?
+            .template
+            .as_ref()?
+            .template_content
+            .as_ref()
+            .map(|x| match &x.html {
+                Some(html) => Body {
+                    content: Some(html.as_str()),
+                    is_html: true,
+                },
+                None => Body {
+                    content: x.text.as_deref(),
+                    is_html: false,
+                },
+            })
+        // .and_then(|x| {
+        //     if let Some(_) = &x.text {
+        //         x.text.as_ref()
+        //     } else if let Some(_) = &x.html {
+        //         x.html.as_ref()
+        //     } else {
+        //         None
+        //     }
+        // })
+    }
+    fn get_html(email: &SendEmailInput) -> Option<Cow<'_, str>> {
+        let template = get_template(email)?;
+        let html = template.template_content.as_ref()?.html.as_deref()?;
+        Some(render(html, template.template_data.as_deref(), true))
+    }
+    fn get_text(email: &SendEmailInput) -> Option<Cow<'_, str>> {
+        let template = get_template(email)?;
+        let text = template.template_content.as_ref()?.text.as_deref()?;
+        Some(render(text, template.template_data.as_deref(), false))
+    }
+}
+
+fn get_template(email: &SendEmailInput) -> Option<&Template> {
+    email.content.as_ref()?.template.as_ref()
+}
+
+/// Fills `{{name}}` / `{{a.b}}` placeholders from the JSON template data, HTML-escaping the
+/// values if `escape`, and `{{{name}}}` placeholders with the values as they are.
+/// Placeholders without a value render as an empty string.
+///
+/// This covers the variables SES templates use, not Handlebars: helpers and blocks such as
+/// `{{#if}}` and `{{#each}}` are left in the output as written.
+fn render<'a>(content: &'a str, template_data: Option<&str>, escape: bool) -> Cow<'a, str> {
+    let data = template_data
+        .and_then(|d| serde_json::from_str::<Value>(d).ok())
+        .unwrap_or(Value::Null);
+    PLACEHOLDER.replace_all(content, |caps: &Captures| {
+        let (name, escape) = match caps.get(1) {
+            Some(raw) => (raw.as_str(), false),
+            None => (&caps[2], escape),
+        };
+        let value = name.split('.').try_fold(&data, |value, key| value.get(key));
+        let value = match value {
+            Some(Value::String(s)) => s.clone(),
+            Some(Value::Null) | None => String::new(),
+            Some(other) => other.to_string(),
+        };
+        match escape {
+            true => escape_html(&value),
+            false => value,
+        }
+    })
+}
+
+// As Handlebars escapes, `=` and backticks included.
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#x27;"),
+            '`' => escaped.push_str("&#x60;"),
+            '=' => escaped.push_str("&#x3D;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,33 @@
+// What `/emails/{id}/links` and `/emails/{id}/match` return.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Part {
+    Html,
+    Text,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Link {
+    pub href: String,
+    /// `href` with any click-tracking redirects unwrapped.
+    pub resolved: String,
+    pub text: Option<String>,
+    pub part: Part,
+    /// Index of the link within its part, in document order.
+    pub position: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Match {
+    pub part: Part,
+    /// Byte offset of the match within the decoded part.
+    pub offset: usize,
+    /// Capture groups, `groups[0]` being the whole match.
+    pub groups: Vec<Option<String>>,
+    pub named: HashMap<String, String>,
+}
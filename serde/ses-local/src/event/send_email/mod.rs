@@ -0,0 +1,488 @@
+use std::borrow::Cow;
+
+use email_wrappers::{EmailWrapper, SimpleEmail, TemplateEmail, UnknownEmail};
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use ses_serde::{
+    operations::send_email::{SendEmailInput, SendEmailOutput},
+    types::{Attachment, Destination, MessageHeader},
+};
+use uuid::Uuid;
+
+pub mod address;
+mod email_wrappers;
+pub mod extract;
+
+pub use email_wrappers::{Body, RawEmail};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SendEmail {
+    pub request: EmailRequest,
+    pub response: SendEmailOutput,
+    /// Uploaded through `POST /emails/import` rather than sent to the SES API.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub imported: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, strum_macros::Display)]
+pub enum EmailTag {
+    Simple,
+    Template,
+    Raw,
+    Unknown,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, strum_macros::Display, PartialEq)]
+pub enum EmailRequest {
+    Simple(SendEmailInput),
+    Template(SendEmailInput),
+    Raw(SendEmailInput),
+    Unknown(SendEmailInput),
+}
+
+pub struct Summary<'a> {
+    pub subject: Option<Cow<'a, str>>,
+    pub from: Option<&'a str>,
+    pub to: Option<&'a Destination>,
+}
+
+impl SendEmail {
+    pub fn new(email: SendEmailInput) -> Self {
+        SendEmail {
+            request: EmailRequest::new(email),
+            response: SendEmailOutput {
+                message_id: Some(new_message_id()),
+            },
+            imported: false,
+        }
+    }
+}
+
+/// A message id in the format SES uses: a timestamp, a UUID and a counter, such as
+/// `0100018f2b1a3c4d-3f2b...-000000`.
+pub fn new_message_id() -> String {
+    format!(
+        "0100{:012x}-{}-000000",
+        Timestamp::now().as_millisecond(),
+        Uuid::new_v4()
+    )
+}
+
+impl EmailRequest {
+    pub fn new(email: SendEmailInput) -> Self {
+        if let Some(content) = &email.content {
+            if content.simple.is_some() {
+                EmailRequest::Simple(email)
+            } else if content.template.is_some() {
+                EmailRequest::Template(email)
+            } else if content.raw.is_some() {
+                EmailRequest::Raw(email)
+            } else {
+                EmailRequest::Unknown(email)
+            }
+        } else {
+            EmailRequest::Unknown(email)
+        }
+    }
+
+    pub fn get_tag(&self) -> EmailTag {
+        match &self {
+            EmailRequest::Simple(_) => EmailTag::Simple,
+            EmailRequest::Template(_) => EmailTag::Template,
+            EmailRequest::Raw(_) => EmailTag::Raw,
+            EmailRequest::Unknown(_) => EmailTag::Unknown,
+        }
+    }
+
+    pub fn get_input(&self) -> &SendEmailInput {
+        match &self {
+            EmailRequest::Simple(e)
+            | EmailRequest::Template(e)
+            | EmailRequest::Raw(e)
+            | EmailRequest::Unknown(e) => e,
+        }
+    }
+
+    pub fn get_subject(&self) -> Option<Cow<'_, str>> {
+        match &self {
+            EmailRequest::Simple(e) => SimpleEmail::get_subject(e),
+            EmailRequest::Template(e) => TemplateEmail::get_subject(e),
+            EmailRequest::Raw(e) => RawEmail::get_subject(e),
+            EmailRequest::Unknown(e) => UnknownEmail::get_subject(e),
+        }
+    }
+
+    pub fn get_rendered_subject(&self) -> Option<Cow<'_, str>> {
+        match &self {
+            EmailRequest::Simple(e) => SimpleEmail::get_rendered_subject(e),
+            EmailRequest::Template(e) => TemplateEmail::get_rendered_subject(e),
+            EmailRequest::Raw(e) => RawEmail::get_rendered_subject(e),
+            EmailRequest::Unknown(e) => UnknownEmail::get_rendered_subject(e),
+        }
+    }
+
+    pub fn get_to(&self) -> Option<&Destination> {
+        match &self {
+            EmailRequest::Simple(e) => SimpleEmail::get_to(e),
+            EmailRequest::Template(e) => TemplateEmail::get_to(e),
+            EmailRequest::Raw(e) => RawEmail::get_to(e),
+            EmailRequest::Unknown(e) => UnknownEmail::get_to(e),
+        }
+    }
+
+    pub fn get_from(&self) -> Option<&str> {
+        match &self {
+            EmailRequest::Simple(e) => SimpleEmail::get_from(e),
+            EmailRequest::Template(e) => TemplateEmail::get_from(e),
+            EmailRequest::Raw(e) => RawEmail::get_from(e),
+            EmailRequest::Unknown(e) => UnknownEmail::get_from(e),
+        }
+    }
+
+    pub fn get_reply_to(&self) -> Option<&[String]> {
+        match &self {
+            EmailRequest::Simple(e) => SimpleEmail::get_reply_to(e),
+            EmailRequest::Template(e) => TemplateEmail::get_reply_to(e),
+            EmailRequest::Raw(e) => RawEmail::get_reply_to(e),
+            EmailRequest::Unknown(e) => UnknownEmail::get_reply_to(e),
+        }
+    }
+
+    pub fn get_headers(&self) -> &[MessageHeader] {
+        match &self {
+            EmailRequest::Simple(e) => SimpleEmail::get_headers(e),
+            EmailRequest::Template(e) => TemplateEmail::get_headers(e),
+            EmailRequest::Raw(e) => RawEmail::get_headers(e),
+            EmailRequest::Unknown(e) => UnknownEmail::get_headers(e),
+        }
+    }
+
+    pub fn get_attachments(&self) -> &[Attachment] {
+        match &self {
+            EmailRequest::Simple(e) => SimpleEmail::get_attachments(e),
+            EmailRequest::Template(e) => TemplateEmail::get_attachments(e),
+            EmailRequest::Raw(e) => RawEmail::get_attachments(e),
+            EmailRequest::Unknown(e) => UnknownEmail::get_attachments(e),
+        }
+    }
+
+    pub fn get_body(&self) -> Option<Body<'_>> {
+        match &self {
+            EmailRequest::Simple(e) => SimpleEmail::get_body(e),
+            EmailRequest::Template(e) => TemplateEmail::get_body(e),
+            EmailRequest::Raw(e) => RawEmail::get_body(e),
+            EmailRequest::Unknown(e) => UnknownEmail::get_body(e),
+        }
+    }
+
+    pub fn get_html(&self) -> Option<Cow<'_, str>> {
+        match &self {
+            EmailRequest::Simple(e) => SimpleEmail::get_html(e),
+            EmailRequest::Template(e) => TemplateEmail::get_html(e),
+            EmailRequest::Raw(e) => RawEmail::get_html(e),
+            EmailRequest::Unknown(e) => UnknownEmail::get_html(e),
+        }
+    }
+
+    pub fn get_text(&self) -> Option<Cow<'_, str>> {
+        match &self {
+            EmailRequest::Simple(e) => SimpleEmail::get_text(e),
+            EmailRequest::Template(e) => TemplateEmail::get_text(e),
+            EmailRequest::Raw(e) => RawEmail::get_text(e),
+            EmailRequest::Unknown(e) => UnknownEmail::get_text(e),
+        }
+    }
+
+    pub fn get_summary(&self) -> Summary<'_> {
+        Summary {
+            subject: self.get_rendered_subject(),
+            from: self.get_from(),
+            to: self.get_to(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use aws_smithy_serde::types::Blob;
+    use ses_serde::types::{
+        Body as SESBody, Content, EmailContent, EmailTemplateContent, Message, RawMessage, Template,
+    };
+
+    use super::*;
+
+    struct ContentWrapper {
+        text: Option<String>,
+        html: Option<String>,
+    }
+
+    struct SendEmailInputWrapper {
+        email_tag: EmailTag,
+        to: Option<String>,
+        from: Option<String>,
+        subject: Option<String>,
+        content: Option<ContentWrapper>,
+    }
+
+    fn create_email(config: SendEmailInputWrapper) -> SendEmailInput {
+        let email_content = match config.email_tag {
+            EmailTag::Simple => EmailContent {
+                simple: Some(Message {
+                    subject: config.subject.map(|data| Content {
+                        data,
+                        charset: None,
+                    }),
+                    body: config.content.map(|x| SESBody {
+                        text: x.text.map(|data| Content {
+                            data,
+                            charset: None,
+                        }),
+                        html: x.html.map(|data| Content {
+                            data,
+                            charset: None,
+                        }),
+                    }),
+                    headers: None,
+                    attachments: None,
+                }),
+                raw: None,
+                template: None,
+            },
+            EmailTag::Template => EmailContent {
+                template: Some(Template {
+                    template_content: Some(EmailTemplateContent {
+                        subject: config.subject,
+                        text: config.content.as_ref().and_then(|x| x.text.clone()),
+                        html: config.content.as_ref().and_then(|x| x.html.clone()),
+                    }),
+                    template_name: None,
+                    template_arn: None,
+                    template_data: None,
+                    headers: None,
+                    attachments: None,
+                }),
+                simple: None,
+                raw: None,
+            },
+            _ => EmailContent {
+                simple: None,
+                raw: None,
+                template: None,
+            },
+        };
+        SendEmailInput {
+            destination: Some(Destination {
+                to_addresses: config.to.map(|x| vec![x]),
+                cc_addresses: None,
+                bcc_addresses: None,
+            }),
+            from_email_address: config.from,
+            content: Some(email_content),
+            from_email_address_identity_arn: None,
+            reply_to_addresses: None,
+            feedback_forwarding_email_address: None,
+            feedback_forwarding_email_address_identity_arn: None,
+            email_tags: None,
+            configuration_set_name: None,
+            endpoint_id: None,
+            list_management_options: None,
+        }
+    }
+
+    #[test]
+    fn simple_email_text() {
+        let sei = create_email(SendEmailInputWrapper {
+            email_tag: EmailTag::Simple,
+            to: Some("to@example.com".to_string()),
+            from: Some("from@example.com".to_string()),
+            subject: Some("Email Subject!".to_string()),
+            content: Some(ContentWrapper {
+                html: Some("Email Content ... html".to_string()),
+                text: None, //Some("Email Content ... text".to_string()),
+            }),
+        });
+
+        let re = EmailRequest::new(sei);
+        assert_eq!(
+            re.get_to().unwrap().to_addresses,
+            Some(vec!["to@example.com".to_string()])
+        );
+        assert_eq!(re.get_from().unwrap(), "from@example.com");
+        assert_eq!(re.get_subject().unwrap(), "Email Subject!");
+        assert_eq!(
+            re.get_body().unwrap(),
+            Body {
+                content: Some("Email Content ... html"),
+                is_html: true
+            }
+        );
+    }
+
+    #[test]
+    fn simple_email_html() {
+        let sei = create_email(SendEmailInputWrapper {
+            email_tag: EmailTag::Simple,
+            to: Some("to@example.com".to_string()),
+            from: Some("from@example.com".to_string()),
+            subject: Some("Email Subject!".to_string()),
+            content: Some(ContentWrapper {
+                html: None,
+                text: Some("Email Content ... text".to_string()),
+            }),
+        });
+
+        let re = EmailRequest::new(sei);
+        assert_eq!(
+            re.get_to().unwrap().to_addresses,
+            Some(vec!["to@example.com".to_string()])
+        );
+        assert_eq!(re.get_from().unwrap(), "from@example.com");
+        assert_eq!(re.get_subject().unwrap(), "Email Subject!");
+        assert_eq!(
+            re.get_body().unwrap(),
+            Body {
+                content: Some("Email Content ... text"),
+                is_html: false
+            }
+        );
+    }
+
+    #[test]
+    fn template_email_html() {
+        let sei = create_email(SendEmailInputWrapper {
+            email_tag: EmailTag::Template,
+            to: Some("to@example.com".to_string()),
+            from: Some("from@example.com".to_string()),
+            subject: Some("Email Subject!".to_string()),
+            content: Some(ContentWrapper {
+                html: Some("Email Content ... html".to_string()),
+                text: None,
+            }),
+        });
+
+        let re = EmailRequest::new(sei);
+        assert_eq!(
+            re.get_to().unwrap().to_addresses,
+            Some(vec!["to@example.com".to_string()])
+        );
+        assert_eq!(re.get_from().unwrap(), "from@example.com");
+        assert_eq!(re.get_subject().unwrap(), "Email Subject!");
+        assert_eq!(
+            re.get_body().unwrap(),
+            Body {
+                content: Some("Email Content ... html"),
+                is_html: true
+            }
+        );
+    }
+
+    #[test]
+    fn template_email_text() {
+        let sei = create_email(SendEmailInputWrapper {
+            email_tag: EmailTag::Template,
+            to: Some("to@example.com".to_string()),
+            from: Some("from@example.com".to_string()),
+            subject: Some("Email Subject!".to_string()),
+            content: Some(ContentWrapper {
+                text: Some("Email Content ... text".to_string()),
+                html: None, //Some("Email Content ... text".to_string()),
+            }),
+        });
+
+        let re = EmailRequest::new(sei);
+        assert_eq!(
+            re.get_to().unwrap().to_addresses,
+            Some(vec!["to@example.com".to_string()])
+        );
+        assert_eq!(re.get_from().unwrap(), "from@example.com");
+        assert_eq!(re.get_subject().unwrap(), "Email Subject!");
+        assert_eq!(
+            re.get_body().unwrap(),
+            Body {
+                content: Some("Email Content ... text"),
+                is_html: false
+            }
+        );
+    }
+
+    #[test]
+    fn template_email_rendered() {
+        let mut sei = create_email(SendEmailInputWrapper {
+            email_tag: EmailTag::Template,
+            to: Some("to@example.com".to_string()),
+            from: Some("from@example.com".to_string()),
+            subject: Some("Email Subject!".to_string()),
+            content: Some(ContentWrapper {
+                text: Some("Hi {{name}}, use {{ code }}{{missing}} {{#if vip}}".to_string()),
+                html: Some(
+                    "<a href=\"https://example.com/{{user.id}}\">{{name}}</a>{{{sig}}}".to_string(),
+                ),
+            }),
+        });
+        if let Some(template) = sei.content.as_mut().and_then(|c| c.template.as_mut()) {
+            template.template_data =
+                Some(r#"{ "name": "Al & <Bo>", "code": 1234, "user": { "id": "u1" }, "sig": "<b>Hi</b>" }"#.to_string());
+        }
+
+        let re = EmailRequest::new(sei);
+        assert_eq!(re.get_text().unwrap(), "Hi Al & <Bo>, use 1234 {{#if vip}}");
+        assert_eq!(
+            re.get_html().unwrap(),
+            "<a href=\"https://example.com/u1\">Al &amp; &lt;Bo&gt;</a><b>Hi</b>"
+        );
+    }
+
+    #[test]
+    fn raw_email_parts() {
+        let mime = "From: from@example.com\r\n\
+            To: to@example.com\r\n\
+            Subject: =?UTF-8?Q?Caf=C3=A9_Subject!?=\r\n\
+            MIME-Version: 1.0\r\n\
+            Content-Type: multipart/alternative; boundary=\"b\"\r\n\
+            \r\n\
+            --b\r\n\
+            Content-Type: text/plain; charset=utf-8\r\n\
+            \r\n\
+            Email Content ... text\r\n\
+            --b\r\n\
+            Content-Type: text/html; charset=utf-8\r\n\
+            Content-Transfer-Encoding: base64\r\n\
+            \r\n\
+            PHA+RW1haWwgQ29udGVudCAuLi4gaHRtbDwvcD4=\r\n\
+            --b--\r\n";
+        let mut sei = create_email(SendEmailInputWrapper {
+            email_tag: EmailTag::Raw,
+            to: Some("to@example.com".to_string()),
+            from: None,
+            subject: None,
+            content: None,
+        });
+        sei.content = Some(EmailContent {
+            simple: None,
+            template: None,
+            raw: Some(RawMessage {
+                data: Blob {
+                    inner: mime.as_bytes().to_vec(),
+                },
+            }),
+        });
+
+        let re = EmailRequest::new(sei);
+        assert_eq!(re.get_tag().to_string(), "Raw");
+        assert_eq!(re.get_subject().unwrap(), "Café Subject!");
+        assert_eq!(re.get_rendered_subject().unwrap(), "Café Subject!");
+        assert_eq!(re.get_text().unwrap().trim_end(), "Email Content ... text");
+        assert_eq!(re.get_html().unwrap(), "<p>Email Content ... html</p>");
+    }
+
+    #[test]
+    fn ses_style_message_ids() {
+        let id = new_message_id();
+        let parts: Vec<&str> = id.split('-').collect();
+        assert_eq!(parts.len(), 7);
+        assert!(parts[0].starts_with("0100") && parts[0].len() == 16);
+        assert!(Uuid::parse_str(&parts[1..6].join("-")).is_ok());
+        assert_eq!(parts[6], "000000");
+    }
+}
@@ -0,0 +1,6 @@
+// The events, emails and queries of SES.local's own API, shared by the server and its client.
+mod event;
+mod query;
+
+pub use event::{send_email, Event, EventContent};
+pub use query::{EmailQuery, EventQuery, Page, QueryError};
@@ -0,0 +1,432 @@
+use jiff::{civil, tz::TimeZone, Timestamp};
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
+use thiserror::Error;
+
+use crate::{
+    send_email::{address, SendEmail},
+    Event,
+};
+
+// Subject words shorter than this, like "a" or "the", are too common to count as overlap.
+const MIN_WORD_LEN: usize = 4;
+
+// Text fields default to `None` when empty, since HTML forms submit every field.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct EmailQuery {
+    /// Free text, matched against the subject, sender and recipients.
+    #[serde(
+        default,
+        deserialize_with = "non_empty",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub q: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "non_empty",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub to: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "non_empty",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub from: Option<String>,
+    #[serde(
+        alias = "subject_contains",
+        default,
+        deserialize_with = "non_empty",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub subject: Option<String>,
+    /// `Simple`, `Template` or `Raw`.
+    #[serde(
+        rename = "type",
+        default,
+        deserialize_with = "non_empty",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub email_type: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "non_empty",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub configuration_set: Option<String>,
+    /// A message tag's name, or `name=value`.
+    #[serde(
+        default,
+        deserialize_with = "non_empty",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub tag: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "timestamp",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub since: Option<Timestamp>,
+    #[serde(
+        default,
+        deserialize_with = "timestamp",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub until: Option<Timestamp>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct EventQuery {
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub event_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<Timestamp>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum QueryError {
+    /// The cursor isn't one a page returned.
+    #[error("invalid cursor")]
+    InvalidCursor,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+    /// How many items there are across every page.
+    #[serde(default)]
+    pub total: usize,
+}
+
+impl EmailQuery {
+    pub fn matches(&self, event: &Event, email: &SendEmail) -> bool {
+        self.matches_to(email) != Some(false)
+            && self.matches_subject(email) != Some(false)
+            && self.matches_filters(email)
+            && is_since(event, self.since.as_ref())
+            && is_until(event, self.until.as_ref())
+    }
+
+    /// An email that misses the `to`/`subject` criteria but comes close: it satisfies one of
+    /// them, has a recipient at the same domain as `to`, or shares a word with `subject`.
+    pub fn nearly_matches(&self, event: &Event, email: &SendEmail) -> bool {
+        let criteria = [self.matches_to(email), self.matches_subject(email)];
+        let close = [self.near_to(email), self.near_subject(email)];
+        is_since(event, self.since.as_ref())
+            && criteria.contains(&Some(false))
+            && (criteria.contains(&Some(true)) || close.contains(&Some(true)))
+    }
+
+    fn near_to(&self, email: &SendEmail) -> Option<bool> {
+        let to = address::parse(self.to.as_ref()?).address;
+        let (_, domain) = to.rsplit_once('@')?;
+        Some(recipients(email).any(|mailbox| {
+            address::parse(mailbox)
+                .address
+                .rsplit_once('@')
+                .is_some_and(|(_, d)| d.eq_ignore_ascii_case(domain))
+        }))
+    }
+
+    fn near_subject(&self, email: &SendEmail) -> Option<bool> {
+        let subject = email.request.get_rendered_subject()?.to_lowercase();
+        Some(
+            self.subject
+                .as_ref()?
+                .to_lowercase()
+                .split_whitespace()
+                .filter(|word| word.chars().count() >= MIN_WORD_LEN)
+                .any(|word| subject.contains(word)),
+        )
+    }
+
+    pub fn matches_to(&self, email: &SendEmail) -> Option<bool> {
+        let to = self.to.as_ref()?;
+        Some(recipients(email).any(|mailbox| is_address(mailbox, to)))
+    }
+
+    pub fn matches_subject(&self, email: &SendEmail) -> Option<bool> {
+        let subject = self.subject.as_ref()?.to_lowercase();
+        Some(
+            email
+                .request
+                .get_rendered_subject()
+                .is_some_and(|s| s.to_lowercase().contains(&subject)),
+        )
+    }
+
+    // The criteria only the UI filters on, which `nearly_matches` leaves out.
+    fn matches_filters(&self, email: &SendEmail) -> bool {
+        let input = email.request.get_input();
+        if let Some(q) = &self.q {
+            let q = q.to_lowercase();
+            let subject = email.request.get_rendered_subject();
+            let found = subject
+                .as_deref()
+                .into_iter()
+                .chain(email.request.get_from())
+                .chain(recipients(email))
+                .any(|text| text.to_lowercase().contains(&q));
+            if !found {
+                return false;
+            }
+        }
+        if let Some(from) = &self.from {
+            if !email
+                .request
+                .get_from()
+                .is_some_and(|f| is_address(f, from))
+            {
+                return false;
+            }
+        }
+        if let Some(email_type) = &self.email_type {
+            if !email
+                .request
+                .get_tag()
+                .to_string()
+                .eq_ignore_ascii_case(email_type)
+            {
+                return false;
+            }
+        }
+        if let Some(configuration_set) = &self.configuration_set {
+            if input.configuration_set_name.as_ref() != Some(configuration_set) {
+                return false;
+            }
+        }
+        if let Some(tag) = &self.tag {
+            let (name, value) = match tag.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (tag.as_str(), None),
+            };
+            let tagged = input
+                .email_tags
+                .iter()
+                .flatten()
+                .any(|t| t.name == name && value.is_none_or(|value| t.value == value));
+            if !tagged {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl EventQuery {
+    pub fn matches(&self, event: &Event) -> bool {
+        if let Some(event_type) = &self.event_type {
+            if !event.get_name().eq_ignore_ascii_case(event_type) {
+                return false;
+            }
+        }
+        if let Some(message_id) = &self.message_id {
+            if event.get_message_id() != Some(message_id.as_str()) {
+                return false;
+            }
+        }
+        is_since(event, self.since.as_ref())
+    }
+}
+
+fn is_since(event: &Event, since: Option<&Timestamp>) -> bool {
+    match since {
+        Some(since) => event
+            .timestamp
+            .parse::<Timestamp>()
+            .is_ok_and(|ts| ts >= *since),
+        None => true,
+    }
+}
+
+fn is_until(event: &Event, until: Option<&Timestamp>) -> bool {
+    match until {
+        Some(until) => event
+            .timestamp
+            .parse::<Timestamp>()
+            .is_ok_and(|ts| ts <= *until),
+        None => true,
+    }
+}
+
+// Every To, Cc and Bcc mailbox, as written.
+fn recipients(email: &SendEmail) -> impl Iterator<Item = &str> {
+    email.request.get_to().into_iter().flat_map(|d| {
+        [&d.to_addresses, &d.cc_addresses, &d.bcc_addresses]
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(String::as_str)
+    })
+}
+
+// Either the address itself or a mailbox with a display name around it.
+fn is_address(mailbox: &str, address: &str) -> bool {
+    mailbox.eq_ignore_ascii_case(address)
+        || address::parse(mailbox)
+            .address
+            .eq_ignore_ascii_case(address)
+}
+
+fn non_empty<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    Ok(Option::<String>::deserialize(deserializer)?.filter(|s| !s.is_empty()))
+}
+
+// RFC 3339, or a date or `datetime-local` input's value taken as UTC.
+fn timestamp<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Timestamp>, D::Error> {
+    let Some(value) = non_empty(deserializer)? else {
+        return Ok(None);
+    };
+    if let Ok(ts) = value.parse::<Timestamp>() {
+        return Ok(Some(ts));
+    }
+    value
+        .parse::<civil::DateTime>()
+        .and_then(|dt| dt.to_zoned(TimeZone::UTC))
+        .map(|zoned| Some(zoned.timestamp()))
+        .map_err(D::Error::custom)
+}
+
+impl<T> Page<T> {
+    /// Builds a page from `(event, item)` pairs, newest first, resuming after the position held
+    /// in `cursor`. That's just after the cursor's event if it's still there, otherwise at the
+    /// first event older than it, so deleting or expiring it doesn't end the listing.
+    pub fn new<'a>(
+        items: impl Iterator<Item = (&'a Event, T)>,
+        cursor: Option<&str>,
+        limit: Option<usize>,
+    ) -> Result<Self, QueryError> {
+        let mut items = items.peekable();
+        let mut skipped = 0;
+        if let Some(cursor) = cursor {
+            let (timestamp, id) = parse_cursor(cursor).ok_or(QueryError::InvalidCursor)?;
+            while items
+                .next_if(|(ev, _)| ev.id != id && !is_older(ev, timestamp))
+                .is_some()
+            {
+                skipped += 1;
+            }
+            if items.next_if(|(ev, _)| ev.id == id).is_some() {
+                skipped += 1;
+            }
+        }
+        let mut page = Page {
+            items: vec![],
+            next_cursor: None,
+            total: 0,
+        };
+        let mut last = None;
+        for (ev, item) in items.by_ref().take(limit.unwrap_or(usize::MAX)) {
+            page.items.push(item);
+            last = Some(ev);
+        }
+        if items.peek().is_some() {
+            page.next_cursor = last.map(|ev| format!("{}_{}", ev.timestamp, ev.id));
+        }
+        page.total = skipped + page.items.len() + items.count();
+        Ok(page)
+    }
+}
+
+// A cursor is the `<timestamp>_<id>` of the last event on the previous page.
+fn parse_cursor(cursor: &str) -> Option<(Timestamp, &str)> {
+    let (timestamp, id) = cursor.split_once('_')?;
+    Some((timestamp.parse().ok()?, id))
+}
+
+fn is_older(event: &Event, timestamp: Timestamp) -> bool {
+    event
+        .timestamp
+        .parse::<Timestamp>()
+        .is_ok_and(|ts| ts < timestamp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EventContent;
+
+    fn create_email() -> (Event, SendEmail) {
+        let email = SendEmail::new(
+            serde_json::from_value(serde_json::json!({
+                "FromEmailAddress": "Sender <s@example.com>",
+                "Destination": { "ToAddresses": ["Ann <a@example.com>"] },
+                "Content": { "Simple": { "Subject": { "Data": "Welcome" } } },
+                "ConfigurationSetName": "staging",
+                "EmailTags": [{ "Name": "flow", "Value": "signup" }]
+            }))
+            .unwrap(),
+        );
+        let mut event = Event::new(EventContent::SendEmail(email.clone()));
+        event.timestamp = String::from("2024-05-01T12:00:00Z");
+        (event, email)
+    }
+
+    fn query(query: &str) -> EmailQuery {
+        serde_urlencoded::from_str(query).unwrap()
+    }
+
+    #[test]
+    fn filters() {
+        let (event, email) = create_email();
+        for matching in [
+            "",
+            "q=ANN&to=&type=",
+            "q=welc&subject=WELCOME",
+            "to=a@example.com&from=S@example.com",
+            "type=simple&configuration_set=staging",
+            "tag=flow&since=2024-05-01",
+            "tag=flow=signup&until=2024-05-01T12:00",
+            "since=2024-05-01T11:00:00Z&until=2024-05-01T12:00:00Z",
+        ] {
+            assert!(query(matching).matches(&event, &email), "{}", matching);
+        }
+        for missing in [
+            "q=zed",
+            "from=a@example.com",
+            "type=raw",
+            "configuration_set=prod",
+            "tag=flow=login",
+            "tag=signup",
+            "since=2024-05-02",
+            "until=2024-05-01T11:59",
+        ] {
+            assert!(!query(missing).matches(&event, &email), "{}", missing);
+        }
+        assert!(serde_urlencoded::from_str::<EmailQuery>("since=yesterday").is_err());
+    }
+
+    #[test]
+    fn near_misses() {
+        let (event, email) = create_email();
+        for near in [
+            "to=a@example.com&subject=reset",
+            "to=b@EXAMPLE.com",
+            "subject=welcome%20back",
+            "to=b@example.com&subject=a%20warm%20WELCOME",
+        ] {
+            assert!(query(near).nearly_matches(&event, &email), "{}", near);
+        }
+        for far in [
+            "",
+            "to=a@example.com",
+            "to=b@example.org",
+            "to=b",
+            "subject=reset%20password",
+            "subject=a%20to%20the",
+            "to=b@example.com&since=2024-05-02",
+        ] {
+            assert!(!query(far).nearly_matches(&event, &email), "{}", far);
+        }
+    }
+}
@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 // use aws_sdk_sesv2::types::AttachmentContentDisposition;
 // #[serde(remote = "AttachmentContentDisposition")]
-#[serde(rename_all = "PascalCase")]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum AttachmentContentDisposition {
     #[allow(missing_docs)] // documentation missing in model
     Attachment,
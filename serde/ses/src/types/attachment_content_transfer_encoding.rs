@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 // use aws_sdk_sesv2::types::AttachmentContentTransferEncoding;
 // #[serde(remote = "AttachmentContentTransferEncoding")]
-#[serde(rename_all = "PascalCase")]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum AttachmentContentTransferEncoding {
     #[allow(missing_docs)] // documentation missing in model
     Base64,